@@ -37,6 +37,7 @@ use termcolor::{BufferWriter,
                 WriteColor};
 
 static VERBOSITY: AtomicBool = AtomicBool::new(false);
+static FORCE_COLOR: AtomicBool = AtomicBool::new(false);
 
 lazy_static! {
     static ref FORMAT: Mutex<OutputFormat> = Mutex::new(OutputFormat::Color(ColorSpec::default()));
@@ -67,6 +68,9 @@ pub fn set_verbosity(format: OutputVerbosity) {
                     Ordering::Relaxed)
 }
 
+/// Force colored output to be rendered with ANSI codes, even when it is not written to a terminal
+pub fn set_force_color(force: bool) { FORCE_COLOR.store(force, Ordering::Relaxed) }
+
 /// Adds structure to printed output. Stores a preamble, a logkey, line, file, column, and content
 /// to print.
 pub struct StructuredOutput<'a> {
@@ -243,6 +247,7 @@ pub enum OutputFormat {
 impl OutputFormat {
     pub fn color_choice(&self) -> ColorChoice {
         match self {
+            OutputFormat::Color(_) if FORCE_COLOR.load(Ordering::Relaxed) => ColorChoice::Always,
             OutputFormat::Color(_) => ColorChoice::Auto,
             OutputFormat::NoColor | OutputFormat::JSON => ColorChoice::Never,
         }
@@ -1,5 +1,6 @@
 mod each_alive;
 mod pkg_path_for;
+mod secret;
 mod str_concat;
 mod str_join;
 mod str_replace;
@@ -11,6 +12,9 @@ mod to_yaml;
 
 pub use self::{each_alive::EACH_ALIVE,
                pkg_path_for::PKG_PATH_FOR,
+               secret::{resolve_via_command,
+                        set_secrets_provider,
+                        SECRET},
                str_concat::STR_CONCAT,
                str_join::STR_JOIN,
                str_replace::STR_REPLACE,
@@ -0,0 +1,163 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use std::{io::{Read,
+               Write},
+          process::{Command,
+                    Stdio},
+          sync::RwLock,
+          thread::{self,
+                   JoinHandle},
+          time::{Duration,
+                 Instant}};
+
+use super::super::RenderResult;
+
+lazy_static! {
+    static ref SECRETS_PROVIDER: RwLock<Option<String>> = RwLock::new(None);
+}
+
+habitat_core::env_config_duration!(SecretsProviderTimeout,
+                                   HAB_SECRETS_PROVIDER_TIMEOUT_SECS => from_secs,
+                                   Duration::from_secs(10));
+
+/// How often a running secrets provider is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Sets the command invoked to resolve the `secret` helper. Until this is called, templates
+/// using the helper fail to render.
+pub fn set_secrets_provider(command: String) {
+    *SECRETS_PROVIDER.write()
+                     .expect("SECRETS_PROVIDER lock poisoned") = Some(command);
+}
+
+/// Resolves a secret by running the given command with the secret's name on its stdin and
+/// taking its stdout, minus any trailing newline, as the secret's value.
+///
+/// Fails if the command cannot be run, exits unsuccessfully, or runs for longer than
+/// `SecretsProviderTimeout`, in which case the template is not rendered and any previously
+/// rendered file is left as it was.
+pub fn resolve_via_command(command: &str, name: &str) -> RenderResult<String> {
+    resolve_via_command_within(command,
+                               name,
+                               SecretsProviderTimeout::configured_value().into())
+}
+
+/// Resolves a secret by running the given command, killing it if it has not exited within the
+/// timeout.
+fn resolve_via_command_within(command: &str,
+                              name: &str,
+                              timeout: Duration)
+                              -> RenderResult<String> {
+    let failed = |reason: String| {
+        RenderError::new(format!("Secrets provider '{}' failed to resolve secret '{}': {}",
+                                 command, name, reason))
+    };
+    let mut child = Command::new(command).stdin(Stdio::piped())
+                                         .stdout(Stdio::piped())
+                                         .stderr(Stdio::piped())
+                                         .spawn()
+                                         .map_err(|e| failed(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(name.as_bytes())
+             .map_err(|e| failed(e.to_string()))?;
+    }
+    // The output is read on separate threads so that a provider which fills a pipe can't block
+    // while it is waited on.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait().map_err(|e| failed(e.to_string()))? {
+            Some(status) => break status,
+            None if started.elapsed() >= timeout => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(failed(format!("timed out after {:?}", timeout)));
+            }
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(failed(format!("{}: {}", status, stderr.trim())));
+    }
+    let value = String::from_utf8(stdout).map_err(|e| failed(e.to_string()))?;
+    Ok(value.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Reads a stream to its end on a new thread, returning what was read when the thread is joined.
+fn read_in_background<R>(stream: Option<R>) -> JoinHandle<Vec<u8>>
+    where R: Read + Send + 'static
+{
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut stream) = stream {
+            stream.read_to_end(&mut buf).ok();
+        }
+        buf
+    })
+}
+
+#[derive(Clone, Copy)]
+pub struct SecretHelper;
+
+impl HelperDef for SecretHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let name = h.param(0)
+                    .and_then(|v| v.value().as_str())
+                    .ok_or_else(|| RenderError::new("Expected a string parameter for \"secret\""))?;
+        let provider = SECRETS_PROVIDER.read()
+                                       .expect("SECRETS_PROVIDER lock poisoned");
+        let command = provider.as_ref().ok_or_else(|| {
+                                            RenderError::new("No secrets provider is configured \
+                                                              for \"secret\"; use \
+                                                              --secrets-provider")
+                                        })?;
+        let value = resolve_via_command(command, name)?;
+        rc.writer.write_all(value.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SECRET: SecretHelper = SecretHelper;
+
+#[cfg(all(test, not(windows)))]
+mod test {
+    use super::*;
+    use std::{fs,
+              os::unix::fs::PermissionsExt};
+
+    #[test]
+    fn resolves_secret_from_command_stdout() {
+        // `cat` echoes the secret name it is given on stdin
+        assert_eq!("db_password",
+                   resolve_via_command("cat", "db_password").unwrap());
+    }
+
+    #[test]
+    fn failing_command_is_a_render_error() {
+        assert!(resolve_via_command("false", "db_password").is_err());
+        assert!(resolve_via_command("/no/such/provider", "db_password").is_err());
+    }
+
+    #[test]
+    fn hung_command_is_killed_after_the_timeout() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let provider = dir.path().join("provider");
+        fs::write(&provider, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        fs::set_permissions(&provider, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let started = Instant::now();
+        let err = resolve_via_command_within(provider.to_str().unwrap(),
+                                             "db_password",
+                                             Duration::from_millis(100)).unwrap_err();
+
+        assert!(err.desc.contains("timed out"), "{}", err.desc);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -51,15 +51,44 @@ impl Into<HashMap<String, String>> for EventStreamMetadata {
 impl EventStreamMetadata {
     /// The name of the Clap argument we'll use for arguments of this type.
     pub const ARG_NAME: &'static str = "EVENT_STREAM_METADATA";
+    /// Keys which collide with the fields every event already
+    /// carries. Setting them as metadata would silently corrupt
+    /// downstream analytics, so they are rejected.
+    pub const RESERVED_KEYS: &'static [&'static str] = &["application",
+                                                         "environment",
+                                                         "fqdn",
+                                                         "ip_address",
+                                                         "occurred_at",
+                                                         "site",
+                                                         "supervisor_id"];
 
     /// Ensure that user input from Clap can be converted into a
-    /// key-value pair we can consume.
+    /// key-value pair we can consume, and that its key isn't one of the
+    /// `RESERVED_KEYS`.
     ///
     /// Note: this validates each value given by the user, not all the
     /// values given at once.
     #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
     pub fn validate(value: String) -> result::Result<(), String> {
-        Self::split_raw(&value).map(|_| ())
+        Self::validate_with_reserved_keys(&value, Self::RESERVED_KEYS)
+    }
+
+    /// Same as `validate`, but rejecting the given set of reserved
+    /// keys instead.
+    pub fn validate_with_reserved_keys(value: &str,
+                                       reserved_keys: &[&str])
+                                       -> result::Result<(), String> {
+        let (key, _) = Self::split_raw(value)?;
+        if reserved_keys.contains(&key.as_str()) {
+            Err(format!("Invalid key-value pair given (the key '{}' is \
+                         reserved for a built-in event field; reserved \
+                         keys are: {}): {}",
+                        key,
+                        reserved_keys.join(", "),
+                        value))
+        } else {
+            Ok(())
+        }
     }
 
     /// Utility function to create a key-value pair tuple from a
@@ -347,6 +376,34 @@ mod tests {
         fn cannot_parse_from_empty_string() { assert!("".parse::<AutomateAuthToken>().is_err()) }
     }
 
+    mod event_stream_metadata {
+        use super::*;
+
+        #[test]
+        fn normal_keys_are_valid() {
+            assert!(EventStreamMetadata::validate(String::from("team=platform")).is_ok());
+            assert!(EventStreamMetadata::validate(String::from("app=billing")).is_ok());
+        }
+
+        #[test]
+        fn reserved_keys_are_rejected() {
+            for key in EventStreamMetadata::RESERVED_KEYS {
+                let err = EventStreamMetadata::validate(format!("{}=value", key)).unwrap_err();
+                assert!(err.contains(&format!("the key '{}' is reserved", key)),
+                        "{}",
+                        err);
+            }
+        }
+
+        #[test]
+        fn reserved_keys_are_configurable() {
+            assert!(EventStreamMetadata::validate_with_reserved_keys("application=billing",
+                                                                     &[]).is_ok());
+            assert!(EventStreamMetadata::validate_with_reserved_keys("team=platform",
+                                                                     &["team"]).is_err());
+        }
+    }
+
     mod gossip_listen_addr {
         use super::*;
         #[test]
@@ -367,10 +367,7 @@ pub trait UIWriter {
     fn warn<T>(&mut self, message: T) -> io::Result<()>
         where T: fmt::Display
     {
-        println(self.err(),
-                format!("{} {}", Glyph::SlashedZero.to_str(), message).as_bytes(),
-                ColorSpec::new().set_fg(Some(Color::Warn.into()))
-                                .set_bold(true))
+        println_warn(self.err(), message)
     }
 
     /// Write a message formatted with `fatal`.
@@ -429,12 +426,25 @@ pub trait UIWriter {
 /// Console (shell) backed UI.
 #[derive(Debug)]
 pub struct UI {
-    shell: Shell,
+    shell:    Shell,
+    /// The warnings written since recording started, if they are being recorded.
+    warnings: Option<Vec<String>>,
 }
 
 impl UI {
     /// Creates a new `UI` from a `Shell`.
-    pub fn new(shell: Shell) -> Self { UI { shell } }
+    pub fn new(shell: Shell) -> Self {
+        UI { shell,
+             warnings: None }
+    }
+
+    /// Records every warning written from now on, so that they can be reviewed once an operation
+    /// completes.
+    pub fn record_warnings(&mut self) { self.warnings.get_or_insert_with(Vec::new); }
+
+    /// Returns the warnings written since `record_warnings` was called, which is empty if they
+    /// are not being recorded.
+    pub fn warnings(&self) -> &[String] { self.warnings.as_deref().unwrap_or_default() }
 
     /// Creates a new default `UI` with a coloring strategy and tty hinting.
     pub fn default_with(coloring: ColorChoice, isatty: Option<bool>) -> Self {
@@ -513,6 +523,16 @@ impl UIWriter for UI {
 
     fn is_err_a_terminal(&self) -> bool { self.shell.err.is_a_terminal() }
 
+    fn warn<T>(&mut self, message: T) -> io::Result<()>
+        where T: fmt::Display
+    {
+        let message = message.to_string();
+        if let Some(ref mut warnings) = self.warnings {
+            warnings.push(message.clone());
+        }
+        println_warn(self.err(), message)
+    }
+
     fn progress(&self) -> Option<Box<dyn DisplayProgress>> {
         if self.is_out_a_terminal() {
             Some(Box::new(Self::ProgressBar::default()))
@@ -981,3 +1001,12 @@ pub fn println(writer: &mut dyn WriteColor, buf: &[u8], color_spec: &ColorSpec)
     writer.write_all(b"\n")?;
     writer.flush()
 }
+
+fn println_warn<T>(writer: &mut dyn WriteColor, message: T) -> io::Result<()>
+    where T: fmt::Display
+{
+    println(writer,
+            format!("{} {}", Glyph::SlashedZero.to_str(), message).as_bytes(),
+            ColorSpec::new().set_fg(Some(Color::Warn.into()))
+                            .set_bold(true))
+}
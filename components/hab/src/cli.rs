@@ -4,7 +4,8 @@ use crate::{cli::hab::{sup::{ConfigOptSup,
                              Sup},
                        ConfigOptHab,
                        Hab},
-            command::studio};
+            command::studio,
+            CTL_CA_CERT_ENVVAR};
 
 use clap::{App,
            AppSettings,
@@ -132,6 +133,10 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                     "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
                 (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
                     "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+                (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+                    "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+                     serves TLS. Its certificate must be issued for the host name given with \
+                     --remote-sup, or for localhost if an IP address or no address is given")
             )
         )
         (@subcommand file =>
@@ -150,6 +155,10 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                 (@arg USER: -u --user +takes_value "Name of the user key")
                 (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
                     "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+                (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+                    "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+                     serves TLS. Its certificate must be issued for the host name given with \
+                     --remote-sup, or for localhost if an IP address or no address is given")
                 (arg: arg_cache_key_path())
             )
         )
@@ -1139,6 +1148,10 @@ fn sub_config_apply() -> App<'static, 'static> {
     (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
     (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
         "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+        "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+         serves TLS. Its certificate must be issued for the host name given with \
+         --remote-sup, or for localhost if an IP address or no address is given")
     (arg: arg_cache_key_path())
     )
 }
@@ -1153,6 +1166,10 @@ fn sub_sup_depart() -> App<'static, 'static> {
         (@arg MEMBER_ID: +required +takes_value "The member-id of the Supervisor to depart")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
     )
 }
 
@@ -1184,11 +1201,11 @@ fn sub_sup_run(_feature_flags: FeatureFlag) -> App<'static, 'static> {
                             // is displayed confusingly as `hab-sup`
                             // see: https://github.com/kbknapp/clap-rs/blob/2724ec5399c500b12a1a24d356f4090f4816f5e2/src/app/mod.rs#L373-L394
                             (usage: "hab sup run [FLAGS] [OPTIONS] [--] [PKG_IDENT_OR_ARTIFACT]")
-                            (@arg LISTEN_GOSSIP: --("listen-gossip") env(GossipListenAddr::ENVVAR) default_value(GossipListenAddr::default_as_str()) {valid_socket_addr}
+                            (@arg LISTEN_GOSSIP: --("listen-gossip") env(GossipListenAddr::ENVVAR) default_value(GossipListenAddr::default_as_str()) {valid_listen_addr}
                              "The listen address for the Gossip System Gateway")
-                            (@arg LOCAL_GOSSIP_MODE: --("local-gossip-mode") conflicts_with("LISTEN_GOSSIP") conflicts_with("PEER") conflicts_with("PEER_WATCH_FILE")
+                            (@arg LOCAL_GOSSIP_MODE: --("local-gossip-mode") conflicts_with("LISTEN_GOSSIP") conflicts_with("PEER") conflicts_with("PEER_WATCH_FILE") conflicts_with("PEER_DNS")
                              "Start the supervisor in local mode")
-                            (@arg LISTEN_HTTP: --("listen-http") env(HttpListenAddr::ENVVAR) default_value(HttpListenAddr::default_as_str()) {valid_socket_addr}
+                            (@arg LISTEN_HTTP: --("listen-http") env(HttpListenAddr::ENVVAR) default_value(HttpListenAddr::default_as_str()) {valid_listen_addr}
                              "The listen address for the HTTP Gateway")
                             (@arg HTTP_DISABLE: --("http-disable") -D
                              "Disable the HTTP Gateway completely")
@@ -1203,6 +1220,10 @@ fn sub_sup_run(_feature_flags: FeatureFlag) -> App<'static, 'static> {
                             (@arg PEER_WATCH_FILE: --("peer-watch-file") +takes_value conflicts_with("PEER")
                              "Watch this file for connecting to the ring"
                             )
+                            (@arg PEER_DNS: --("peer-dns") +takes_value {non_empty}
+                             "A name whose DNS SRV records give the listen addresses of peers. The records are \
+                              resolved at startup and periodically thereafter, and their peers replace those of \
+                              --peer once resolved (ex: _hab-gossip._tcp.example.com)")
                             (arg: arg_cache_key_path())
                             (@arg RING: --ring -r env(RING_ENVVAR) conflicts_with("RING_KEY") {non_empty}
                              "The name of the ring used by the Supervisor when running with wire encryption. \
@@ -1218,10 +1239,22 @@ fn sub_sup_run(_feature_flags: FeatureFlag) -> App<'static, 'static> {
                               be taken from the HAB_BLDR_URL environment variable if defined. (default: \
                               https://bldr.habitat.sh)")
 
-                            (@arg CONFIG_DIR: --("config-from") +takes_value {dir_exists}
+                            (@arg CONFIG_DIR: --("config-from") +takes_value {valid_config_dir}
                              "Use package config from this path, rather than the package itself")
-                            (@arg AUTO_UPDATE: --("auto-update") -A "Enable automatic updates for the Supervisor \
-                                                                     itself")
+                            (@arg AUTO_UPDATE: --("auto-update") -A
+                             "Enable automatic updates for the Supervisor itself. If neither this nor \
+                              --no-auto-update is specified, the value will be taken from the \
+                              HAB_AUTO_UPDATE environment variable if defined")
+                            (@arg NO_AUTO_UPDATE: --("no-auto-update") conflicts_with("AUTO_UPDATE")
+                             "Disable automatic updates for the Supervisor itself, overriding the \
+                              HAB_AUTO_UPDATE environment variable")
+                            (@arg AUTO_UPDATE_CHANNEL: --("auto-update-channel") +takes_value {non_empty}
+                             "Receive automatic updates for the Supervisor itself from the specified \
+                              release channel. If not specified, the value of --channel is used")
+                            (@arg AUTO_UPDATE_PERIOD: --("auto-update-period") +takes_value {valid_positive_seconds}
+                             "The interval (seconds) on which to check for automatic updates for the \
+                              Supervisor itself. If not specified, the value will be taken from the \
+                              HAB_SUP_UPDATE_MS environment variable if defined [default: 60]")
                             (@arg KEY_FILE: --key +takes_value {file_exists} requires[CERT_FILE]
                              "Used for enabling TLS for the HTTP gateway. Read private key from KEY_FILE. \
                               This should be a RSA private key or PKCS8-encoded private key, in PEM format")
@@ -1232,6 +1265,13 @@ fn sub_sup_run(_feature_flags: FeatureFlag) -> App<'static, 'static> {
                             (@arg CA_CERT_FILE: --("ca-certs") +takes_value {file_exists} requires[CERT_FILE] requires[KEY_FILE]
                              "Used for enabling client-authentication with TLS for the HTTP gateway. Read CA certificate from CA_CERT_FILE. \
                               This should contain PEM-format certificate that can be used to validate client requests")
+                            (@arg LISTEN_CTL_KEY_FILE: --("listen-ctl-key") +takes_value {file_exists} requires[LISTEN_CTL_CERT_FILE]
+                             "Used for enabling TLS for the Control Gateway. Read private key from LISTEN_CTL_KEY_FILE. \
+                              This should be a RSA private key or PKCS8-encoded private key, in PEM format")
+                            (@arg LISTEN_CTL_CERT_FILE: --("listen-ctl-cert") +takes_value {file_exists} requires[LISTEN_CTL_KEY_FILE]
+                             "Used for enabling TLS for the Control Gateway. Read server certificates from \
+                              LISTEN_CTL_CERT_FILE. This should contain PEM-format certificates in the right order \
+                              (the first certificate should certify LISTEN_CTL_KEY_FILE, the last should be a root CA)")
                             // === Optional arguments to additionally load an initial service for the Supervisor
                             (@arg PKG_IDENT_OR_ARTIFACT: +takes_value "Load the given Habitat package as part of \
                                                                        the Supervisor startup specified by a package identifier \
@@ -1249,20 +1289,34 @@ fn sub_sup_run(_feature_flags: FeatureFlag) -> App<'static, 'static> {
                              "The update strategy; [default: none] [values: none, at-once, rolling]")
                             (@arg BIND: --bind +takes_value +multiple
                              "One or more service groups to bind to a configuration")
+                            (@arg BIND_FILE: --("bind-file") +takes_value {file_exists}
+                             "A file of service groups to bind to a configuration, one per line. \
+                              Combined with any --bind values")
                             (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
                              "Governs how the presence or absence of binds affects service startup. `strict` blocks \
                               startup until all binds are present. [default: strict] [values: relaxed, strict]")
                             (@arg VERBOSE: -v "Verbose output; shows file and line/column numbers")
                             (@arg NO_COLOR: --("no-color") "Turn ANSI color off")
+                            (@arg FORCE_COLOR: --("force-color") conflicts_with[NO_COLOR JSON]
+                             "Turn ANSI color on, even when output is not written to a terminal")
                             (@arg JSON: --("json-logging") "Use structured JSON logging for the Supervisor. \
                                                             Implies NO_COLOR")
                             (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
                              "The interval (seconds) on which to run health checks [default: 30]")
+                            (@arg DEFAULT_HEALTH_CHECK_INTERVAL: --("default-health-check-interval") +takes_value {valid_health_check_interval}
+                             "The interval (seconds) on which to run health checks for services \
+                              loaded without their own interval [default: 30]")
                             (@arg SYS_IP_ADDRESS: --("sys-ip-address") +takes_value {valid_ipv4_address}
                              "The IPv4 address to use as the `sys.ip` template variable. If this \
                              argument is not set, the supervisor tries to dynamically determine \
                              an IP address. If that fails, the supervisor defaults to using \
                              `127.0.0.1`")
+                            (@arg SECRETS_PROVIDER: --("secrets-provider") +takes_value {non_empty}
+                             "A command which resolves the `secret` template helper. The command is \
+                              given the secret's name on stdin and must print its value on stdout. \
+                              A template fails to render if the command exits unsuccessfully, or \
+                              is still running after HAB_SECRETS_PROVIDER_TIMEOUT_SECS (default: \
+                              10)")
     );
 
     // clap_app macro does not allow setting short and long help seperately
@@ -1326,6 +1380,10 @@ fn sub_svc_start() -> App<'static, 'static> {
             "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
     )
 }
 
@@ -1337,6 +1395,15 @@ fn sub_svc_status() -> App<'static, 'static> {
         (@arg PKG_IDENT: +takes_value {valid_ident} "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
         "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
+        (@arg WATCH: --watch -w +takes_value min_values(0) max_values(1) value_name("INTERVAL")
+            {valid_positive_seconds}
+            "Refresh the status every INTERVAL seconds until interrupted [default: 2]")
+        (@arg SINCE: --since +takes_value value_name("SECONDS") {valid_positive_seconds}
+            "Only show services whose state changed within the last SECONDS seconds")
     )
 }
 
@@ -1353,6 +1420,10 @@ fn sub_svc_stop() -> App<'static, 'static> {
             "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
     );
     add_shutdown_timeout_option(sub)
 }
@@ -1389,6 +1460,10 @@ fn sub_svc_load() -> App<'static, 'static> {
             was previously loaded and running this operation will also restart the service")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
         (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
             "The interval (seconds) on which to run health checks [default: 30]")
     );
@@ -1419,6 +1494,10 @@ fn sub_svc_unload() -> App<'static, 'static> {
             "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
         (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        (@arg CTL_CA_CERT: --("ctl-ca-cert") +takes_value {file_exists} env(CTL_CA_CERT_ENVVAR)
+            "Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which \
+             serves TLS. Its certificate must be issued for the host name given with \
+             --remote-sup, or for localhost if an IP address or no address is given")
     );
     add_shutdown_timeout_option(sub)
 }
@@ -1538,6 +1617,25 @@ fn dir_exists(val: String) -> result::Result<(), String> {
     }
 }
 
+/// A `--config-from` directory must exist and have the layout of a package, with a
+/// `default.toml` file or a `config` or `hooks` directory, since those are the only things the
+/// Supervisor reads from it.
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_config_dir(val: String) -> result::Result<(), String> {
+    dir_exists(val.clone())?;
+    let dir = Path::new(&val);
+    if dir.join("default.toml").is_file()
+       || dir.join("config").is_dir()
+       || dir.join("hooks").is_dir()
+    {
+        Ok(())
+    } else {
+        Err(format!("Directory: '{}' does not contain a default.toml \
+                     file or a config or hooks directory",
+                    &val))
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn file_exists(val: String) -> result::Result<(), String> {
     if Path::new(&val).is_file() {
@@ -1577,6 +1675,21 @@ fn valid_socket_addr(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_listen_addr(val: String) -> result::Result<(), String> {
+    if SocketAddr::from_str(&val).is_ok() {
+        return Ok(());
+    }
+    match val.rfind(':') {
+        Some(i) if i > 0 && val[i + 1..].parse::<u16>().is_ok() => Ok(()),
+        _ => {
+            Err(String::from("Listen address should include both a host \
+                              or IP and a port, eg: '0.0.0.0:9631' or \
+                              'localhost:9631'"))
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_url(val: String) -> result::Result<(), String> {
     match Url::parse(&val) {
@@ -1593,6 +1706,15 @@ fn valid_numeric<T: FromStr>(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_positive_seconds(val: String) -> result::Result<(), String> {
+    match val.parse::<u64>() {
+        Ok(0) => Err("the number of seconds must be greater than zero".to_string()),
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("'{}' is not a valid number of seconds", &val)),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_health_check_interval(val: String) -> result::Result<(), String> {
     match HealthCheckInterval::from_str(&val) {
@@ -1667,7 +1789,7 @@ fn valid_target(val: String) -> result::Result<(), String> {
 }
 
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
-fn valid_fully_qualified_ident(val: String) -> result::Result<(), String> {
+pub fn valid_fully_qualified_ident(val: String) -> result::Result<(), String> {
     match PackageIdent::from_str(&val) {
         Ok(ref ident) if ident.fully_qualified() => Ok(()),
         _ => {
@@ -1761,6 +1883,45 @@ mod tests {
         assert!(r.is_ok());
     }
 
+    mod config_dir {
+        use super::*;
+        use std::fs;
+        use tempfile::TempDir;
+
+        fn path_str(path: &Path) -> String { path.to_str().unwrap().to_string() }
+
+        #[test]
+        fn config_dir_with_default_toml_config_or_hooks_is_valid() {
+            let default_toml = TempDir::new().unwrap();
+            fs::write(default_toml.path().join("default.toml"), "port = 6379\n").unwrap();
+            let config = TempDir::new().unwrap();
+            fs::create_dir(config.path().join("config")).unwrap();
+            let hooks = TempDir::new().unwrap();
+            fs::create_dir(hooks.path().join("hooks")).unwrap();
+
+            assert!(valid_config_dir(path_str(default_toml.path())).is_ok());
+            assert!(valid_config_dir(path_str(config.path())).is_ok());
+            assert!(valid_config_dir(path_str(hooks.path())).is_ok());
+        }
+
+        #[test]
+        fn missing_config_dir_is_invalid() {
+            let tmp = TempDir::new().unwrap();
+
+            assert!(valid_config_dir(path_str(&tmp.path().join("missing"))).is_err());
+        }
+
+        #[test]
+        fn empty_config_dir_is_invalid() {
+            let tmp = TempDir::new().unwrap();
+
+            let err = valid_config_dir(path_str(tmp.path())).unwrap_err();
+            assert!(err.contains("does not contain a default.toml file"),
+                    "{}",
+                    err);
+        }
+    }
+
     mod sup_commands {
 
         use super::*;
@@ -1789,6 +1950,238 @@ mod tests {
             let run_matches = run_matches.expect("Error while getting run matches");
             assert_eq!(run_matches.value_of("PEER"), Some("1.1.1.1"));
         }
+
+        #[test]
+        fn sup_subcommand_status_with_optional_watch_interval() {
+            let status_matches = |args: Vec<&str>| {
+                let matches = get(no_feature_flags()).get_matches_from_safe(args)
+                                                     .expect("Error while getting matches");
+                let sup_matches = matches.subcommand_matches("sup")
+                                         .expect("Error while getting sup matches")
+                                         .clone();
+                sup_matches.subcommand_matches("status")
+                           .expect("Error while getting status matches")
+                           .clone()
+            };
+
+            let m = status_matches(vec!["hab", "sup", "status"]);
+            assert!(!m.is_present("WATCH"));
+
+            let m = status_matches(vec!["hab", "sup", "status", "--watch"]);
+            assert!(m.is_present("WATCH"));
+            assert_eq!(m.value_of("WATCH"), None);
+
+            let m = status_matches(vec!["hab", "sup", "status", "core/redis", "--watch", "5"]);
+            assert_eq!(m.value_of("PKG_IDENT"), Some("core/redis"));
+            assert_eq!(m.value_of("WATCH"), Some("5"));
+
+            for interval in &["0", "soon"] {
+                let args = vec!["hab", "sup", "status", "--watch", interval];
+                let r = get(no_feature_flags()).get_matches_from_safe(args);
+                assert_eq!(r.unwrap_err().kind, ErrorKind::ValueValidation);
+            }
+        }
+
+        #[test]
+        fn sup_subcommand_status_with_since() {
+            let matches = get(no_feature_flags()).get_matches_from_safe(vec!["hab", "sup",
+                                                                             "status", "--since",
+                                                                             "300"])
+                                                 .expect("Error while getting matches");
+            let status_matches = matches.subcommand_matches("sup")
+                                        .and_then(|m| m.subcommand_matches("status"))
+                                        .expect("Error while getting status matches");
+            assert_eq!(status_matches.value_of("SINCE"), Some("300"));
+
+            for since in &["0", "-1", "5m"] {
+                let args = vec!["hab", "sup", "status", "--since", since];
+                let r = get(no_feature_flags()).get_matches_from_safe(args);
+                assert_eq!(r.unwrap_err().kind, ErrorKind::ValueValidation);
+            }
+        }
+    }
+
+    mod sup_run_color {
+        use super::*;
+        use clap::ErrorKind;
+
+        #[test]
+        fn force_color_is_accepted() {
+            let args = vec!["run", "--force-color"];
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(args)
+                                                         .expect("Error while getting matches");
+            assert!(matches.is_present("FORCE_COLOR"));
+        }
+
+        #[test]
+        fn force_color_conflicts_with_no_color_and_json_logging() {
+            for flag in &["--no-color", "--json-logging"] {
+                let args = vec!["run", "--force-color", *flag];
+                let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+                assert_eq!(r.unwrap_err().kind, ErrorKind::ArgumentConflict);
+            }
+        }
+    }
+
+    mod sup_run_peer_dns {
+        use super::*;
+        use clap::ErrorKind;
+
+        #[test]
+        fn peer_dns_composes_with_peers() {
+            let args = vec!["run",
+                            "--peer-dns",
+                            "_hab-gossip._tcp.example.com",
+                            "--peer",
+                            "1.1.1.1"];
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(args)
+                                                         .expect("Error while getting matches");
+            assert_eq!(matches.value_of("PEER_DNS"),
+                       Some("_hab-gossip._tcp.example.com"));
+            assert_eq!(matches.value_of("PEER"), Some("1.1.1.1"));
+        }
+
+        #[test]
+        fn peer_dns_conflicts_with_local_gossip_mode() {
+            let args = vec!["run",
+                            "--peer-dns",
+                            "_hab-gossip._tcp.example.com",
+                            "--local-gossip-mode"];
+            let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+            assert_eq!(r.unwrap_err().kind, ErrorKind::ArgumentConflict);
+        }
+    }
+
+    mod sup_run_default_health_check_interval {
+        use super::*;
+        use clap::ErrorKind;
+
+        #[test]
+        fn default_health_check_interval_is_accepted() {
+            let args = vec!["run", "--default-health-check-interval", "10"];
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(args)
+                                                         .expect("Error while getting matches");
+            assert_eq!(matches.value_of("DEFAULT_HEALTH_CHECK_INTERVAL"),
+                       Some("10"));
+        }
+
+        #[test]
+        fn default_health_check_interval_must_be_a_number_of_seconds() {
+            for interval in &["-1", "10s", "soon"] {
+                let args = vec!["run", "--default-health-check-interval", *interval];
+                let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+                assert_eq!(r.unwrap_err().kind, ErrorKind::ValueValidation);
+            }
+        }
+    }
+
+    mod sup_run_listen_ctl_tls {
+        use super::*;
+        use clap::ErrorKind;
+        use std::fs;
+        use tempfile::TempDir;
+
+        #[test]
+        fn listen_ctl_key_and_cert_are_accepted_together() {
+            let tmp = TempDir::new().unwrap();
+            let key = tmp.path().join("ctl.key");
+            let cert = tmp.path().join("ctl.crt");
+            fs::write(&key, "").unwrap();
+            fs::write(&cert, "").unwrap();
+            let args = vec!["run",
+                            "--listen-ctl-key",
+                            key.to_str().unwrap(),
+                            "--listen-ctl-cert",
+                            cert.to_str().unwrap()];
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(args)
+                                                         .expect("Error while getting matches");
+            assert_eq!(matches.value_of("LISTEN_CTL_KEY_FILE"), key.to_str());
+            assert_eq!(matches.value_of("LISTEN_CTL_CERT_FILE"), cert.to_str());
+        }
+
+        #[test]
+        fn listen_ctl_key_and_cert_require_each_other() {
+            let tmp = TempDir::new().unwrap();
+            let file = tmp.path().join("ctl.pem");
+            fs::write(&file, "").unwrap();
+            for flag in &["--listen-ctl-key", "--listen-ctl-cert"] {
+                let args = vec!["run", *flag, file.to_str().unwrap()];
+                let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+                assert_eq!(r.unwrap_err().kind, ErrorKind::MissingRequiredArgument);
+            }
+        }
+
+        #[test]
+        fn listen_ctl_key_and_cert_must_exist() {
+            let args = vec!["run",
+                            "--listen-ctl-key",
+                            "/no/such/ctl.key",
+                            "--listen-ctl-cert",
+                            "/no/such/ctl.crt"];
+            let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+            assert_eq!(r.unwrap_err().kind, ErrorKind::ValueValidation);
+        }
+    }
+
+    mod ctl_ca_cert {
+        use super::*;
+        use clap::ErrorKind;
+        use std::fs;
+        use tempfile::TempDir;
+
+        #[test]
+        fn ctl_ca_cert_is_accepted_by_ctl_client_commands() {
+            let tmp = TempDir::new().unwrap();
+            let ca_cert = tmp.path().join("ca.crt");
+            fs::write(&ca_cert, "").unwrap();
+            let ca_cert = ca_cert.to_str().unwrap();
+            for command in &[vec!["hab", "svc", "load", "core/redis"],
+                             vec!["hab", "svc", "unload", "core/redis"],
+                             vec!["hab", "svc", "start", "core/redis"],
+                             vec!["hab", "svc", "stop", "core/redis"],
+                             vec!["hab", "svc", "status"],
+                             vec!["hab", "sup", "depart", "member-id"]]
+            {
+                let mut args = command.clone();
+                args.extend(&["--ctl-ca-cert", ca_cert]);
+                let matches = get(no_feature_flags()).get_matches_from_safe(args)
+                                                     .expect("Error while getting matches");
+                let (_, m) = matches.subcommand();
+                let (_, m) = m.unwrap().subcommand();
+                assert_eq!(m.unwrap().value_of("CTL_CA_CERT"), Some(ca_cert));
+            }
+        }
+
+        #[test]
+        fn ctl_ca_cert_must_exist() {
+            let args = vec!["hab", "svc", "status", "--ctl-ca-cert", "/no/such/ca.crt"];
+            let r = get(no_feature_flags()).get_matches_from_safe(args);
+            assert_eq!(r.unwrap_err().kind, ErrorKind::ValueValidation);
+        }
+    }
+
+    mod sup_run_secrets_provider {
+        use super::*;
+        use clap::ErrorKind;
+
+        #[test]
+        fn secrets_provider_is_accepted() {
+            let args = vec!["run", "--secrets-provider", "/bin/fetch-secret"];
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(args)
+                                                         .expect("Error while getting matches");
+            assert_eq!(matches.value_of("SECRETS_PROVIDER"),
+                       Some("/bin/fetch-secret"));
+        }
+
+        #[test]
+        fn secrets_provider_requires_a_command() {
+            for args in &[vec!["run", "--secrets-provider"],
+                          vec!["run", "--secrets-provider", ""]]
+            {
+                let r = sub_sup_run(no_feature_flags()).get_matches_from_safe(args);
+                assert!(r.is_err());
+            }
+        }
     }
 
     mod event_stream_feature {
@@ -1996,6 +2389,25 @@ mod tests {
             assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::ValueValidation);
         }
 
+        #[test]
+        fn event_meta_key_cannot_be_reserved() {
+            let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(vec![
+                "run",
+                "--event-meta",
+                "environment=staging",
+                "--event-stream-application",
+                "MY_APP",
+                "--event-stream-environment",
+                "MY_ENV",
+                "--event-stream-token",
+                "MY_TOKEN",
+                "--event-stream-url",
+                "127.0.0.1:4222",
+            ]);
+            assert!(matches.is_err());
+            assert_eq!(matches.unwrap_err().kind, clap::ErrorKind::ValueValidation);
+        }
+
         #[test]
         fn event_meta_value_cannot_be_empty() {
             let matches = sub_sup_run(no_feature_flags()).get_matches_from_safe(vec![
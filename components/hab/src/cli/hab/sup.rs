@@ -19,6 +19,7 @@ use habitat_common::{cli::{RING_ENVVAR,
                              ListenCtlAddr}};
 use habitat_core::{env::Config,
                    package::PackageIdent,
+                   service::HealthCheckInterval,
                    util::serde_string};
 use rants::{error::Error as RantsError,
             Address as NatsAddress};
@@ -69,6 +70,12 @@ pub enum Sup {
         pkg_ident:  Option<PackageIdent>,
         #[structopt(flatten)]
         remote_sup: RemoteSup,
+        /// Refresh the status every INTERVAL seconds until interrupted [default: 2]
+        #[structopt(name = "WATCH", long = "watch", short = "w", value_name = "INTERVAL")]
+        watch:      Option<Option<u64>>,
+        /// Only show services whose state changed within the last SECONDS seconds
+        #[structopt(name = "SINCE", long = "since", value_name = "SECONDS")]
+        since:      Option<u64>,
     },
     /// Gracefully terminate the Habitat Supervisor and all of its running services
     #[structopt(usage = "hab sup term [OPTIONS]", no_version)]
@@ -109,18 +116,18 @@ pub struct SupRun {
                 long = "listen-gossip",
                 env = GossipListenAddr::ENVVAR,
                 default_value = GossipListenAddr::default_as_str())]
-    listen_gossip: SocketAddr,
+    listen_gossip: String,
     /// Start the supervisor in local mode
     #[structopt(name = "LOCAL_GOSSIP_MODE",
                 long = "local-gossip-mode",
-                conflicts_with_all = &["LISTEN_GOSSIP", "PEER", "PEER_WATCH_FILE"])]
+                conflicts_with_all = &["LISTEN_GOSSIP", "PEER", "PEER_WATCH_FILE", "PEER_DNS"])]
     local_gossip_mode: bool,
     /// The listen address for the HTTP Gateway
     #[structopt(name = "LISTEN_HTTP",
                 long = "listen-http",
                 env = HttpListenAddr::ENVVAR,
                 default_value = HttpListenAddr::default_as_str())]
-    listen_http: SocketAddr,
+    listen_http: String,
     /// Disable the HTTP Gateway completely
     #[structopt(name = "HTTP_DISABLE", long = "http-disable", short = "D")]
     http_disable: bool,
@@ -147,6 +154,11 @@ pub struct SupRun {
                 long = "peer-watch-file",
                 conflicts_with = "PEER")]
     peer_watch_file: Option<PathBuf>,
+    /// A name whose DNS SRV records give the listen addresses of peers. The records are resolved
+    /// at startup and periodically thereafter, and their peers replace those of --peer once
+    /// resolved (ex: _hab-gossip._tcp.example.com)
+    #[structopt(name = "PEER_DNS", long = "peer-dns")]
+    peer_dns: Option<String>,
     #[structopt(flatten)]
     #[serde(flatten)]
     cache_key_path: CacheKeyPath,
@@ -171,9 +183,25 @@ pub struct SupRun {
     /// Use package config from this path, rather than the package itself
     #[structopt(name = "CONFIG_DIR", long = "config-from")]
     config_dir: Option<PathBuf>,
-    /// Enable automatic updates for the Supervisor itself
+    /// Enable automatic updates for the Supervisor itself. If neither this nor --no-auto-update is
+    /// specified, the value will be taken from the HAB_AUTO_UPDATE environment variable if defined
     #[structopt(name = "AUTO_UPDATE", long = "auto-update", short = "A")]
     auto_update: bool,
+    /// Disable automatic updates for the Supervisor itself, overriding the HAB_AUTO_UPDATE
+    /// environment variable
+    #[structopt(name = "NO_AUTO_UPDATE",
+                long = "no-auto-update",
+                conflicts_with = "AUTO_UPDATE")]
+    no_auto_update: bool,
+    /// Receive automatic updates for the Supervisor itself from the specified release channel. If
+    /// not specified, the value of --channel is used
+    #[structopt(name = "AUTO_UPDATE_CHANNEL", long = "auto-update-channel")]
+    auto_update_channel: Option<String>,
+    /// The interval (seconds) on which to check for automatic updates for the Supervisor itself.
+    /// If not specified, the value will be taken from the HAB_SUP_UPDATE_MS environment variable
+    /// if defined [default: 60]
+    #[structopt(name = "AUTO_UPDATE_PERIOD", long = "auto-update-period")]
+    auto_update_period: Option<u64>,
     /// Used for enabling TLS for the HTTP gateway. Read private key from KEY_FILE. This should be
     /// a RSA private key or PKCS8-encoded private key, in PEM format
     #[structopt(name = "KEY_FILE", long = "key", requires = "CERT_FILE")]
@@ -190,6 +218,19 @@ pub struct SupRun {
                 long = "ca-certs",
                 requires_all = &["CERT_FILE", "KEY_FILE"])]
     ca_cert_file: Option<PathBuf>,
+    /// Used for enabling TLS for the Control Gateway. Read private key from LISTEN_CTL_KEY_FILE.
+    /// This should be a RSA private key or PKCS8-encoded private key, in PEM format
+    #[structopt(name = "LISTEN_CTL_KEY_FILE",
+                long = "listen-ctl-key",
+                requires = "LISTEN_CTL_CERT_FILE")]
+    listen_ctl_key_file: Option<PathBuf>,
+    /// Used for enabling TLS for the Control Gateway. Read server certificates from
+    /// LISTEN_CTL_CERT_FILE. This should contain PEM-format certificates in the right order (the
+    /// first certificate should certify LISTEN_CTL_KEY_FILE, the last should be a root CA)
+    #[structopt(name = "LISTEN_CTL_CERT_FILE",
+                long = "listen-ctl-cert",
+                requires = "LISTEN_CTL_KEY_FILE")]
+    listen_ctl_cert_file: Option<PathBuf>,
     /// Load the given Habitat package as part of the Supervisor startup specified by a package
     /// identifier (ex: core/redis) or filepath to a Habitat Artifact (ex:
     /// /home/core-redis-3.0.7-21120102031201-x86_64-linux.hart)
@@ -202,6 +243,11 @@ pub struct SupRun {
     /// Turn ANSI color off
     #[structopt(name = "NO_COLOR", long = "no-color")]
     no_color: bool,
+    /// Turn ANSI color on, even when output is not written to a terminal
+    #[structopt(name = "FORCE_COLOR",
+                long = "force-color",
+                conflicts_with_all = &["NO_COLOR", "JSON"])]
+    force_color: bool,
     /// Use structured JSON logging for the Supervisor. Implies NO_COLOR
     #[structopt(name = "JSON", long = "json-logging")]
     json_logging: bool,
@@ -210,6 +256,12 @@ pub struct SupRun {
     /// defaults to using `127.0.0.1`
     #[structopt(name = "SYS_IP_ADDRESS", long = "sys-ip-address")]
     sys_ip_address: Option<Ipv4Addr>,
+    /// A command which resolves the `secret` template helper. The command is given the secret's
+    /// name on stdin and must print its value on stdout. A template fails to render if the
+    /// command exits unsuccessfully, or is still running after HAB_SECRETS_PROVIDER_TIMEOUT_SECS
+    /// (default: 10)
+    #[structopt(name = "SECRETS_PROVIDER", long = "secrets-provider")]
+    secrets_provider: Option<String>,
     /// The name of the application for event stream purposes. This will be attached to all events
     /// generated by this Supervisor
     #[structopt(name = "EVENT_STREAM_APPLICATION", long = "event-stream-application")]
@@ -266,6 +318,15 @@ pub struct SupRun {
                 long = "keep-latest-packages",
                 env = "HAB_KEEP_LATEST_PACKAGES")]
     keep_latest_packages: Option<usize>,
+    /// The interval (seconds) on which to run health checks for services loaded without their own
+    /// interval [default: 30]
+    #[structopt(name = "DEFAULT_HEALTH_CHECK_INTERVAL",
+                long = "default-health-check-interval")]
+    default_health_check_interval: Option<HealthCheckInterval>,
+    /// A file of service groups to bind to a configuration, one per line. Combined with any
+    /// --bind values
+    #[structopt(name = "BIND_FILE", long = "bind-file")]
+    bind_file: Option<PathBuf>,
     #[structopt(flatten)]
     #[serde(flatten)]
     shared_load: SharedLoad,
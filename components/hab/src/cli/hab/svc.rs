@@ -38,6 +38,12 @@ pub enum Svc {
         pkg_ident:  Option<PackageIdent>,
         #[structopt(flatten)]
         remote_sup: RemoteSup,
+        /// Refresh the status every INTERVAL seconds until interrupted [default: 2]
+        #[structopt(name = "WATCH", long = "watch", short = "w", value_name = "INTERVAL")]
+        watch:      Option<Option<u64>>,
+        /// Only show services whose state changed within the last SECONDS seconds
+        #[structopt(name = "SINCE", long = "since", value_name = "SECONDS")]
+        since:      Option<u64>,
     },
     /// Stop a running Habitat service.
     Stop {
@@ -1,4 +1,5 @@
-use crate::cli::valid_fully_qualified_ident;
+use crate::{cli::valid_fully_qualified_ident,
+            CTL_CA_CERT_ENVVAR};
 use configopt::{self,
                 ConfigOpt};
 use habitat_core::{crypto::CACHE_KEY_PATH_ENV_VAR,
@@ -73,5 +74,10 @@ pub struct FullyQualifiedPkgIdent {
 pub struct RemoteSup {
     /// Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]
     #[structopt(name = "REMOTE_SUP", long = "remote-sup", short = "r")]
-    remote_sup: Option<SocketAddr>,
+    remote_sup:  Option<SocketAddr>,
+    /// Path to PEM-format CA certificates to verify a Supervisor's Control Gateway which serves
+    /// TLS. Its certificate must be issued for the host name given with --remote-sup, or for
+    /// localhost if an IP address or no address is given
+    #[structopt(name = "CTL_CA_CERT", long = "ctl-ca-cert", env = CTL_CA_CERT_ENVVAR)]
+    ctl_ca_cert: Option<PathBuf>,
 }
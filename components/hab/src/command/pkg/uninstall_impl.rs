@@ -241,10 +241,12 @@ async fn supervisor_services() -> Result<Vec<PackageIdent>> {
     let cfg = config::load()?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let listen_ctl_addr = ListenCtlAddr::default();
+    let tls_config = config::local_ctl_tls_config()?;
     let msg = habitat_sup_protocol::ctl::SvcStatus::default();
 
     let mut out: Vec<PackageIdent> = vec![];
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
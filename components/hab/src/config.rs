@@ -3,17 +3,23 @@ use crate::{error::{Error,
             hcore::{config::ConfigFile,
                     fs::{am_i_root,
                          FS_ROOT_PATH}},
+            CTL_CA_CERT_ENVVAR,
             CTL_SECRET_ENVVAR};
 use dirs;
 use habitat_core::env as henv;
-use habitat_sup_client::SrvClient;
+use habitat_sup_client::{CtlTlsConfig,
+                         SrvClient};
 use std::{fs::{self,
                File},
           io::Write,
-          path::PathBuf};
+          path::{Path,
+                 PathBuf}};
 use toml;
 
 const CLI_CONFIG_PATH: &str = "hab/etc/cli.toml";
+/// The name which the certificate of a Control Gateway served over TLS must be issued for when
+/// it is addressed by IP address.
+pub const LOCAL_CTL_SERVER_NAME: &str = "localhost";
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Config {
@@ -77,6 +83,15 @@ pub fn ctl_secret_key(config: &Config) -> Result<String> {
     }
 }
 
+/// Check the HAB_CTL_CA_CERT env var for the trust root with which to connect to the local
+/// Supervisor's Control Gateway over TLS. If it is not set, the connection is made in plaintext.
+pub fn local_ctl_tls_config() -> Result<Option<CtlTlsConfig>> {
+    match henv::var(CTL_CA_CERT_ENVVAR) {
+        Ok(path) => Ok(Some(CtlTlsConfig::new(Path::new(&path), LOCAL_CTL_SERVER_NAME)?)),
+        Err(_) => Ok(None),
+    }
+}
+
 fn cli_config_path() -> PathBuf {
     if !am_i_root() {
         if let Some(home) = dirs::home_dir() {
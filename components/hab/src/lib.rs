@@ -36,6 +36,7 @@ pub mod scaffolding;
 pub const PRODUCT: &str = "hab";
 pub const VERSION: &str = include_str!(concat!(env!("OUT_DIR"), "/VERSION"));
 pub const CTL_SECRET_ENVVAR: &str = "HAB_CTL_SECRET";
+pub const CTL_CA_CERT_ENVVAR: &str = "HAB_CTL_CA_CERT";
 pub const ORIGIN_ENVVAR: &str = "HAB_ORIGIN";
 pub const BLDR_URL_ENVVAR: &str = "HAB_BLDR_URL";
 
@@ -63,7 +63,8 @@ use habitat_core::{crypto::{init,
                    url::{bldr_url_from_env,
                          default_bldr_url},
                    ChannelIdent};
-use habitat_sup_client::{SrvClient,
+use habitat_sup_client::{CtlTlsConfig,
+                         SrvClient,
                          SrvClientError};
 use habitat_sup_protocol::{self as sup_proto,
                            codec::*,
@@ -77,24 +78,30 @@ use std::{env,
           io::{self,
                prelude::*,
                Read},
-          net::ToSocketAddrs,
+          net::{IpAddr,
+                SocketAddr,
+                ToSocketAddrs},
           path::{Path,
                  PathBuf},
           process,
           result,
           str::FromStr,
           string::ToString,
-          thread};
+          thread,
+          time::Duration};
 use tabwriter::TabWriter;
 use termcolor::{self,
                 Color,
                 ColorSpec};
+use tokio::time;
 use toml;
 
 /// Makes the --org CLI param optional when this env var is set
 const HABITAT_ORG_ENVVAR: &str = "HAB_ORG";
 /// Makes the --user CLI param optional when this env var is set
 const HABITAT_USER_ENVVAR: &str = "HAB_USER";
+/// How often `hab sup status --watch` refreshes when no interval is given
+const DEFAULT_STATUS_WATCH_INTERVAL_SECS: u64 = 2;
 
 lazy_static! {
     static ref STATUS_HEADER: Vec<&'static str> = {
@@ -1129,6 +1136,7 @@ async fn sub_pkg_channels(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
 async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
     let mut ui = ui();
@@ -1175,7 +1183,10 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
                         .map(ToString::to_string)
                         .unwrap_or_else(|| "UNKNOWN".to_string()),))?;
     ui.status(Status::Creating, "service configuration")?;
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, validate).await?;
+    let mut response = SrvClient::request_with_tls(&listen_ctl_addr,
+                                                   tls_config.as_ref(),
+                                                   &secret_key,
+                                                   validate).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1194,7 +1205,8 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
         }
     }
     ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))?;
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, set).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, set).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1215,10 +1227,12 @@ async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let mut msg = sup_proto::ctl::SvcGetDefaultCfg::default();
     msg.ident = Some(ident.into());
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1240,11 +1254,13 @@ async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
 async fn sub_svc_load(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let mut msg = svc_load_from_input(m)?;
     let ident: PackageIdent = m.value_of("PKG_IDENT").unwrap().parse()?;
     msg.ident = Some(ident.into());
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         handle_ctl_reply(&reply)?;
@@ -1256,13 +1272,15 @@ async fn sub_svc_unload(m: &ArgMatches<'_>) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let timeout_in_seconds =
         parse_optional_arg::<ShutdownTimeout>("SHUTDOWN_TIMEOUT", m).map(u32::from);
 
     let msg = sup_proto::ctl::SvcUnload { ident: Some(ident.into()),
                                           timeout_in_seconds };
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         handle_ctl_reply(&reply)?;
@@ -1274,10 +1292,12 @@ async fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let mut msg = sup_proto::ctl::SvcStart::default();
     msg.ident = Some(ident.into());
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         handle_ctl_reply(&reply)?;
@@ -1288,24 +1308,69 @@ async fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
 async fn sub_svc_status(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
-    let mut msg = sup_proto::ctl::SvcStatus::default();
-    if let Some(pkg) = m.value_of("PKG_IDENT") {
-        msg.ident = Some(PackageIdent::from_str(pkg)?.into());
+    let ident = m.value_of("PKG_IDENT")
+                 .map(PackageIdent::from_str)
+                 .transpose()?;
+
+    let since = parse_optional_arg("SINCE", m).map(Duration::from_secs);
+
+    let interval = match watch_interval_from_input(m) {
+        Some(interval) => interval,
+        None => {
+            return svc_status(&listen_ctl_addr,
+                              tls_config.as_ref(),
+                              &secret_key,
+                              ident.as_ref(),
+                              since).await
+        }
+    };
+    loop {
+        match svc_status(&listen_ctl_addr,
+                         tls_config.as_ref(),
+                         &secret_key,
+                         ident.as_ref(),
+                         since).await
+        {
+            Ok(()) => {}
+            // Keep watching while the Supervisor restarts or is otherwise unreachable
+            Err(Error::CtlClient(SrvClientError::ConnectionRefused))
+            | Err(Error::CtlClient(SrvClientError::ConnectionClosed))
+            | Err(Error::CtlClient(SrvClientError::Io(_))) => {
+                eprintln!("Unable to reach the Supervisor at {}, retrying in {}s",
+                          listen_ctl_addr.as_ref(),
+                          interval.as_secs());
+            }
+            Err(e) => return Err(e),
+        }
+        time::delay_for(interval).await;
+        println!();
     }
+}
+
+async fn svc_status(listen_ctl_addr: &ListenCtlAddr,
+                    tls_config: Option<&CtlTlsConfig>,
+                    secret_key: &str,
+                    ident: Option<&PackageIdent>,
+                    since: Option<Duration>)
+                    -> Result<()> {
+    let mut msg = sup_proto::ctl::SvcStatus::default();
+    msg.ident = ident.cloned().map(Into::into);
 
     let mut out = TabWriter::new(io::stdout());
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(listen_ctl_addr, tls_config, secret_key, msg).await?;
     // Ensure there is at least one result from the server otherwise produce an error
     if let Some(message_result) = response.next().await {
-        let reply = message_result?;
-        print_svc_status(&mut out, &reply, true)?;
+        let reply = message_result.map_err(SrvClientError::from)?;
+        print_svc_status(&mut out, &reply, true, since)?;
     } else {
         return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into());
     }
     while let Some(message_result) = response.next().await {
-        let reply = message_result?;
-        print_svc_status(&mut out, &reply, false)?;
+        let reply = message_result.map_err(SrvClientError::from)?;
+        print_svc_status(&mut out, &reply, false, since)?;
     }
     out.flush()?;
     Ok(())
@@ -1315,13 +1380,15 @@ async fn sub_svc_stop(m: &ArgMatches<'_>) -> Result<()> {
     let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let timeout_in_seconds =
         parse_optional_arg::<ShutdownTimeout>("SHUTDOWN_TIMEOUT", m).map(u32::from);
 
     let msg = sup_proto::ctl::SvcStop { ident: Some(ident.into()),
                                         timeout_in_seconds };
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         handle_ctl_reply(&reply)?;
@@ -1333,6 +1400,7 @@ async fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let mut ui = ui();
     let mut msg = sup_proto::ctl::SvcFilePut::default();
@@ -1374,7 +1442,8 @@ async fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     }
     ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))
       .unwrap();
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1399,6 +1468,7 @@ async fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
 async fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let tls_config = ctl_tls_config_from_input(m)?;
     let secret_key = config::ctl_secret_key(&cfg)?;
     let mut ui = ui();
     let mut msg = sup_proto::ctl::SupDepart::default();
@@ -1412,7 +1482,8 @@ async fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
       .unwrap();
     ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))
       .unwrap();
-    let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
+    let mut response =
+        SrvClient::request_with_tls(&listen_ctl_addr, tls_config.as_ref(), &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
         match reply.message_id() {
@@ -1840,7 +1911,8 @@ fn handle_ctl_reply(reply: &SrvMessage) -> result::Result<(), SrvClientError> {
 
 fn print_svc_status<T>(out: &mut T,
                        reply: &SrvMessage,
-                       print_header: bool)
+                       print_header: bool,
+                       since: Option<Duration>)
                        -> result::Result<(), SrvClientError>
     where T: io::Write
 {
@@ -1881,6 +1953,11 @@ fn print_svc_status<T>(out: &mut T,
     if print_header {
         writeln!(out, "{}", STATUS_HEADER.join("\t")).unwrap();
     }
+    if let Some(since) = since {
+        if !state_changed_within(&status, since) {
+            return Ok(());
+        }
+    }
     // Composites were removed in 0.75 but people could be
     // depending on the exact format of this output even if they
     // never used composites. We don't want to break their tooling
@@ -1900,6 +1977,15 @@ fn print_svc_status<T>(out: &mut T,
     Ok(())
 }
 
+/// Returns `true` if the service's process entered its current state within the given window.
+/// A service without process information has no known state change, and so is excluded.
+fn state_changed_within(status: &sup_proto::types::ServiceStatus, since: Duration) -> bool {
+    status.process
+          .as_ref()
+          .and_then(|process| process.elapsed)
+          .map_or(false, |elapsed| elapsed <= since.as_secs())
+}
+
 fn bulkupload_dir_from_matches(matches: &ArgMatches<'_>) -> PathBuf {
     matches.value_of("UPLOAD_DIRECTORY")
            .map(PathBuf::from)
@@ -1972,11 +2058,53 @@ fn get_update_condition_from_input(m: &ArgMatches<'_>) -> Option<UpdateCondition
      .and_then(|f| UpdateCondition::from_str(f).ok())
 }
 
+fn watch_interval_from_input(m: &ArgMatches<'_>) -> Option<Duration> {
+    if m.is_present("WATCH") {
+        let secs = parse_optional_arg("WATCH", m).unwrap_or(DEFAULT_STATUS_WATCH_INTERVAL_SECS);
+        Some(Duration::from_secs(secs))
+    } else {
+        None
+    }
+}
+
 fn listen_ctl_addr_from_input(m: &ArgMatches<'_>) -> Result<ListenCtlAddr> {
     m.value_of("REMOTE_SUP")
      .map_or(Ok(ListenCtlAddr::default()), resolve_listen_ctl_addr)
 }
 
+/// Returns the TLS configuration with which to connect to the Control Gateway, if the
+/// `--ctl-ca-cert` trust root was given.
+fn ctl_tls_config_from_input(m: &ArgMatches<'_>) -> Result<Option<CtlTlsConfig>> {
+    match m.value_of("CTL_CA_CERT") {
+        Some(ca_cert_path) => {
+            let server_name = m.value_of("REMOTE_SUP")
+                               .map_or(config::LOCAL_CTL_SERVER_NAME, ctl_server_name);
+            Ok(Some(CtlTlsConfig::new(Path::new(ca_cert_path), server_name)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the name which the certificate of the Control Gateway at the `--remote-sup` address
+/// must be issued for: its host name, or `localhost` if it is an IP address.
+fn ctl_server_name(remote_sup: &str) -> &str {
+    if remote_sup.parse::<SocketAddr>().is_ok() {
+        return config::LOCAL_CTL_SERVER_NAME;
+    }
+    // Only remove a trailing port, which a bare or bracketed IPv6 address does not have
+    let host = if remote_sup.ends_with(']') || remote_sup.parse::<IpAddr>().is_ok() {
+        remote_sup
+    } else {
+        remote_sup.rsplitn(2, ':').last().unwrap_or(remote_sup)
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.parse::<IpAddr>().is_ok() {
+        config::LOCAL_CTL_SERVER_NAME
+    } else {
+        host
+    }
+}
+
 fn resolve_listen_ctl_addr(input: &str) -> Result<ListenCtlAddr> {
     let listen_ctl_addr = if input.find(':').is_some() {
         input.to_string()
@@ -2193,6 +2321,60 @@ mod test {
         }
     }
 
+    mod state_changed_within {
+        use super::*;
+        use sup_proto::types::{ProcessStatus,
+                               ServiceStatus};
+
+        fn status(elapsed: Option<u64>) -> ServiceStatus {
+            let process = ProcessStatus { elapsed,
+                                          ..Default::default() };
+            ServiceStatus { process: Some(process),
+                            ..Default::default() }
+        }
+
+        #[test]
+        fn services_are_filtered_by_time_in_current_state() {
+            let since = Duration::from_secs(300);
+            let services = vec![("core/redis", status(Some(10))),
+                                ("core/nginx", status(Some(300))),
+                                ("core/postgresql", status(Some(3600))),
+                                ("core/consul", status(None)),
+                                ("core/vault", ServiceStatus::default())];
+
+            let recent: Vec<_> = services.iter()
+                                         .filter(|(_, s)| state_changed_within(s, since))
+                                         .map(|(name, _)| *name)
+                                         .collect();
+
+            assert_eq!(recent, vec!["core/redis", "core/nginx"]);
+        }
+    }
+
+    mod ctl_server_name {
+        use super::*;
+
+        #[test]
+        fn host_name_is_the_server_name() {
+            assert_eq!(ctl_server_name("sup.acme.io:9632"), "sup.acme.io");
+            assert_eq!(ctl_server_name("sup.acme.io"), "sup.acme.io");
+        }
+
+        #[test]
+        fn ip_address_is_verified_as_localhost() {
+            assert_eq!(ctl_server_name("10.0.0.12:9632"), "localhost");
+            assert_eq!(ctl_server_name("10.0.0.12"), "localhost");
+        }
+
+        #[test]
+        fn ipv6_address_is_verified_as_localhost() {
+            assert_eq!(ctl_server_name("[::1]:9632"), "localhost");
+            assert_eq!(ctl_server_name("[fd00::12]:9632"), "localhost");
+            assert_eq!(ctl_server_name("::1"), "localhost");
+            assert_eq!(ctl_server_name("[::1]"), "localhost");
+        }
+    }
+
     mod resolve_listen_ctl_addr {
         use super::*;
 
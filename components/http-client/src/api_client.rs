@@ -33,6 +33,10 @@ const CLIENT_SOCKET_RW_TIMEOUT_SEC: u64 = 300;
 const CACERTS_PKG_IDENT: &str = "core/cacerts";
 const CACERT_PEM: &str = include_str!(concat!(env!("OUT_DIR"), "/cacert.pem"));
 
+/// The environment variable naming a CA bundle file, such as one holding the certificate of a
+/// TLS-intercepting proxy, whose certificates are trusted in addition to all others.
+pub const SSL_CERT_FILE_ENVVAR: &str = "HAB_SSL_CERT_FILE";
+
 /// A generic wrapper around a Reqwest HTTP client intended for API-like usage.
 ///
 /// When an `ApiClient` is created, it has a constant URL base which is assumed to be some API
@@ -64,6 +68,24 @@ impl ApiClient {
                   fs_root_path: Option<&Path>)
                   -> Result<Self>
         where T: IntoUrl
+    {
+        Self::new_with_ca_bundle(endpoint, product, version, fs_root_path, None)
+    }
+
+    /// Creates and returns a new `ApiClient` instance which, in addition to the certificates
+    /// described in `certificates`, trusts every certificate in the given CA bundle file.
+    ///
+    /// # Errors
+    ///
+    /// * If the CA bundle file cannot be read or holds an invalid certificate
+    /// * For any of the reasons given for `new`
+    pub fn new_with_ca_bundle<T>(endpoint: T,
+                                 product: &str,
+                                 version: &str,
+                                 fs_root_path: Option<&Path>,
+                                 ca_bundle: Option<&Path>)
+                                 -> Result<Self>
+        where T: IntoUrl
     {
         let endpoint = endpoint.into_url().map_err(Error::ReqwestError)?;
 
@@ -102,17 +124,18 @@ impl ApiClient {
                                                  .timeout(Duration::from_secs(timeout_in_secs))
                                                  .danger_accept_invalid_certs(skip_cert_verify);
 
-        client =
-            certificates(fs_root_path)?.iter()
-                                       .map(Certificate::to_der)
-                                       .collect::<std::result::Result<Vec<_>, _>>()?
-                                       .into_iter()
-                                       .map(|raw| ReqwestCertificate::from_der(&*raw))
-                                       .collect::<std::result::Result<Vec<_>, _>>()?
-                                       .into_iter()
-                                       .fold(client, |client, cert| {
-                                           client.add_root_certificate(cert)
-                                       });
+        let mut certificates = certificates(fs_root_path)?;
+        if let Some(ca_bundle) = ca_bundle {
+            certificates.extend(ca_bundle_certificates(ca_bundle)?);
+        }
+        client = certificates.iter()
+                             .map(Certificate::to_der)
+                             .collect::<std::result::Result<Vec<_>, _>>()?
+                             .into_iter()
+                             .map(|raw| ReqwestCertificate::from_der(&*raw))
+                             .collect::<std::result::Result<Vec<_>, _>>()?
+                             .into_iter()
+                             .fold(client, |client, cert| client.add_root_certificate(cert));
 
         Ok(ApiClient { inner: client.build()?,
                        endpoint })
@@ -287,6 +310,9 @@ fn user_agent(product: &str, version: &str) -> Result<HeaderValue> {
 ///    will also get loaded into the root certs list. Both PEM and DER formats are supported. All
 ///    files will be assumed to be one of the supported formats, and any errors will be ignored
 ///    silently (other than debug logging)
+/// 4. Every certificate in the CA bundle file named by the `HAB_SSL_CERT_FILE` environment
+///    variable, if it is set, will also get loaded into the root certs list. Unlike the files in
+///    the SSL cache directory, an unreadable or invalid bundle is an error.
 pub fn certificates(fs_root_path: Option<&Path>) -> Result<Vec<Certificate>> {
     let mut certificates = Vec::new();
     let cert_cache_dir = cache_ssl_path(fs_root_path);
@@ -304,6 +330,38 @@ pub fn certificates(fs_root_path: Option<&Path>) -> Result<Vec<Certificate>> {
     }
 
     process_cache_dir(&cert_cache_dir, &mut certificates);
+    if let Ok(ca_bundle) = env::var(SSL_CERT_FILE_ENVVAR) {
+        debug!("Processing CA bundle from {}: {}",
+               SSL_CERT_FILE_ENVVAR, ca_bundle);
+        certificates.extend(ca_bundle_certificates(Path::new(&ca_bundle))?);
+    }
+    Ok(certificates)
+}
+
+/// Returns every certificate in a CA bundle file, which holds either any number of PEM-encoded
+/// certificates or a single DER-encoded certificate.
+///
+/// # Errors
+///
+/// * If the file cannot be read
+/// * If a certificate in the file is invalid
+pub fn ca_bundle_certificates(file_path: &Path) -> Result<Vec<Certificate>> {
+    const PEM_END: &str = "-----END CERTIFICATE-----";
+
+    let buf = fs::read(file_path)?;
+    let pem = String::from_utf8_lossy(&buf);
+    if !pem.contains(PEM_END) {
+        return Ok(vec![Certificate::from_der(&buf).map_err(Error::NativeTlsError)?]);
+    }
+    let mut certificates = Vec::new();
+    let mut start = 0;
+    for (i, _) in pem.match_indices(PEM_END) {
+        let end = i + PEM_END.len();
+        let certificate =
+            Certificate::from_pem(pem[start..end].as_bytes()).map_err(Error::NativeTlsError)?;
+        certificates.push(certificate);
+        start = end;
+    }
     Ok(certificates)
 }
 
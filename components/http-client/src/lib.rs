@@ -4,7 +4,9 @@ extern crate log;
 mod api_client;
 mod error;
 
-pub use crate::{api_client::{certificates,
-                             ApiClient},
+pub use crate::{api_client::{ca_bundle_certificates,
+                             certificates,
+                             ApiClient,
+                             SSL_CERT_FILE_ENVVAR},
                 error::{Error,
                         Result}};
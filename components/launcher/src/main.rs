@@ -50,6 +50,9 @@ fn set_global_logging_options(args: &[String]) {
     if args.contains(&String::from("--no-color")) {
         output::set_format(OutputFormat::NoColor)
     }
+    if args.contains(&String::from("--force-color")) {
+        output::set_force_color(true)
+    }
     if args.contains(&String::from("--json-logging")) {
         output::set_format(OutputFormat::JSON)
     }
@@ -0,0 +1,82 @@
+//! OCI annotations, which describe an image in its manifest rather than in its configuration as
+//! labels do.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// An annotation for the manifest of an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub key:   String,
+    pub value: String,
+}
+
+impl FromStr for Annotation {
+    type Err = Error;
+
+    /// Parses a `KEY=VALUE` pair, where the key is made of lowercase letters, digits, and the
+    /// separators `.`, `-`, `_`, and `/`, in reverse domain notation by convention (ex:
+    /// `org.opencontainers.image.source=https://github.com/acme/app`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, '=');
+        let key = parts.next().unwrap_or_default();
+        match parts.next() {
+            Some(annotation_value) if valid_key(key) => {
+                Ok(Annotation { key:   key.to_string(),
+                                value: annotation_value.to_string(), })
+            }
+            _ => Err(Error::InvalidAnnotation(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+/// Returns `true` if the key starts and ends with a lowercase letter or digit, and otherwise
+/// contains only lowercase letters, digits, and the separators `.`, `-`, `_`, and `/`.
+fn valid_key(key: &str) -> bool {
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    key.starts_with(is_alphanumeric)
+    && key.ends_with(is_alphanumeric)
+    && key.chars()
+          .all(|c| is_alphanumeric(c) || c == '.' || c == '-' || c == '_' || c == '/')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn annotation_parses_key_and_value() {
+        let annotation: Annotation =
+            "org.opencontainers.image.source=https://github.com/a/b=c".parse()
+                                                                      .unwrap();
+        assert_eq!(annotation.key, "org.opencontainers.image.source");
+        assert_eq!(annotation.value, "https://github.com/a/b=c");
+
+        let annotation: Annotation = "sh.habitat.note=".parse().unwrap();
+        assert_eq!(annotation.value, "");
+    }
+
+    #[test]
+    fn annotation_rejects_invalid_keys() {
+        for value in &["NO_VALUE",
+                       "=value",
+                       "Org.Acme=value",
+                       ".acme=value",
+                       "acme.=value",
+                       "acme app=value"]
+        {
+            match value.parse::<Annotation>() {
+                Err(Error::InvalidAnnotation(v)) => assert_eq!(&v, value),
+                r => panic!("Expected InvalidAnnotation for {}, got {:?}", value, r),
+            }
+        }
+    }
+}
@@ -2,9 +2,22 @@
 use crate::rootfs;
 use crate::{accounts::{EtcGroupEntry,
                        EtcPasswdEntry},
+            bundle::Bundle,
+            capability::Capability,
+            config,
+            env_vars::{EnvVar,
+                       EnvVars},
             error::{Error,
                     Result},
+            exclude::Exclusions,
             graph::Graph,
+            hosts::EtcHostsEntry,
+            init::InitSystem,
+            interrupt::Interrupt,
+            lockfile::PackageLock,
+            platform::Platform,
+            stop_signal::StopSignal,
+            userns::UsernsRemap,
             util,
             BUSYBOX_IDENT,
             CACERTS_IDENT,
@@ -23,17 +36,23 @@ use habitat_common::{command::package::install::{InstallHookMode,
                           UIWriter,
                           UI},
                      PROGRAM_NAME};
+#[cfg(unix)]
+use habitat_core::package::target;
 #[cfg(windows)]
 use habitat_core::util::docker;
 use habitat_core::{env,
                    fs::{cache_artifact_path,
                         cache_key_path,
+                        pkg_install_path,
                         CACHE_ARTIFACT_PATH,
-                        CACHE_KEY_PATH},
+                        CACHE_KEY_PATH,
+                        CACHE_SSL_PATH},
                    package::{PackageArchive,
                              PackageIdent,
-                             PackageInstall},
+                             PackageInstall,
+                             PackageTarget},
                    ChannelIdent};
+use habitat_http_client::ca_bundle_certificates;
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 #[cfg(windows)]
@@ -42,8 +61,13 @@ use std::{collections::HashMap,
           fs as stdfs,
           path::{Path,
                  PathBuf},
+          result,
           str::FromStr};
 use tempfile::TempDir;
+use tokio::sync::mpsc::{self,
+                        UnboundedReceiver,
+                        UnboundedSender};
+use url::Url;
 
 // Much of this functionality is duplicated (or slightly modified)
 // in the tar exporter. This needs to be abstacted out in
@@ -77,6 +101,49 @@ fn default_docker_base_image() -> Result<String> {
     }
 }
 
+/// Returns the environment variables read from the `--env-file` file, if any, overridden by
+/// those given inline with `--env`.
+fn env_vars_from_matches(m: &clap::ArgMatches<'_>) -> Result<EnvVars> {
+    let mut env_vars = m.value_of("ENV_FILE")
+                        .map(EnvVars::from_file)
+                        .transpose()?
+                        .unwrap_or_default();
+    for var in m.values_of("ENV").into_iter().flatten() {
+        env_vars.set(EnvVar::from_str(var)?);
+    }
+    Ok(env_vars)
+}
+
+/// A progress event sent while a `BuildSpec` creates its `BuildRoot`.
+///
+/// Packages are installed, dependencies included, by a single call into the Habitat installer,
+/// which reports its own progress only as UI text. The package events are therefore summaries
+/// sent once a package's install has completed, rather than as its download and extraction
+/// happen.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildEvent {
+    /// The packages to install, along with their dependencies, are being resolved.
+    ResolvingDeps,
+    /// A package's artifact, of the given size, is in the artifact cache after its install.
+    Cached { ident: PackageIdent, bytes: u64 },
+    /// A package has been installed into the root file system.
+    Installed { ident: PackageIdent },
+}
+
+impl BuildEvent {
+    /// Reports the event as a status line on the UI.
+    fn report(&self, ui: &mut UI) -> Result<()> {
+        match self {
+            BuildEvent::ResolvingDeps => ui.status(Status::Determining, "packages to install")?,
+            BuildEvent::Cached { ident, bytes } => {
+                ui.status(Status::Cached, format!("{} ({} bytes)", ident, bytes))?
+            }
+            BuildEvent::Installed { ident } => ui.status(Status::Installed, ident)?,
+        }
+        Ok(())
+    }
+}
+
 /// The specification for creating a temporary file system build root, based on Habitat packages.
 ///
 /// When a `BuildSpec` is created, a `BuildRoot` is returned which can be used to produce exported
@@ -89,6 +156,11 @@ pub struct BuildSpec<'a> {
     pub hab_launcher:       &'a str,
     /// A string representation of a Habitat Package Identifer for the Habitat Supervisor package.
     pub hab_sup:            &'a str,
+    /// The Habitat Package Identifier for the Busybox package, which provides the image's shell.
+    pub busybox:            PackageIdent,
+    /// The Habitat Package Identifier for the SSL certificate authorities (CA) certificates
+    /// package.
+    pub cacerts:            PackageIdent,
     /// The Builder URL which is used to install all service and extra Habitat packages.
     pub url:                &'a str,
     /// The Habitat release channel which is used to install all service and extra Habitat
@@ -98,9 +170,23 @@ pub struct BuildSpec<'a> {
     pub base_pkgs_url:      &'a str,
     /// The Habitat release channel which is used to install all base Habitat packages.
     pub base_pkgs_channel:  ChannelIdent,
+    /// An optional CA bundle file whose certificates are trusted when downloading packages from
+    /// Builder.
+    pub ca_bundle:          Option<&'a str>,
     /// A list of either Habitat Package Identifiers or local paths to Habitat Artifact files which
     /// will be installed.
     pub idents_or_archives: Vec<&'a str>,
+    /// An optional bundle of services to install and run under the Supervisor, in addition to
+    /// the given packages.
+    pub bundle:             Option<Bundle>,
+    /// Whether or not every Habitat Package Identifier must be fully qualified.
+    pub strict_ident:       bool,
+    /// Whether or not to install every package and report all failures together, rather than
+    /// stopping at the first failure.
+    pub collect_errors:     bool,
+    /// An optional package target which determines the image's platform, rather than the primary
+    /// service package's target.
+    pub target:             Option<PackageTarget>,
     /// The Builder Auth Token to use in the request
     pub auth:               Option<&'a str>,
     /// Base image used in From of dockerfile
@@ -108,15 +194,94 @@ pub struct BuildSpec<'a> {
     /// Whether or not to create an image with a single layer for each
     /// Habitat package.
     pub multi_layer:        bool,
+    /// Additional host entries to add to the root file system's `/etc/hosts`.
+    pub hosts:              Vec<EtcHostsEntry>,
+    /// An optional path to a file which replaces the root file system's `/etc/resolv.conf`.
+    pub resolv_conf:        Option<&'a str>,
+    /// Whether or not to clear the setuid and setgid bits from all files in the root file system.
+    pub no_setuid:          bool,
+    /// Whether or not to remove the metadata files of installed packages which only describe how
+    /// they were built.
+    pub strip_artifacts:    bool,
+    /// Whether or not to leave busybox, and so a shell, out of the image.
+    pub no_shell:           bool,
+    /// The init program which runs as PID 1 and wraps the image's entrypoint.
+    pub init:               InitSystem,
+    /// An optional package which provides the init program, rather than its default package.
+    pub init_pkg:           Option<&'a str>,
+    /// An optional user namespace range whose first id owns the files copied into the image,
+    /// recorded in an image label for rootless container runtimes.
+    pub userns_remap:       Option<UsernsRemap>,
+    /// Rules for paths to remove from the root file system once it has been assembled.
+    pub exclusions:         Exclusions,
+    /// An optional working directory for the image's entrypoint, which defaults to the primary
+    /// service's directory.
+    pub workdir:            Option<&'a str>,
+    /// An optional signal sent to the container to stop it, which defaults to the container
+    /// engine's default.
+    pub stop_signal:        Option<StopSignal>,
+    /// Absolute paths of mount points to declare as volumes in the image.
+    pub volumes:            Vec<&'a str>,
+    /// An optional program in the primary service package's `bin` path to use as the image's
+    /// entrypoint, rather than the Supervisor.
+    pub binary:             Option<&'a str>,
+    /// Linux capabilities required by the image's services, recorded in an image label.
+    pub capabilities:       Vec<Capability>,
+    /// Absolute paths of files which secrets are mounted onto at runtime, recorded in an image
+    /// label.
+    pub secret_mounts:      Vec<&'a str>,
+    /// Environment variables to set in the image.
+    pub env_vars:           EnvVars,
+    /// Optional configuration values with which to render the primary service's configuration
+    /// templates into the image.
+    pub render_config:      Option<toml::value::Table>,
+    /// Transitive dependencies to leave out of the image, as they aren't needed at runtime.
+    pub excluded_deps:      Vec<PackageIdent>,
+    /// An optional lock file whose packages are installed in place of the packages which would
+    /// otherwise be resolved.
+    pub lock:               Option<PackageLock>,
+    /// An optional path to which a lock file of the resolved packages is written.
+    pub emit_lock_file:     Option<&'a str>,
+    /// An optional directory in which to create the `BuildRoot`, which defaults to the system
+    /// temporary directory.
+    pub build_root_dir:     Option<&'a str>,
+    /// An optional channel on which progress events are sent while the `BuildRoot` is created.
+    pub events:             Option<UnboundedSender<BuildEvent>>,
+    /// Notifies that the process was interrupted, cancelling the creation of the `BuildRoot`.
+    pub interrupt:          Interrupt,
 }
 
 impl<'a> BuildSpec<'a> {
     /// Creates a `BuildSpec` from cli arguments.
+    ///
+    /// The default Builder URL is used for any Builder URL which is not provided, and is
+    /// typically the value of `HAB_BLDR_URL` when set.
+    ///
+    /// # Errors
+    ///
+    /// * If no Habitat package identifiers or artifacts were provided
+    /// * If the default Builder URL is used and is not a valid URL
+    /// * If a host entry or ignore file is invalid
+    /// * If a Busybox or cacerts package identifier is invalid
+    /// * If a user namespace mapping is invalid
+    /// * If the bundle spec cannot be read or is invalid
+    /// * If the lock file cannot be read or is invalid
     pub fn new_from_cli_matches(m: &'a clap::ArgMatches<'_>, default_url: &'a str) -> Result<Self> {
+        if !m.is_present("PKG_IDENT_OR_ARTIFACT") && !m.is_present("BUNDLE_SPEC") {
+            return Err(Error::NoPackagesSpecified.into());
+        }
+        let default_url_used = !m.is_present("BLDR_URL") || !m.is_present("BASE_PKGS_BLDR_URL");
+        if default_url_used && Url::parse(default_url).is_err() {
+            return Err(Error::InvalidBldrUrl(default_url.to_string()).into());
+        }
         Ok(BuildSpec { hab:                m.value_of("HAB_PKG").unwrap_or(DEFAULT_HAB_IDENT),
                        hab_launcher:       m.value_of("HAB_LAUNCHER_PKG")
                                             .unwrap_or(DEFAULT_LAUNCHER_IDENT),
                        hab_sup:            m.value_of("HAB_SUP_PKG").unwrap_or(DEFAULT_SUP_IDENT),
+                       busybox:            PackageIdent::from_str(m.value_of("BUSYBOX_PKG")
+                                                                   .unwrap_or(BUSYBOX_IDENT))?,
+                       cacerts:            PackageIdent::from_str(m.value_of("CACERTS_PKG")
+                                                                   .unwrap_or(CACERTS_IDENT))?,
                        url:                m.value_of("BLDR_URL").unwrap_or(&default_url),
                        channel:            m.value_of("CHANNEL")
                                             .map(ChannelIdent::from)
@@ -125,53 +290,239 @@ impl<'a> BuildSpec<'a> {
                        base_pkgs_channel:  m.value_of("BASE_PKGS_CHANNEL")
                                             .map(ChannelIdent::from)
                                             .unwrap_or_default(),
+                       ca_bundle:          m.value_of("CA_BUNDLE"),
                        auth:               m.value_of("BLDR_AUTH_TOKEN"),
                        idents_or_archives: m.values_of("PKG_IDENT_OR_ARTIFACT")
-                                            .expect("No package specified")
-                                            .collect(),
+                                            .map(Iterator::collect)
+                                            .unwrap_or_default(),
+                       bundle:             m.value_of("BUNDLE_SPEC")
+                                            .map(Bundle::from_file)
+                                            .transpose()?,
+                       strict_ident:       m.is_present("STRICT_IDENT"),
+                       collect_errors:     m.is_present("COLLECT_ERRORS"),
+                       target:             m.value_of("TARGET")
+                                            .map(PackageTarget::from_str)
+                                            .transpose()?,
                        base_image:         m.value_of("BASE_IMAGE")
                                             .map(str::to_string)
                                             .unwrap_or_else(|| {
                                                 default_docker_base_image().expect("No base image \
                                                                                     supported")
                                             }),
-                       multi_layer:        m.is_present("MULTI_LAYER"), })
+                       multi_layer:        m.is_present("MULTI_LAYER"),
+                       hosts:              m.values_of("ADD_HOST")
+                                            .map(|hosts| {
+                                                hosts.map(EtcHostsEntry::from_str)
+                                                     .collect::<result::Result<_, _>>()
+                                            })
+                                            .transpose()?
+                                            .unwrap_or_default(),
+                       resolv_conf:        m.value_of("RESOLV_CONF"),
+                       no_setuid:          m.is_present("NO_SETUID"),
+                       strip_artifacts:    m.is_present("STRIP_BUILD_ARTIFACTS"),
+                       no_shell:           m.is_present("NO_SHELL"),
+                       init:               m.value_of("INIT")
+                                            .map(InitSystem::from_str)
+                                            .transpose()?
+                                            .unwrap_or_default(),
+                       init_pkg:           m.value_of("INIT_PKG"),
+                       userns_remap:       m.value_of("USERNS_REMAP")
+                                            .map(UsernsRemap::from_str)
+                                            .transpose()?,
+                       exclusions:         m.value_of("IGNORE_FILE")
+                                            .map(Exclusions::from_file)
+                                            .transpose()?
+                                            .unwrap_or_default(),
+                       workdir:            m.value_of("WORKDIR"),
+                       stop_signal:        m.value_of("STOP_SIGNAL")
+                                            .map(StopSignal::from_str)
+                                            .transpose()?,
+                       volumes:            m.values_of("VOLUME")
+                                            .map(Iterator::collect)
+                                            .unwrap_or_default(),
+                       binary:             m.value_of("BINARY"),
+                       capabilities:       m.values_of("REQUIRED_CAPABILITY")
+                                            .map(|caps| {
+                                                caps.map(Capability::from_str)
+                                                    .collect::<result::Result<_, _>>()
+                                            })
+                                            .transpose()?
+                                            .unwrap_or_default(),
+                       secret_mounts:      m.values_of("SECRET_MOUNT")
+                                            .map(Iterator::collect)
+                                            .unwrap_or_default(),
+                       env_vars:           env_vars_from_matches(m)?,
+                       render_config:      m.value_of("RENDER_CONFIG")
+                                            .map(config::values_from_file)
+                                            .transpose()?,
+                       excluded_deps:      m.values_of("EXCLUDE_TRANSITIVE_DEP")
+                                            .map(|idents| {
+                                                idents.map(PackageIdent::from_str)
+                                                      .collect::<result::Result<_, _>>()
+                                            })
+                                            .transpose()?
+                                            .unwrap_or_default(),
+                       lock:               m.value_of("PKG_LOCK_FILE")
+                                            .map(PackageLock::from_file)
+                                            .transpose()?,
+                       emit_lock_file:     m.value_of("EMIT_LOCK_FILE"),
+                       build_root_dir:     m.value_of("BUILD_ROOT_DIR"),
+                       events:             None,
+                       interrupt:          Interrupt::default(), })
+    }
+
+    /// Returns the packages to install, which are the given packages followed by the services of
+    /// the bundle, if any.
+    pub fn pkgs(&self) -> Vec<String> {
+        self.idents_or_archives
+            .iter()
+            .map(|ioa| ioa.to_string())
+            .chain(self.bundle.iter().flat_map(Bundle::idents))
+            .collect()
+    }
+
+    /// Returns the Habitat CLI, Supervisor, and Launcher packages, as identifiers or artifact
+    /// paths, in the order they are installed into the root file system.
+    fn hab_pkgs(&self) -> [&'a str; 3] { [self.hab, self.hab_sup, self.hab_launcher] }
+
+    /// Returns the package which provides the init program, if an init program is used.
+    fn init_program_pkg(&self) -> Option<&str> {
+        self.init
+            .default_pkg()
+            .map(|default_pkg| self.init_pkg.unwrap_or(default_pkg))
+    }
+
+    /// Returns a receiver for the progress events sent while the `BuildRoot` is created,
+    /// replacing any earlier subscription.
+    pub fn subscribe(&mut self) -> UnboundedReceiver<BuildEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.events = Some(tx);
+        rx
+    }
+
+    /// Reports a progress event on the UI and sends it to the subscriber, if any.
+    fn emit(&self, ui: &mut UI, event: BuildEvent) -> Result<()> {
+        event.report(ui)?;
+        if let Some(ref events) = self.events {
+            // A subscriber that stopped listening shouldn't fail the build.
+            let _ = events.send(event);
+        }
+        Ok(())
+    }
+
+    /// Checks that the specification describes something to export.
+    ///
+    /// # Errors
+    ///
+    /// * If no Habitat package identifiers or artifacts were provided
+    /// * If strict identifiers are required and a package identifier is not fully qualified
+    fn validate(&self) -> Result<()> {
+        let pkgs = self.pkgs();
+        if pkgs.is_empty() {
+            return Err(Error::NoPackagesSpecified.into());
+        }
+        if self.strict_ident {
+            for ident in pkgs.iter().filter(|i| !Path::new(i).is_file()) {
+                hab::cli::valid_fully_qualified_ident(ident.to_string())
+                    .map_err(|_| Error::IdentNotFullyQualified(ident.to_string()))?;
+            }
+        }
+        Ok(())
     }
 
     /// Creates a `BuildRoot` for the given specification.
     ///
     /// # Errors
     ///
+    /// * If no Habitat package identifiers or artifacts were provided
     /// * If a temporary directory cannot be created
     /// * If the root file system cannot be created
+    /// * If the process is interrupted while the root file system is created
+    /// * If the resolved packages do not match the lock file
+    /// * If the lock file cannot be written
     /// * If the `BuildRootContext` cannot be created
     pub async fn create(self, ui: &mut UI) -> Result<BuildRoot> {
         debug!("Creating BuildRoot from {:?}", &self);
-        let workdir = TempDir::new()?;
+        self.validate()?;
+        let workdir = self.create_temp_dir()?;
         let rootfs = workdir.path().join("rootfs");
         ui.status(Status::Creating,
                   format!("build root in {}", workdir.path().display()))?;
-        let graph = self.prepare_rootfs(ui, &rootfs).await?;
+        self.emit(ui, BuildEvent::ResolvingDeps)?;
+        // Cancelling drops the temporary directory, which removes it
+        let graph = self.interrupt
+                        .cancel_on_interrupt(self.prepare_rootfs(ui, &rootfs))
+                        .await?;
+        self.lock_pkgs(ui, &graph)?;
+        let ctx = BuildRootContext::from_spec(&self, &rootfs)?;
+        self.render_primary_svc_config(ui, &rootfs, &ctx)?;
+        self.write_bundle_specs(ui, &rootfs)?;
         Ok(BuildRoot { workdir,
-                       ctx: BuildRootContext::from_spec(&self, &rootfs)?,
+                       ctx,
                        graph })
     }
 
+    /// Creates the temporary directory for the `BuildRoot`.
+    ///
+    /// # Errors
+    ///
+    /// * If the directory cannot be created in the given build root directory
+    /// * If the directory cannot be created in the system temporary directory
+    fn create_temp_dir(&self) -> Result<TempDir> {
+        match self.build_root_dir {
+            Some(dir) => {
+                let path = dir.to_string();
+                TempDir::new_in(dir).map_err(|e| {
+                                        let reason = e.to_string();
+                                        Error::BuildRootDirNotWritable { path, reason }.into()
+                                    })
+            }
+            None => Ok(TempDir::new()?),
+        }
+    }
+
     #[cfg(unix)]
     async fn prepare_rootfs(&self, ui: &mut UI, rootfs: &Path) -> Result<Graph> {
         ui.status(Status::Creating, "root filesystem")?;
         rootfs::create(rootfs)?;
+        if let Some(resolv_conf) = self.resolv_conf {
+            ui.status(Status::Using,
+                      format!("{} as /etc/resolv.conf", resolv_conf))?;
+            rootfs::set_resolv_conf(rootfs, resolv_conf)?;
+        }
+        if !self.hosts.is_empty() {
+            ui.status(Status::Creating, "host entries in /etc/hosts")?;
+            rootfs::add_hosts(rootfs, &self.hosts)?;
+        }
         self.create_symlink_to_artifact_cache(ui, rootfs)?;
         self.create_symlink_to_key_cache(ui, rootfs)?;
+        let staged_certs = self.stage_ca_bundle(ui, rootfs)?;
         let base_pkgs = self.install_base_pkgs(ui, rootfs).await?;
         let user_pkgs = self.install_user_pkgs(ui, rootfs).await?;
+        self.remove_staged_ca_bundle(ui, rootfs, &staged_certs)?;
         self.link_binaries(ui, rootfs, &base_pkgs)?;
         self.link_cacerts(ui, rootfs, &base_pkgs)?;
         self.link_user_pkgs(ui, rootfs, &user_pkgs)?;
         self.remove_symlink_to_key_cache(ui, rootfs)?;
         self.remove_symlink_to_artifact_cache(ui, rootfs)?;
+        self.remove_excluded_paths(ui, rootfs)?;
+        if self.strip_artifacts {
+            self.strip_build_artifacts(ui, rootfs)?;
+        }
+        if self.no_setuid {
+            self.strip_setuid(ui, rootfs)?;
+        }
+        if self.no_shell {
+            ui.status(Status::Creating, "runtime directories")?;
+            rootfs::create_runtime_dirs(rootfs)?;
+        }
+        for path in &self.secret_mounts {
+            ui.status(Status::Creating, format!("secret mount point {}", path))?;
+            rootfs::create_secret_mount(rootfs, path)?;
+        }
 
-        let graph = Graph::from_packages(base_pkgs, user_pkgs, &rootfs)?;
+        let mut graph = Graph::from_packages(base_pkgs, user_pkgs, &rootfs)?;
+        self.remove_excluded_deps(ui, rootfs, &mut graph)?;
 
         Ok(graph)
     }
@@ -181,16 +532,59 @@ impl<'a> BuildSpec<'a> {
         ui.status(Status::Creating, "root filesystem")?;
         self.create_symlink_to_artifact_cache(ui, rootfs)?;
         self.create_symlink_to_key_cache(ui, rootfs)?;
+        let staged_certs = self.stage_ca_bundle(ui, rootfs)?;
         let base_pkgs = self.install_base_pkgs(ui, rootfs).await?;
         let user_pkgs = self.install_user_pkgs(ui, rootfs).await?;
+        self.remove_staged_ca_bundle(ui, rootfs, &staged_certs)?;
         self.remove_symlink_to_key_cache(ui, rootfs)?;
         self.remove_symlink_to_artifact_cache(ui, rootfs)?;
+        self.remove_excluded_paths(ui, rootfs)?;
 
-        let graph = Graph::from_packages(base_pkgs, user_pkgs, &rootfs)?;
+        let mut graph = Graph::from_packages(base_pkgs, user_pkgs, &rootfs)?;
+        self.remove_excluded_deps(ui, rootfs, &mut graph)?;
 
         Ok(graph)
     }
 
+    /// Writes a Supervisor spec file for each service of the bundle, if any, so that the
+    /// Supervisor loads them all when the image is run.
+    fn write_bundle_specs(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
+        if let Some(ref bundle) = self.bundle {
+            ui.status(Status::Creating,
+                      "Supervisor specs for the bundled services")?;
+            bundle.write_specs(rootfs)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the resolved packages against the lock file, if one was provided, and writes a lock
+    /// file of them, if requested.
+    fn lock_pkgs(&self, ui: &mut UI, graph: &Graph) -> Result<()> {
+        if let Some(ref lock) = self.lock {
+            ui.status(Status::Verifying, "resolved packages against the lock file")?;
+            lock.verify(graph)?;
+        }
+        if let Some(path) = self.emit_lock_file {
+            ui.status(Status::Creating, format!("lock file {}", path))?;
+            PackageLock::from_graph(graph).write(path)?;
+        }
+        Ok(())
+    }
+
+    fn render_primary_svc_config(&self,
+                                 ui: &mut UI,
+                                 rootfs: &Path,
+                                 ctx: &BuildRootContext)
+                                 -> Result<()> {
+        if let Some(ref values) = self.render_config {
+            let pkg_install = ctx.primary_svc()?;
+            ui.status(Status::Creating,
+                      format!("configuration for {}", pkg_install.ident()))?;
+            config::render(rootfs, &pkg_install, values)?;
+        }
+        Ok(())
+    }
+
     fn create_symlink_to_artifact_cache(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
         ui.status(Status::Creating, "artifact cache symlink")?;
         let src = cache_artifact_path(None::<&Path>);
@@ -217,31 +611,90 @@ impl<'a> BuildSpec<'a> {
         Ok(())
     }
 
+    /// Stages the certificates of the CA bundle, if one was given, in the SSL cache of the root
+    /// file system. The Builder clients created while installing packages into the root file
+    /// system trust the certificates they find there. Returns the staged files.
+    fn stage_ca_bundle(&self, ui: &mut UI, rootfs: &Path) -> Result<Vec<PathBuf>> {
+        let ca_bundle = match self.ca_bundle {
+            Some(ca_bundle) => ca_bundle,
+            None => return Ok(Vec::new()),
+        };
+        ui.status(Status::Using, format!("{} as a CA bundle", ca_bundle))?;
+        let dir = rootfs.join(CACHE_SSL_PATH);
+        stdfs::create_dir_all(&dir)?;
+        let mut staged = Vec::new();
+        let certificates = ca_bundle_certificates(Path::new(ca_bundle))?;
+        for (i, certificate) in certificates.iter().enumerate() {
+            let file = dir.join(format!("ca-bundle-{}.der", i));
+            stdfs::write(&file, certificate.to_der()?)?;
+            staged.push(file);
+        }
+        Ok(staged)
+    }
+
+    /// Removes the staged certificates of the CA bundle, and the SSL cache of the root file
+    /// system if nothing else is left in it, so that they don't end up in the image.
+    fn remove_staged_ca_bundle(&self,
+                               ui: &mut UI,
+                               rootfs: &Path,
+                               staged: &[PathBuf])
+                               -> Result<()> {
+        if staged.is_empty() {
+            return Ok(());
+        }
+        ui.status(Status::Deleting, "staged CA bundle")?;
+        for file in staged {
+            stdfs::remove_file(file)?;
+        }
+        // Fails, harmlessly, if the directory is not empty
+        stdfs::remove_dir(rootfs.join(CACHE_SSL_PATH)).ok();
+        Ok(())
+    }
+
     async fn install_base_pkgs(&self, ui: &mut UI, rootfs: &Path) -> Result<BasePkgIdents> {
-        let hab = self.install_base_pkg(ui, self.hab, rootfs).await?;
-        let sup = self.install_base_pkg(ui, self.hab_sup, rootfs).await?;
-        let launcher = self.install_base_pkg(ui, self.hab_launcher, rootfs).await?;
-        let busybox = if cfg!(target_os = "linux") {
-            Some(self.install_base_pkg(ui, BUSYBOX_IDENT, rootfs).await?)
+        let [hab, sup, launcher] = self.hab_pkgs();
+        let hab = self.install_base_pkg(ui, hab, rootfs).await?;
+        let sup = self.install_base_pkg(ui, sup, rootfs).await?;
+        let launcher = self.install_base_pkg(ui, launcher, rootfs).await?;
+        let busybox = if cfg!(target_os = "linux") && !self.no_shell {
+            Some(self.install_base_pkg(ui, &self.busybox.to_string(), rootfs)
+                     .await?)
         } else {
             None
         };
-        let cacerts = self.install_base_pkg(ui, CACERTS_IDENT, rootfs).await?;
+        let init = match self.init_program_pkg() {
+            Some(init_pkg) if cfg!(target_os = "linux") => {
+                Some(self.install_base_pkg(ui, init_pkg, rootfs).await?)
+            }
+            _ => None,
+        };
+        let cacerts = self.install_base_pkg(ui, &self.cacerts.to_string(), rootfs)
+                          .await?;
 
         Ok(BasePkgIdents { hab,
                            sup,
                            launcher,
                            busybox,
+                           init,
                            cacerts })
     }
 
     async fn install_user_pkgs(&self, ui: &mut UI, rootfs: &Path) -> Result<Vec<PackageIdent>> {
         let mut idents = Vec::new();
-        for ioa in self.idents_or_archives.iter() {
-            idents.push(self.install_user_pkg(ui, ioa, rootfs).await?);
+        let mut failures = Vec::new();
+        for ioa in self.pkgs() {
+            match self.install_user_pkg(ui, &ioa, rootfs).await {
+                Ok(ident) => idents.push(ident),
+                Err(e) if self.collect_errors => failures.push(format!("{}: {}", ioa, e)),
+                Err(e) => return Err(e),
+            }
         }
 
-        Ok(idents)
+        if failures.is_empty() {
+            Ok(idents)
+        } else {
+            Err(Error::PackagesNotInstalled(failures).into())
+        }
     }
 
     #[cfg(unix)]
@@ -257,15 +710,16 @@ impl<'a> BuildSpec<'a> {
     #[cfg(unix)]
     fn link_binaries(&self, ui: &mut UI, rootfs: &Path, base_pkgs: &BasePkgIdents) -> Result<()> {
         let dst = util::bin_path();
-        hab::command::pkg::binlink::binlink_all_in_pkg(ui,
-                                                       &base_pkgs.busybox
-                                                                 .clone()
-                                                                 .expect("No busybox in idents"),
-                                                       &dst,
-                                                       rootfs,
-                                                       true).map_err(SyncFailure::new)?;
+        if let Some(ref busybox) = base_pkgs.busybox {
+            hab::command::pkg::binlink::binlink_all_in_pkg(ui, busybox, &dst, rootfs, true)
+                .map_err(SyncFailure::new)?;
+        }
         hab::command::pkg::binlink::start(ui, &base_pkgs.hab, "hab", &dst, rootfs, true)
             .map_err(SyncFailure::new)?;
+        if let (Some(init), Some(program)) = (&base_pkgs.init, self.init.program()) {
+            hab::command::pkg::binlink::start(ui, init, program, &dst, rootfs, true)
+                .map_err(SyncFailure::new)?;
+        }
         Ok(())
     }
 
@@ -283,6 +737,44 @@ impl<'a> BuildSpec<'a> {
         Ok(())
     }
 
+    #[cfg(unix)]
+    fn strip_setuid(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
+        for path in rootfs::strip_setuid(rootfs)? {
+            let path = Path::new("/").join(path.strip_prefix(rootfs)
+                                               .expect("path contains rootfs path"));
+            ui.status(Status::Deleting,
+                      format!("setuid/setgid bits from {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn strip_build_artifacts(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
+        for path in rootfs::strip_build_artifacts(rootfs)? {
+            let path = Path::new("/").join(path.strip_prefix(rootfs)
+                                               .expect("path contains rootfs path"));
+            ui.status(Status::Deleting,
+                      format!("build artifact {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn remove_excluded_paths(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
+        for path in self.exclusions.apply(rootfs)? {
+            ui.status(Status::Deleting,
+                      format!("excluded path {}", Path::new("/").join(path).display()))?;
+        }
+        Ok(())
+    }
+
+    fn remove_excluded_deps(&self, ui: &mut UI, rootfs: &Path, graph: &mut Graph) -> Result<()> {
+        for ident in graph.exclude_transitive_deps(&self.excluded_deps)? {
+            ui.status(Status::Deleting, format!("excluded dependency {}", ident))?;
+            stdfs::remove_dir_all(pkg_install_path(&ident, Some(rootfs)))?;
+        }
+        Ok(())
+    }
+
     fn remove_symlink_to_artifact_cache(&self, ui: &mut UI, rootfs: &Path) -> Result<()> {
         ui.status(Status::Deleting, "artifact cache symlink")?;
         stdfs::remove_dir_all(rootfs.join(CACHE_ARTIFACT_PATH))?;
@@ -332,7 +824,7 @@ impl<'a> BuildSpec<'a> {
                      fs_root_path: &Path,
                      token: Option<&str>)
                      -> Result<PackageIdent> {
-        let install_source: InstallSource = ident_or_archive.parse()?;
+        let install_source: InstallSource = self.locked(ident_or_archive)?.parse()?;
         let package_install =
             habitat_common::command::package::install::start(ui,
                                                      url,
@@ -350,7 +842,36 @@ impl<'a> BuildSpec<'a> {
                                                      // ignore-local mode
                                                      &LocalPackageUsage::default(),
                                                      InstallHookMode::Ignore).await?;
-        Ok(package_install.into())
+        let ident: PackageIdent = package_install.into();
+        let bytes =
+            ident.archive_name()
+                 .ok()
+                 .and_then(|name| {
+                     stdfs::metadata(cache_artifact_path(Some(&fs_root_path)).join(name)).ok()
+                 })
+                 .map_or(0, |metadata| metadata.len());
+        self.emit(ui,
+                  BuildEvent::Cached { ident: ident.clone(),
+                                       bytes })?;
+        self.emit(ui, BuildEvent::Installed { ident: ident.clone(), })?;
+        Ok(ident)
+    }
+
+    /// Returns the locked package to install in place of a package identifier, if a lock file was
+    /// provided. Artifacts are always installed as given.
+    ///
+    /// # Errors
+    ///
+    /// * If the package identifier is invalid
+    /// * If no locked package satisfies the package identifier
+    fn locked(&self, ident_or_archive: &str) -> Result<String> {
+        match self.lock {
+            Some(ref lock) if !Path::new(ident_or_archive).is_file() => {
+                Ok(lock.pin(&PackageIdent::from_str(ident_or_archive)?)?
+                       .to_string())
+            }
+            _ => Ok(ident_or_archive.to_string()),
+        }
     }
 }
 
@@ -415,6 +936,34 @@ pub struct BuildRootContext {
     /// Whether or not to create an image with a single layer for each
     /// Habitat package.
     multi_layer:     bool,
+    /// An optional working directory for the image's entrypoint.
+    workdir:         Option<PathBuf>,
+    /// An optional signal sent to the container to stop it.
+    stop_signal:     Option<StopSignal>,
+    /// Mount points to declare as volumes in the image.
+    volumes:         Vec<String>,
+    /// The path, within the image, of a program to use as the image's entrypoint.
+    entrypoint:      Option<PathBuf>,
+    /// Linux capabilities required by the image's services.
+    capabilities:    Vec<Capability>,
+    /// Files which secrets are mounted onto at runtime.
+    secret_mounts:   Vec<String>,
+    /// Environment variables to set in the image.
+    env_vars:        EnvVars,
+    /// Whether or not the image is built without a shell.
+    no_shell:        bool,
+    /// The user namespace range whose first id owns the files copied into the image.
+    userns_remap:    Option<UsernsRemap>,
+    /// The Busybox package which provides the image's shell.
+    busybox:         PackageIdent,
+    /// The init program which wraps the image's entrypoint.
+    init:            InitSystem,
+    /// Whether or not the primary service's configuration is rendered into the image.
+    config_rendered: bool,
+    /// Whether or not the Supervisor loads a bundle of services, rather than the primary service.
+    bundled:         bool,
+    /// An optional package target which overrides the primary service package's target.
+    target:          Option<PackageTarget>,
 }
 
 impl BuildRootContext {
@@ -428,11 +977,12 @@ impl BuildRootContext {
     /// * If an artifact file cannot be read or if a Package Identifier cannot be determined
     /// * If a Package Identifier cannot be parsed from an string representation
     /// * If package metadata cannot be read
+    /// * If the entrypoint program is not in the primary service package's `bin` path
     pub fn from_spec<P: Into<PathBuf>>(spec: &BuildSpec<'_>, rootfs: P) -> Result<Self> {
         let rootfs = rootfs.into();
         let mut idents = Vec::new();
         let mut tdeps = Vec::new();
-        for ident_or_archive in &spec.idents_or_archives {
+        for ident_or_archive in &spec.pkgs() {
             let ident = if Path::new(ident_or_archive).is_file() {
                 // We're going to use the `$pkg_origin/$pkg_name`, fuzzy form of a package
                 // identifier to ensure that update strategies will work if desired
@@ -444,11 +994,15 @@ impl BuildRootContext {
                 PackageIdent::from_str(ident_or_archive)?
             };
             let pkg_install = PackageInstall::load(&ident, Some(&rootfs))?;
+            #[cfg(unix)]
+            fail_if_windows_target(&pkg_install)?;
             tdeps.push(ident.name.clone());
             for dependency in pkg_install.tdeps()? {
                 tdeps.push(dependency.name);
             }
             if pkg_install.is_runnable() {
+                #[cfg(unix)]
+                fail_if_hooks_require_shell(spec, &pkg_install)?;
                 idents.push(PkgIdentType::Svc(SvcIdent { ident,
                                                          exposes: pkg_install.exposes()? }));
             } else {
@@ -472,6 +1026,27 @@ impl BuildRootContext {
 
         let bin_path = util::bin_path();
 
+        let mut volumes = Vec::new();
+        for volume in &spec.volumes {
+            if !volumes.iter().any(|v| v == volume) {
+                volumes.push(volume.to_string());
+            }
+        }
+
+        let mut capabilities = Vec::new();
+        for capability in &spec.capabilities {
+            if !capabilities.contains(capability) {
+                capabilities.push(capability.clone());
+            }
+        }
+
+        let mut secret_mounts = Vec::new();
+        for path in &spec.secret_mounts {
+            if !secret_mounts.iter().any(|p| p == path) {
+                secret_mounts.push(path.to_string());
+            }
+        }
+
         let context = BuildRootContext { idents,
                                          environment,
                                          bin_path: bin_path.into(),
@@ -479,10 +1054,29 @@ impl BuildRootContext {
                                          channel: spec.channel.clone(),
                                          rootfs,
                                          base_image: spec.base_image.clone(),
-                                         multi_layer: spec.multi_layer };
+                                         multi_layer: spec.multi_layer,
+                                         workdir: spec.workdir.map(PathBuf::from),
+                                         stop_signal: spec.stop_signal.clone(),
+                                         volumes,
+                                         entrypoint: None,
+                                         capabilities,
+                                         secret_mounts,
+                                         env_vars: spec.env_vars.clone(),
+                                         no_shell: spec.no_shell,
+                                         userns_remap: spec.userns_remap,
+                                         busybox: spec.busybox.clone(),
+                                         init: spec.init,
+                                         config_rendered: spec.render_config.is_some(),
+                                         bundled: spec.bundle.is_some(),
+                                         target: spec.target };
         context.validate()?;
+        context.platform()?;
+        let entrypoint = spec.binary
+                             .map(|binary| context.primary_svc_binary(binary))
+                             .transpose()?;
 
-        Ok(context)
+        Ok(BuildRootContext { entrypoint,
+                              ..context })
     }
 
     /// Returns a list of all provided Habitat packages which contain a runnable service.
@@ -509,6 +1103,31 @@ impl BuildRootContext {
         PackageInstall::load(self.primary_svc_ident(), Some(&self.rootfs)).map_err(From::from)
     }
 
+    /// Returns the path, within the image, of the named program in the primary service package's
+    /// `bin` path.
+    ///
+    /// # Errors
+    ///
+    /// * If the primary service package could not be loaded from disk
+    /// * If no directory in the package's `bin` path contains the program
+    fn primary_svc_binary(&self, binary: &str) -> Result<PathBuf> {
+        let pkg_install = self.primary_svc()?;
+        let pkg_prefix = pkg_install_path(pkg_install.ident(), None::<&Path>);
+        for path in pkg_install.paths()? {
+            let relative = path.strip_prefix(&pkg_prefix)
+                               .expect("package paths are under the package prefix");
+            if pkg_install.installed_path()
+                          .join(relative)
+                          .join(binary)
+                          .is_file()
+            {
+                return Ok(path.join(binary));
+            }
+        }
+        Err(Error::BinaryNotFound { binary: binary.to_string(),
+                                    ident:  pkg_install.ident().to_string(), }.into())
+    }
+
     /// Returns the fully qualified Package Identifier for the first service package.
     ///
     /// # Errors
@@ -519,6 +1138,51 @@ impl BuildRootContext {
         Ok(pkg_install.ident().clone())
     }
 
+    /// Returns the platform of the image, which is determined by the provided package target or
+    /// else the primary service package's target.
+    ///
+    /// # Errors
+    ///
+    /// * If the primary service package could not be loaded from disk
+    /// * If images cannot be built for the package target
+    pub fn platform(&self) -> Result<Platform> {
+        let target = match self.target {
+            Some(target) => target,
+            None => pkg_target(&self.primary_svc()?)?,
+        };
+        Platform::for_target(target)
+    }
+
+    /// Returns the platform to pass to the image build, which is only needed when a package
+    /// target was provided or the image's platform is not this host's. Otherwise Docker's own
+    /// default applies, so that engines without multi-platform support can build the image.
+    ///
+    /// # Errors
+    ///
+    /// * If the primary service package could not be loaded from disk
+    /// * If images cannot be built for the package target
+    pub fn build_platform(&self) -> Result<Option<Platform>> {
+        let platform = self.platform()?;
+        if self.target.is_some() || !platform.is_host() {
+            Ok(Some(platform))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns `true` if the primary service package has a `health-check` hook.
+    ///
+    /// # Errors
+    ///
+    /// * If the primary service package could not be loaded from disk
+    pub fn primary_svc_has_health_check(&self) -> Result<bool> {
+        Ok(self.primary_svc()?
+               .installed_path()
+               .join("hooks")
+               .join("health-check")
+               .is_file())
+    }
+
     /// Returns the list of package port exposes over all service packages.
     pub fn svc_exposes(&self) -> Vec<&str> {
         let mut exposes = Vec::new();
@@ -690,6 +1354,53 @@ impl BuildRootContext {
 
     pub fn multi_layer(&self) -> bool { self.multi_layer }
 
+    /// Returns the mount points to declare as volumes in the image.
+    pub fn volumes(&self) -> &[String] { &self.volumes }
+
+    /// Returns the path, within the image, of the program to use as the image's entrypoint, if
+    /// one was provided.
+    pub fn entrypoint(&self) -> Option<&Path> { self.entrypoint.as_ref().map(PathBuf::as_path) }
+
+    /// Returns the Linux capabilities required by the image's services.
+    pub fn capabilities(&self) -> &[Capability] { &self.capabilities }
+
+    /// Returns the files which secrets are mounted onto at runtime.
+    pub fn secret_mounts(&self) -> &[String] { &self.secret_mounts }
+
+    /// Returns the environment variables to set in the image.
+    pub fn env_vars(&self) -> &EnvVars { &self.env_vars }
+
+    /// Returns `true` if the image is built without a shell.
+    pub fn no_shell(&self) -> bool { self.no_shell }
+
+    /// Returns the user namespace range whose first id owns the files copied into the image, if
+    /// one was provided.
+    pub fn userns_remap(&self) -> Option<UsernsRemap> { self.userns_remap }
+
+    /// Returns the Busybox package which provides the image's shell.
+    pub fn busybox_ident(&self) -> &PackageIdent { &self.busybox }
+
+    /// Returns the init program which wraps the image's entrypoint.
+    pub fn init(&self) -> InitSystem { self.init }
+
+    /// Returns `true` if the primary service's configuration is rendered into the image.
+    pub fn config_rendered(&self) -> bool { self.config_rendered }
+
+    /// Returns `true` if the Supervisor loads a bundle of services from their spec files, rather
+    /// than running the primary service.
+    pub fn bundled(&self) -> bool { self.bundled }
+
+    /// Returns the signal sent to the container to stop it, if one was provided.
+    pub fn stop_signal(&self) -> Option<&StopSignal> { self.stop_signal.as_ref() }
+
+    /// Returns the working directory for the image's entrypoint, which is the primary service's
+    /// directory unless one was provided.
+    pub fn image_workdir(&self) -> PathBuf {
+        self.workdir
+            .clone()
+            .unwrap_or_else(|| Path::new("/hab/svc").join(&self.primary_svc_ident().name))
+    }
+
     fn validate(&self) -> Result<()> {
         // A valid context for a build root will contain at least one service package, called the
         // primary service package.
@@ -704,6 +1415,83 @@ impl BuildRootContext {
     }
 }
 
+/// Returns the package target of an installed package from its `TARGET` metadata. Packages
+/// without the metadata are assumed to target this host.
+fn pkg_target(pkg_install: &PackageInstall) -> Result<PackageTarget> {
+    let metafile = pkg_install.installed_path().join("TARGET");
+    if !metafile.is_file() {
+        return Ok(PackageTarget::active_target());
+    }
+    Ok(PackageTarget::from_str(stdfs::read_to_string(metafile)?.trim())?)
+}
+
+/// Returns an error if an installed package targets Windows, using the package's `TARGET`
+/// metadata, as the Linux Docker daemon used on this host cannot run it. Packages without the
+/// metadata are assumed to target this host.
+#[cfg(unix)]
+fn fail_if_windows_target(pkg_install: &PackageInstall) -> Result<()> {
+    let target = pkg_target(pkg_install)?;
+    if target == target::X86_64_WINDOWS {
+        return Err(Error::WindowsPackageOnLinux { ident:  pkg_install.ident().to_string(),
+                                                  target: target.to_string(), }.into());
+    }
+    Ok(())
+}
+
+/// Returns an error if the image is built without a shell and the service package's run script
+/// or any of its hooks must be run by one. Hooks aren't checked when a binary is the image's
+/// entrypoint, as the Supervisor doesn't run them.
+#[cfg(unix)]
+fn fail_if_hooks_require_shell(spec: &BuildSpec<'_>, pkg_install: &PackageInstall) -> Result<()> {
+    if !spec.no_shell || spec.binary.is_some() {
+        return Ok(());
+    }
+    let mut hooks = vec![pkg_install.installed_path().join("run")];
+    let hooks_path = pkg_install.installed_path().join("hooks");
+    if hooks_path.is_dir() {
+        for entry in stdfs::read_dir(hooks_path)? {
+            hooks.push(entry?.path());
+        }
+    }
+    for hook in hooks.iter().filter(|h| h.is_file()) {
+        if requires_shell(&stdfs::read(hook)?) {
+            let ident = pkg_install.ident().to_string();
+            let hook = hook.file_name()
+                           .expect("hook has a file name")
+                           .to_string_lossy()
+                           .into_owned();
+            return Err(Error::HookRequiresShell { ident, hook }.into());
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if a hook is a script with no interpreter line, or whose interpreter is a
+/// shell, whether named directly (ex: `#!/bin/sh`) or through `env` (ex: `#!/usr/bin/env bash`).
+/// Compiled programs never require a shell.
+#[cfg(unix)]
+fn requires_shell(hook: &[u8]) -> bool {
+    const SHELLS: &[&str] = &["ash", "bash", "busybox", "dash", "ksh", "sh", "zsh"];
+
+    if hook.starts_with(b"\x7fELF") {
+        return false;
+    }
+    if !hook.starts_with(b"#!") {
+        return true;
+    }
+    let line = String::from_utf8_lossy(&hook[2..]);
+    let mut words = line.lines().next().unwrap_or_default().split_whitespace();
+    let program = |word: &str| word.rsplit('/').next().unwrap_or_default().to_string();
+    let interpreter = match words.next().map(program) {
+        Some(ref env) if env == "env" => words.next().map(program),
+        interpreter => interpreter,
+    };
+    match interpreter {
+        Some(interpreter) => SHELLS.contains(&interpreter.as_str()),
+        None => true,
+    }
+}
+
 /// The package identifiers for installed base packages.
 #[derive(Debug)]
 pub struct BasePkgIdents {
@@ -715,6 +1503,8 @@ pub struct BasePkgIdents {
     pub launcher: PackageIdent,
     /// Installed package identifer for the Busybox package.
     pub busybox:  Option<PackageIdent>,
+    /// Installed package identifer for the init program package.
+    pub init:     Option<PackageIdent>,
     /// Installed package identifer for the CA certs package.
     pub cacerts:  PackageIdent,
 }
@@ -762,18 +1552,58 @@ mod test {
         app.get_matches_from(args)
     }
 
+    /// Generate Clap ArgMatches for the exporter from the arguments following the program name,
+    /// including any arguments which are required on the current platform.
+    fn spec_matches<'a>(args: &[&str]) -> ArgMatches<'a> {
+        let mut argv = vec!["hab-pkg-export-docker"];
+        argv.extend_from_slice(args);
+        #[cfg(windows)]
+        argv.extend_from_slice(&["--base-image", "some/image"]);
+        arg_matches(&argv)
+    }
+
     fn build_spec<'a>() -> BuildSpec<'a> {
         BuildSpec { hab:                "hab",
                     hab_launcher:       "hab_launcher",
                     hab_sup:            "hab_sup",
+                    busybox:            PackageIdent::from_str(BUSYBOX_IDENT).unwrap(),
+                    cacerts:            PackageIdent::from_str(CACERTS_IDENT).unwrap(),
                     url:                "url",
                     channel:            ChannelIdent::from("channel"),
                     base_pkgs_url:      "base_pkgs_url",
                     base_pkgs_channel:  ChannelIdent::from("base_pkgs_channel"),
+                    ca_bundle:          None,
                     idents_or_archives: Vec::new(),
+                    bundle:             None,
+                    strict_ident:       false,
+                    collect_errors:     false,
+                    target:             None,
                     auth:               Some("heresafakeauthtokenduh"),
                     base_image:         String::from("scratch"),
-                    multi_layer:        false, }
+                    multi_layer:        false,
+                    hosts:              Vec::new(),
+                    resolv_conf:        None,
+                    no_setuid:          false,
+                    strip_artifacts:    false,
+                    no_shell:           false,
+                    init:               InitSystem::None,
+                    init_pkg:           None,
+                    userns_remap:       None,
+                    exclusions:         Exclusions::default(),
+                    workdir:            None,
+                    stop_signal:        None,
+                    volumes:            Vec::new(),
+                    binary:             None,
+                    capabilities:       Vec::new(),
+                    secret_mounts:      Vec::new(),
+                    env_vars:           EnvVars::default(),
+                    render_config:      None,
+                    excluded_deps:      Vec::new(),
+                    lock:               None,
+                    emit_lock_file:     None,
+                    build_root_dir:     None,
+                    events:             None,
+                    interrupt:          Interrupt::default(), }
     }
 
     struct FakePkg {
@@ -783,6 +1613,10 @@ mod test {
         rootfs:    PathBuf,
         svc_user:  String,
         svc_group: String,
+        target:    PackageTarget,
+        run:       String,
+        hooks:     Vec<(String, String)>,
+        files:     Vec<(String, String)>,
     }
     impl FakePkg {
         pub fn new<P>(ident: &str, rootfs: P) -> FakePkg
@@ -793,7 +1627,11 @@ mod test {
                       is_svc:    false,
                       rootfs:    rootfs.as_ref().to_path_buf(),
                       svc_user:  "my_user".to_string(),
-                      svc_group: "my_group".to_string(), }
+                      svc_group: "my_group".to_string(),
+                      target:    PackageTarget::active_target(),
+                      run:       String::new(),
+                      hooks:     Vec::new(),
+                      files:     Vec::new(), }
         }
 
         #[cfg(not(windows))]
@@ -817,6 +1655,26 @@ mod test {
             self
         }
 
+        pub fn set_target(&mut self, target: PackageTarget) -> &mut FakePkg {
+            self.target = target;
+            self
+        }
+
+        pub fn set_run(&mut self, run: &str) -> &mut FakePkg {
+            self.run = run.to_string();
+            self
+        }
+
+        pub fn add_hook(&mut self, hook: &str, contents: &str) -> &mut FakePkg {
+            self.hooks.push((hook.to_string(), contents.to_string()));
+            self
+        }
+
+        pub fn add_file(&mut self, path: &str, contents: &str) -> &mut FakePkg {
+            self.files.push((path.to_string(), contents.to_string()));
+            self
+        }
+
         pub fn install(&self) -> PackageIdent {
             let mut ident = PackageIdent::from_str(&self.ident).unwrap();
             if ident.version.is_none() {
@@ -827,7 +1685,7 @@ mod test {
             }
             let prefix = fs::pkg_install_path(&ident, Some(self.rootfs.as_path()));
             util::write_file(prefix.join("IDENT"), &ident.to_string()).unwrap();
-            util::write_file(prefix.join("TARGET"), &PackageTarget::active_target()).unwrap();
+            util::write_file(prefix.join("TARGET"), &self.target).unwrap();
 
             util::write_file(prefix.join("SVC_USER"), &self.svc_user).unwrap();
             util::write_file(prefix.join("SVC_GROUP"), &self.svc_group).unwrap();
@@ -842,7 +1700,13 @@ mod test {
                 }
             }
             if self.is_svc {
-                util::write_file(prefix.join("run"), "").unwrap();
+                util::write_file(prefix.join("run"), &self.run).unwrap();
+            }
+            for (hook, contents) in self.hooks.iter() {
+                util::write_file(prefix.join("hooks").join(hook), contents).unwrap();
+            }
+            for (path, contents) in self.files.iter() {
+                util::write_file(prefix.join(path), contents).unwrap();
             }
             ident
         }
@@ -851,8 +1715,419 @@ mod test {
     mod build_spec {
         use super::*;
         use habitat_common::ui::UI;
+        use habitat_core::url::default_bldr_url;
+        use lazy_static::lazy_static;
         use tempfile::TempDir;
 
+        habitat_core::locked_env_var!(HAB_BLDR_CHANNEL, lock_bldr_channel_env_var);
+        habitat_core::locked_env_var!(HAB_BLDR_URL, lock_bldr_url_env_var);
+        habitat_core::locked_env_var!(HAB_TMP, lock_hab_tmp_env_var);
+
+        #[test]
+        fn bldr_url_defaults_to_env_var() {
+            let env_var = lock_bldr_url_env_var();
+            env_var.set("https://bldr.acme.io");
+            let default_url = default_bldr_url();
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec = BuildSpec::new_from_cli_matches(&matches, &default_url).unwrap();
+
+            assert_eq!("https://bldr.acme.io", spec.url);
+            assert_eq!("https://bldr.acme.io", spec.base_pkgs_url);
+        }
+
+        #[test]
+        fn bldr_url_can_be_provided() {
+            let env_var = lock_bldr_url_env_var();
+            env_var.set("https://bldr.acme.io");
+            let default_url = default_bldr_url();
+            for flag in &["--url", "--bldr-url"] {
+                let matches = spec_matches(&[flag, "https://bldr.example.com", "acme/my_pkg"]);
+                let spec = BuildSpec::new_from_cli_matches(&matches, &default_url).unwrap();
+
+                assert_eq!("https://bldr.example.com", spec.url);
+                assert_eq!("https://bldr.acme.io", spec.base_pkgs_url);
+            }
+        }
+
+        #[test]
+        fn malformed_bldr_urls_are_rejected() {
+            let matches = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                                  "--bldr-url",
+                                                                  "bldr.acme.io",
+                                                                  "acme/my_pkg"]);
+            assert!(matches.is_err());
+
+            let env_var = lock_bldr_url_env_var();
+            env_var.set("bldr.acme.io");
+            let default_url = default_bldr_url();
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let err = BuildSpec::new_from_cli_matches(&matches, &default_url).unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::InvalidBldrUrl(url)) => assert_eq!("bldr.acme.io", url),
+                e => panic!("Expected InvalidBldrUrl, got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn channel_defaults_to_stable() {
+            let env_var = lock_bldr_channel_env_var();
+            env_var.unset();
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+
+            assert_eq!(ChannelIdent::stable(), spec.channel);
+            assert_eq!(ChannelIdent::stable(), spec.base_pkgs_channel);
+        }
+
+        #[test]
+        fn channel_can_be_provided() {
+            let env_var = lock_bldr_channel_env_var();
+            env_var.unset();
+            let matches = spec_matches(&["--channel", "unstable", "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+
+            assert_eq!(ChannelIdent::unstable(), spec.channel);
+            assert_eq!(ChannelIdent::stable(), spec.base_pkgs_channel);
+        }
+
+        #[test]
+        fn invalid_channels_are_rejected() {
+            for channel in &["", "my channel", "rc/1"] {
+                let matches = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                                      "--channel",
+                                                                      channel,
+                                                                      "acme/my_pkg"]);
+
+                assert!(matches.is_err(), "{}", channel);
+            }
+        }
+
+        #[tokio::test]
+        async fn empty_spec_is_rejected_before_creating_build_root() {
+            let mut ui = UI::with_sinks();
+            let err = build_spec().create(&mut ui).await.unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::NoPackagesSpecified) => {}
+                e => panic!("Expected NoPackagesSpecified, got {:?}", e),
+            }
+        }
+
+        #[tokio::test]
+        async fn floating_idents_are_rejected_before_creating_build_root_in_strict_mode() {
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["core/redis/3.0.7/20200101000000", "core/redis"];
+            spec.strict_ident = true;
+            let err = spec.create(&mut UI::with_sinks()).await.unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::IdentNotFullyQualified(ident)) => assert_eq!(ident, "core/redis"),
+                e => panic!("Expected IdentNotFullyQualified, got {:?}", e),
+            }
+        }
+
+        #[tokio::test]
+        async fn install_failures_are_reported_together_when_collecting_errors() {
+            let rootfs = TempDir::new().unwrap();
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme", "acme/redis/4.0.14/20190319155852/extra"];
+            spec.collect_errors = true;
+            let err = spec.install_user_pkgs(&mut UI::with_sinks(), rootfs.path())
+                          .await
+                          .unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::PackagesNotInstalled(failures)) => {
+                    assert_eq!(failures.len(), 2);
+                    assert!(failures[0].starts_with("acme: "));
+                    assert!(failures[1].starts_with("acme/redis/4.0.14/20190319155852/extra: "));
+                }
+                e => panic!("Expected PackagesNotInstalled, got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn floating_idents_are_accepted_without_strict_mode() {
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["core/redis", "core/redis/3.0.7"];
+            assert!(spec.validate().is_ok());
+
+            spec.idents_or_archives = vec!["core/redis/3.0.7/20200101000000"];
+            spec.strict_ident = true;
+            assert!(spec.validate().is_ok());
+        }
+
+        #[test]
+        fn base_pkg_overrides_are_set_from_cli() {
+            let matches = spec_matches(&["--busybox-pkg",
+                                         "acme/busybox/1.31.0",
+                                         "--cacerts-pkg",
+                                         "acme/cacerts",
+                                         "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(PackageIdent::from_str("acme/busybox/1.31.0").unwrap(),
+                       spec.busybox);
+            assert_eq!(PackageIdent::from_str("acme/cacerts").unwrap(),
+                       spec.cacerts);
+
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(PackageIdent::from_str(BUSYBOX_IDENT).unwrap(), spec.busybox);
+            assert_eq!(PackageIdent::from_str(CACERTS_IDENT).unwrap(), spec.cacerts);
+        }
+
+        #[test]
+        fn malformed_base_pkg_overrides_are_rejected() {
+            for flag in &["--busybox-pkg", "--cacerts-pkg"] {
+                for ident in &["busybox", "acme/busybox/1.31.0/20200101000000/extra"] {
+                    let matches = spec_matches(&[*flag, *ident, "acme/my_pkg"]);
+                    let err = BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh")
+                        .unwrap_err();
+                    match err.downcast_ref::<habitat_core::Error>() {
+                        Some(habitat_core::Error::InvalidPackageIdent(i)) => assert_eq!(ident, i),
+                        e => panic!("Expected InvalidPackageIdent, got {:?}", e),
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn hab_pkg_overrides_are_installed_into_the_rootfs() {
+            let matches = spec_matches(&["--hab-pkg",
+                                         "core/hab/1.5.71",
+                                         "--sup-pkg",
+                                         "core/hab-sup/1.5.71/20200416181153",
+                                         "--launcher-pkg",
+                                         "core/hab-launcher/13458",
+                                         "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(["core/hab/1.5.71",
+                        "core/hab-sup/1.5.71/20200416181153",
+                        "core/hab-launcher/13458"],
+                       spec.hab_pkgs());
+
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!([DEFAULT_HAB_IDENT, DEFAULT_SUP_IDENT, DEFAULT_LAUNCHER_IDENT],
+                       spec.hab_pkgs());
+        }
+
+        #[test]
+        fn malformed_hab_pkg_overrides_are_rejected() {
+            for flag in &["--hab-pkg", "--sup-pkg", "--launcher-pkg"] {
+                for ident in &["hab",
+                               "core/hab/1.5.71/20200416181153/extra",
+                               "missing.hart"]
+                {
+                    let result = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                                         *flag,
+                                                                         *ident,
+                                                                         "acme/my_pkg"]);
+                    assert!(result.is_err(), "{} {}", flag, ident);
+                }
+            }
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn init_is_set_from_cli() {
+            let matches = spec_matches(&["--init", "tini", "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(InitSystem::Tini, spec.init);
+            assert_eq!(Some("core/tini"), spec.init_program_pkg());
+
+            let matches = spec_matches(&["--init",
+                                         "dumb-init",
+                                         "--init-pkg",
+                                         "acme/dumb-init",
+                                         "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(InitSystem::DumbInit, spec.init);
+            assert_eq!(Some("acme/dumb-init"), spec.init_program_pkg());
+
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(InitSystem::None, spec.init);
+            assert_eq!(None, spec.init_program_pkg());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn unknown_init_is_rejected_by_cli() {
+            let result = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                                 "--init",
+                                                                 "systemd",
+                                                                 "acme/my_pkg"]);
+            assert!(result.is_err());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn userns_remap_is_set_from_cli() {
+            let matches = spec_matches(&["--userns-remap", "100000:65536", "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(Some("100000:65536".parse().unwrap()), spec.userns_remap);
+
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert_eq!(None, spec.userns_remap);
+        }
+
+        #[test]
+        fn lock_files_are_set_from_cli() {
+            let dir = TempDir::new().unwrap();
+            let lock_file = dir.path().join("redis.lock");
+            stdfs::write(&lock_file,
+                         "core/glibc/2.27/20190115002733\ncore/redis/4.0.14/20190319155852\n").unwrap();
+            let matches = spec_matches(&["--pkg-lock-file",
+                                         lock_file.to_str().unwrap(),
+                                         "--emit-lock-file",
+                                         "next.lock",
+                                         "core/redis"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+
+            assert_eq!(2, spec.lock.as_ref().unwrap().idents().len());
+            assert_eq!(Some("next.lock"), spec.emit_lock_file);
+            assert_eq!("core/redis/4.0.14/20190319155852",
+                       spec.locked("core/redis").unwrap());
+            assert!(spec.locked("core/nginx").is_err());
+            let archive = lock_file.to_str().unwrap();
+            assert_eq!(archive, spec.locked(archive).unwrap());
+
+            let matches = spec_matches(&["core/redis"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert!(spec.lock.is_none());
+            assert_eq!("core/redis", spec.locked("core/redis").unwrap());
+        }
+
+        #[test]
+        fn strict_ident_is_set_from_cli() {
+            let matches = spec_matches(&["--strict-ident", "core/redis"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+            assert!(spec.strict_ident);
+        }
+
+        #[test]
+        fn inline_env_vars_take_precedence_over_env_file() {
+            let dir = TempDir::new().unwrap();
+            let env_file = dir.path().join("app.env");
+            stdfs::write(&env_file,
+                         "# app settings\nDB_HOST='db.internal'\nDB_PORT=5432\n").unwrap();
+            let matches = spec_matches(&["--env-file",
+                                         env_file.to_str().unwrap(),
+                                         "--env",
+                                         "DB_HOST=db.example.com",
+                                         "--env",
+                                         "LOG_LEVEL=debug",
+                                         "acme/my_pkg"]);
+            let spec =
+                BuildSpec::new_from_cli_matches(&matches, "https://bldr.habitat.sh").unwrap();
+
+            let env_vars: Vec<_> = spec.env_vars
+                                       .iter()
+                                       .map(|v| format!("{}={}", v.name, v.value))
+                                       .collect();
+            assert_eq!(vec!["DB_HOST=db.example.com", "DB_PORT=5432", "LOG_LEVEL=debug"],
+                       env_vars);
+        }
+
+        #[test]
+        fn malformed_inline_env_vars_are_rejected() {
+            let matches = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                                  "--env",
+                                                                  "NO_VALUE",
+                                                                  "acme/my_pkg"]);
+
+            assert!(matches.is_err());
+        }
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        #[tokio::test]
+        async fn installing_a_package_sends_events_to_subscriber() {
+            let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures");
+            let archive =
+                fixtures.join("happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart");
+            let rootfs = TempDir::new().unwrap();
+            let keys = cache_key_path(Some(rootfs.path()));
+            stdfs::create_dir_all(&keys).unwrap();
+            stdfs::copy(fixtures.join("happyhumans-20160424223347.pub"),
+                        keys.join("happyhumans-20160424223347.pub")).unwrap();
+            let mut ui = UI::with_sinks();
+            let mut spec = build_spec();
+            let mut events = spec.subscribe();
+
+            let ident = spec.install(&mut ui,
+                                     archive.to_str().unwrap(),
+                                     "https://bldr.habitat.sh",
+                                     &ChannelIdent::stable(),
+                                     rootfs.path(),
+                                     None)
+                            .await
+                            .unwrap();
+            drop(spec);
+
+            let bytes = stdfs::metadata(&archive).unwrap().len();
+            let mut received = Vec::new();
+            while let Some(event) = events.recv().await {
+                received.push(event);
+            }
+            assert_eq!(vec![BuildEvent::Cached { ident: ident.clone(),
+                                                 bytes },
+                            BuildEvent::Installed { ident }],
+                       received);
+        }
+
+        #[test]
+        fn build_root_is_created_in_build_root_dir() {
+            let build_root_dir = TempDir::new().unwrap();
+            let dir = build_root_dir.path().to_string_lossy().into_owned();
+            let mut spec = build_spec();
+            spec.build_root_dir = Some(&dir);
+            let workdir = spec.create_temp_dir().unwrap();
+
+            assert_eq!(Some(build_root_dir.path()), workdir.path().parent());
+        }
+
+        #[test]
+        fn build_root_dir_can_be_provided_by_env_var() {
+            let env_var = lock_hab_tmp_env_var();
+            let dir = std::env::temp_dir().to_string_lossy().into_owned();
+            env_var.set(&dir);
+            let default_url = default_bldr_url();
+            let matches = spec_matches(&["acme/my_pkg"]);
+            let spec = BuildSpec::new_from_cli_matches(&matches, &default_url).unwrap();
+
+            assert_eq!(Some(dir.as_str()), spec.build_root_dir);
+        }
+
+        #[test]
+        fn missing_build_root_dir_is_an_error() {
+            let mut spec = build_spec();
+            spec.build_root_dir = Some("/nonexistent/build-root-dir");
+
+            match spec.create_temp_dir().unwrap_err().downcast_ref::<Error>() {
+                Some(Error::BuildRootDirNotWritable { path, .. }) => {
+                    assert_eq!("/nonexistent/build-root-dir", path)
+                }
+                e => panic!("Expected BuildRootDirNotWritable, got {:?}", e),
+            }
+        }
+
         #[test]
         fn artifact_cache_symlink() {
             let rootfs = TempDir::new().unwrap();
@@ -876,6 +2151,28 @@ mod test {
             assert_eq!(cache_key_path(None::<&Path>), link.read_link().unwrap());
         }
 
+        #[test]
+        fn ca_bundle_is_staged_in_the_rootfs_ssl_cache_while_installing() {
+            let rootfs = TempDir::new().unwrap();
+            let mut ui = UI::with_sinks();
+            let ca_bundle = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/ca-bundle.pem");
+            let mut spec = build_spec();
+            spec.ca_bundle = Some(ca_bundle);
+
+            let staged = spec.stage_ca_bundle(&mut ui, rootfs.path()).unwrap();
+
+            let ssl_cache = rootfs.path().join(CACHE_SSL_PATH);
+            assert_eq!(2, staged.len());
+            for file in &staged {
+                assert_eq!(Some(ssl_cache.as_path()), file.parent());
+                assert!(file.is_file(), "{}", file.display());
+            }
+
+            spec.remove_staged_ca_bundle(&mut ui, rootfs.path(), &staged)
+                .unwrap();
+            assert!(!ssl_cache.exists());
+        }
+
         #[cfg(unix)]
         #[test]
         fn link_binaries() {
@@ -900,6 +2197,28 @@ mod test {
                        "hab program is symlinked into /bin");
         }
 
+        #[cfg(unix)]
+        #[test]
+        fn link_binaries_without_busybox() {
+            let rootfs = TempDir::new().unwrap();
+            let mut ui = UI::with_sinks();
+            let base_pkgs = BasePkgIdents { busybox: None,
+                                            ..base_pkgs(rootfs.path()) };
+            build_spec().link_binaries(&mut ui, rootfs.path(), &base_pkgs)
+                        .unwrap();
+
+            assert!(rootfs.path()
+                          .join("bin/busybox")
+                          .symlink_metadata()
+                          .is_err(),
+                    "busybox program is not symlinked into /bin");
+            assert!(rootfs.path().join("bin/sh").symlink_metadata().is_err(),
+                    "no shell is symlinked into /bin");
+            assert_eq!(fs::pkg_install_path(&base_pkgs.hab, None::<&Path>).join("bin/hab"),
+                       rootfs.path().join("bin/hab").read_link().unwrap(),
+                       "hab program is symlinked into /bin");
+        }
+
         #[cfg(unix)]
         #[test]
         fn link_cacerts() {
@@ -914,12 +2233,47 @@ mod test {
                        "cacerts are symlinked into /etc/ssl");
         }
 
+        #[test]
+        #[cfg(not(windows))]
+        fn render_config_writes_rendered_files_and_user_toml() {
+            let rootfs = TempDir::new().unwrap();
+            let defaults = ["port = 6379", "[tls]", "enabled = false"].join("\n");
+            let template = ["port {{cfg.port}}",
+                            "tls {{cfg.tls.enabled}}",
+                            "dir {{pkg.svc_data_path}}"].join("\n");
+            let _ =
+                FakePkg::new("acme/redis", rootfs.path()).set_svc(true)
+                                                         .add_file("default.toml", &defaults)
+                                                         .add_file("config/redis.conf", &template)
+                                                         .add_file("config/conf.d/bind.conf",
+                                                                   "bind {{cfg.bind}}")
+                                                         .install();
+            let values = ["bind = \"0.0.0.0\"", "[tls]", "enabled = true"].join("\n");
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/redis"];
+            spec.render_config = Some(toml::from_str(&values).unwrap());
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            spec.render_primary_svc_config(&mut UI::with_sinks(), rootfs.path(), &ctx)
+                .unwrap();
+
+            let config = rootfs.path().join("hab/svc/redis/config");
+            assert_eq!("port 6379\ntls true\ndir /hab/svc/redis/data",
+                       stdfs::read_to_string(config.join("redis.conf")).unwrap());
+            assert_eq!("bind 0.0.0.0",
+                       stdfs::read_to_string(config.join("conf.d/bind.conf")).unwrap());
+            let user_toml = rootfs.path().join("hab/user/redis/config/user.toml");
+            let user_toml = stdfs::read_to_string(user_toml).unwrap();
+            assert!(user_toml.contains("bind = \"0.0.0.0\""), "{}", user_toml);
+        }
+
         #[cfg(not(windows))]
         fn base_pkgs<P: AsRef<Path>>(rootfs: P) -> BasePkgIdents {
             BasePkgIdents { hab:      fake_hab_install(&rootfs),
                             sup:      fake_sup_install(&rootfs),
                             launcher: fake_launcher_install(&rootfs),
                             busybox:  Some(fake_busybox_install(&rootfs)),
+                            init:     None,
                             cacerts:  fake_cacerts_install(&rootfs), }
         }
 
@@ -1001,6 +2355,268 @@ mod test {
             // TODO fn: check ctx.svc_exposes()
         }
 
+        #[test]
+        fn bundled_services_are_installed_and_loaded_by_the_supervisor() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+            let _ = FakePkg::new("acme/jogga", rootfs.path()).set_svc(true)
+                                                             .install();
+
+            let contents = r#"
+                [[service]]
+                ident = "acme/runna"
+                binds = ["backend:jogga.default"]
+
+                [[service]]
+                ident = "acme/jogga"
+                group = "prod"
+                topology = "leader"
+                binding_mode = "relaxed"
+                "#;
+            let bundle: Bundle = contents.parse().unwrap();
+            bundle.write_specs(rootfs.path()).unwrap();
+            let mut spec = build_spec();
+            spec.bundle = Some(bundle);
+            assert_eq!(vec!["acme/runna", "acme/jogga"], spec.pkgs());
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert!(ctx.bundled());
+            assert_eq!(vec![&PackageIdent::from_str("acme/runna").unwrap(),
+                            &PackageIdent::from_str("acme/jogga").unwrap(),],
+                       ctx.svc_idents());
+
+            let specs = rootfs.path().join("hab/sup/default/specs");
+            let read_spec = |name: &str| -> toml::value::Table {
+                toml::from_str(&stdfs::read_to_string(specs.join(name)).unwrap()).unwrap()
+            };
+
+            let runna = read_spec("runna.spec");
+            assert_eq!(Some("acme/runna"), runna["ident"].as_str());
+            assert_eq!(Some("default"), runna["group"].as_str());
+            assert_eq!(Some("standalone"), runna["topology"].as_str());
+            assert_eq!(Some("backend:jogga.default"), runna["binds"][0].as_str());
+            assert_eq!(Some("strict"), runna["binding_mode"].as_str());
+
+            let jogga = read_spec("jogga.spec");
+            assert_eq!(Some("acme/jogga"), jogga["ident"].as_str());
+            assert_eq!(Some("prod"), jogga["group"].as_str());
+            assert_eq!(Some("leader"), jogga["topology"].as_str());
+            assert_eq!(Some(0), jogga["binds"].as_array().map(Vec::len));
+            assert_eq!(Some("relaxed"), jogga["binding_mode"].as_str());
+        }
+
+        #[test]
+        fn workdir_defaults_to_primary_service_directory() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert_eq!(Path::new("/hab/svc/runna"), ctx.image_workdir());
+        }
+
+        #[test]
+        fn workdir_can_be_provided() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            spec.workdir = Some("/srv/runna");
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert_eq!(Path::new("/srv/runna"), ctx.image_workdir());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn binary_from_primary_service_package_is_the_entrypoint() {
+            let rootfs = TempDir::new().unwrap();
+            let ident = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                                 .add_bin("runna-cli")
+                                                                 .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            spec.binary = Some("runna-cli");
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert_eq!(Some(fs::pkg_install_path(&ident, None::<&Path>).join("bin")
+                                                                       .join("runna-cli")
+                                                                       .as_path()),
+                       ctx.entrypoint());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn binary_missing_from_primary_service_package_is_rejected() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .add_bin("runna")
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            spec.binary = Some("runna-cli");
+            let err = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::BinaryNotFound { binary, .. }) => assert_eq!("runna-cli", binary),
+                e => panic!("Expected Error::BinaryNotFound, got {:?}", e),
+            }
+        }
+
+        #[test]
+        fn volumes_are_declared_once() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            spec.volumes = vec!["/hab/svc/runna/data", "/var/log", "/hab/svc/runna/data"];
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert_eq!(vec!["/hab/svc/runna/data", "/var/log"], ctx.volumes());
+        }
+
+        #[test]
+        fn capabilities_are_declared_once() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            spec.capabilities =
+                ["NET_ADMIN", "CAP_NET_RAW", "net_admin"].iter()
+                                                         .map(|c| c.parse().unwrap())
+                                                         .collect();
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            let capabilities: Vec<_> = ctx.capabilities().iter().map(ToString::to_string).collect();
+            assert_eq!(vec!["NET_ADMIN", "NET_RAW"], capabilities);
+        }
+
+        #[test]
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        fn platform_is_only_passed_to_the_build_when_needed() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/runna", rootfs.path()).set_svc(true)
+                                                             .install();
+            let _ = FakePkg::new("acme/armada", rootfs.path()).set_svc(true)
+                                                              .set_target(target::AARCH64_LINUX)
+                                                              .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/runna"];
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+            assert_eq!(None, ctx.build_platform().unwrap());
+
+            spec.target = Some(target::X86_64_LINUX);
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+            assert_eq!("linux/amd64",
+                       ctx.build_platform().unwrap().unwrap().to_string());
+
+            spec.idents_or_archives = vec!["acme/armada"];
+            spec.target = None;
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+            assert_eq!("linux/arm64",
+                       ctx.build_platform().unwrap().unwrap().to_string());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn windows_target_packages_are_rejected() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/iis", rootfs.path()).set_svc(true)
+                                                           .set_target(target::X86_64_WINDOWS)
+                                                           .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/iis"];
+            let err = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::WindowsPackageOnLinux { ident, target }) => {
+                    assert_eq!("acme/iis/1.2.3/21120102121200", ident);
+                    assert_eq!("x86_64-windows", target);
+                }
+                e => panic!("Expected WindowsPackageOnLinux, got {:?}", e),
+            }
+            assert!(err.to_string().contains("Windows containers"), "{}", err);
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn hooks_requiring_a_shell_are_rejected_without_shell() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/redis", rootfs.path()).set_svc(true)
+                                                             .set_run("\x7fELF")
+                                                             .add_hook("init", "#!/bin/sh\n")
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/redis"];
+            spec.no_shell = true;
+            let err = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::HookRequiresShell { ident, hook }) => {
+                    assert_eq!("acme/redis/1.2.3/21120102121200", ident);
+                    assert_eq!("init", hook);
+                }
+                e => panic!("Expected HookRequiresShell, got {:?}", e),
+            }
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn hooks_run_without_a_shell_are_accepted_without_shell() {
+            let rootfs = TempDir::new().unwrap();
+            let _ = FakePkg::new("acme/redis", rootfs.path()).set_svc(true)
+                                                             .set_run("\x7fELF")
+                                                             .add_hook("health-check",
+                                                                       "#!/usr/bin/env python3\n")
+                                                             .install();
+
+            let mut spec = build_spec();
+            spec.idents_or_archives = vec!["acme/redis"];
+            spec.no_shell = true;
+            let ctx = BuildRootContext::from_spec(&spec, rootfs.path()).unwrap();
+
+            assert!(ctx.no_shell());
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn shell_scripts_require_a_shell() {
+            assert!(requires_shell(b"#!/bin/sh\nexec redis-server\n"));
+            assert!(requires_shell(b"#!/usr/bin/env bash\n"));
+            assert!(requires_shell(b"#! /hab/pkgs/core/busybox-static/bin/sh -e\n"));
+            assert!(requires_shell(b"exec redis-server\n"));
+            assert!(requires_shell(b""));
+
+            assert!(!requires_shell(b"\x7fELF\x02\x01\x01"));
+            assert!(!requires_shell(b"#!/usr/bin/env python3\n"));
+            assert!(!requires_shell(b"#!/hab/pkgs/core/ruby/2.6.3/20190531201035/bin/ruby\n"));
+        }
+
+        #[test]
+        fn relative_volumes_are_rejected() {
+            let matches = crate::cli().get_matches_from_safe(vec![&*PROGRAM_NAME,
+                                                                  "--volume",
+                                                                  "data",
+                                                                  "acme/runna"]);
+
+            assert!(matches.is_err());
+        }
+
         #[test]
         fn hab_user_and_group_are_created_even_if_not_explicitly_called_for() {
             let rootfs = TempDir::new().unwrap();
@@ -0,0 +1,115 @@
+//! Build arguments, which are passed to the Docker build and may be referenced by a custom base
+//! image, such as to select the tag of a parameterized base image.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// A build argument, passed to the build's `--build-arg` option.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildArg {
+    pub key:   String,
+    pub value: String,
+}
+
+impl FromStr for BuildArg {
+    type Err = Error;
+
+    /// Parses a `KEY=VALUE` pair, where the key is made of letters, digits, and `_`, and does
+    /// not start with a digit (ex: `BASE_TAG=3.11`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, '=');
+        let key = parts.next().unwrap_or_default();
+        match parts.next() {
+            Some(arg_value) if valid_key(key) => {
+                Ok(BuildArg { key:   key.to_string(),
+                              value: arg_value.to_string(), })
+            }
+            _ => Err(Error::InvalidBuildArg(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for BuildArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+/// Returns the names of the build arguments referenced by an image name, as `$KEY` or `${KEY}`
+/// (ex: `acme/base:${BASE_TAG}`), in the order they are first referenced. A reference may give a
+/// default or alternate value, as in `${BASE_TAG:-latest}`.
+pub fn referenced_keys(image: &str) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut rest = image;
+    while let Some(start) = rest.find('$') {
+        rest = &rest[start + 1..];
+        let braced = rest.starts_with('{');
+        if braced {
+            rest = &rest[1..];
+        }
+        let end = rest.find(|c: char| !is_key_char(c))
+                      .unwrap_or_else(|| rest.len());
+        let key = &rest[..end];
+        rest = &rest[end..];
+        if valid_key(key)
+           && (!braced || rest.starts_with('}') || rest.starts_with(':'))
+           && !keys.iter().any(|k| k == key)
+        {
+            keys.push(key.to_string());
+        }
+    }
+    keys
+}
+
+fn is_key_char(c: char) -> bool { c.is_ascii_alphanumeric() || c == '_' }
+
+/// Returns `true` if the key is made of letters, digits, and `_`, and does not start with a
+/// digit.
+fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+    && !key.starts_with(|c: char| c.is_ascii_digit())
+    && key.chars().all(is_key_char)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn build_arg_parses_key_and_value() {
+        let arg: BuildArg = "BASE_TAG=3.11=slim".parse().unwrap();
+        assert_eq!(arg.key, "BASE_TAG");
+        assert_eq!(arg.value, "3.11=slim");
+        assert_eq!(arg.to_string(), "BASE_TAG=3.11=slim");
+
+        let arg: BuildArg = "http_proxy=".parse().unwrap();
+        assert_eq!(arg.value, "");
+    }
+
+    #[test]
+    fn build_arg_rejects_invalid_keys() {
+        for value in &["NO_VALUE",
+                       "=value",
+                       "1TAG=value",
+                       "BASE-TAG=value",
+                       "BASE TAG=value"]
+        {
+            match value.parse::<BuildArg>() {
+                Err(Error::InvalidBuildArg(v)) => assert_eq!(&v, value),
+                r => panic!("Expected InvalidBuildArg for {}, got {:?}", value, r),
+            }
+        }
+    }
+
+    #[test]
+    fn referenced_keys_are_found_in_image_names() {
+        assert!(referenced_keys("scratch").is_empty());
+        assert_eq!(referenced_keys("$REGISTRY/acme/base:${BASE_TAG}"),
+                   vec!["REGISTRY", "BASE_TAG"]);
+        assert_eq!(referenced_keys("acme/base:${BASE_TAG:-latest}-${BASE_TAG}"),
+                   vec!["BASE_TAG"]);
+        assert!(referenced_keys("acme/base:$1-${}-${TAG").is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+//! Supervisor bundles, which describe several services to run together in one image, along with
+//! the options the Supervisor loads each of them with.
+
+use crate::{error::{Error,
+                    Result},
+            util};
+use habitat_core::{package::PackageIdent,
+                   service::{BindingMode,
+                             ServiceBind,
+                             ServiceGroup}};
+use std::{fs,
+          path::Path,
+          result,
+          str::FromStr};
+use toml::value::{Table,
+                  Value};
+
+/// The directory, relative to the root file system, from which the Supervisor loads the spec
+/// files of its services when it starts.
+const SPECS_PATH: &str = "hab/sup/default/specs";
+
+/// The keys which a service of a bundle may have.
+const SERVICE_KEYS: &[&str] = &["ident", "group", "topology", "binds", "binding_mode"];
+
+/// A service of a bundle and the options it is loaded with.
+#[derive(Debug, PartialEq)]
+pub struct BundleService {
+    pub ident:        PackageIdent,
+    pub group:        String,
+    pub topology:     String,
+    pub binds:        Vec<ServiceBind>,
+    pub binding_mode: BindingMode,
+}
+
+impl BundleService {
+    /// Returns the Supervisor spec file which loads this service.
+    ///
+    /// # Errors
+    ///
+    /// * If the spec file cannot be serialized
+    fn spec(&self) -> Result<String> {
+        let mut spec = Table::new();
+        spec.insert("ident".to_string(), Value::String(self.ident.to_string()));
+        spec.insert("group".to_string(), Value::String(self.group.clone()));
+        spec.insert("topology".to_string(), Value::String(self.topology.clone()));
+        spec.insert("binds".to_string(),
+                    Value::Array(self.binds
+                                     .iter()
+                                     .map(|bind| Value::String(bind.to_string()))
+                                     .collect()));
+        spec.insert("binding_mode".to_string(),
+                    Value::String(self.binding_mode.to_string()));
+        Ok(toml::to_string(&spec)?)
+    }
+}
+
+/// The services of a bundle, in the order they are given.
+#[derive(Debug, PartialEq)]
+pub struct Bundle {
+    services: Vec<BundleService>,
+}
+
+impl Bundle {
+    /// Reads a bundle from a TOML file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read or does not describe a valid bundle
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let invalid = |reason: String| {
+            Error::InvalidBundleSpec { path: path.display().to_string(),
+                                       reason }
+        };
+        let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        Ok(contents.parse().map_err(invalid)?)
+    }
+
+    /// Returns the services of the bundle.
+    pub fn services(&self) -> &[BundleService] { &self.services }
+
+    /// Returns the package identifiers of the services, in the form they are installed with.
+    pub fn idents(&self) -> Vec<String> {
+        self.services
+            .iter()
+            .map(|service| service.ident.to_string())
+            .collect()
+    }
+
+    /// Writes a Supervisor spec file for each service into the root file system, so that the
+    /// Supervisor loads every service when it starts.
+    ///
+    /// # Errors
+    ///
+    /// * If a spec file cannot be written
+    pub fn write_specs<P: AsRef<Path>>(&self, rootfs: P) -> Result<()> {
+        let specs = rootfs.as_ref().join(SPECS_PATH);
+        for service in &self.services {
+            util::write_file(specs.join(format!("{}.spec", service.ident.name)),
+                             &service.spec()?)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bundle {
+    type Err = String;
+
+    /// Parses a bundle with a `[[service]]` table for each service, of which only the `ident`
+    /// is required (ex: `ident = "core/redis"`). A service is loaded into the `default` group
+    /// with the `standalone` topology and the `strict` binding mode unless given a `group`,
+    /// `topology`, or `binding_mode`, and may have `binds` of the form `NAME:SERVICE.GROUP`.
+    fn from_str(contents: &str) -> result::Result<Self, Self::Err> {
+        let mut table: Table = toml::from_str(contents).map_err(|e| e.to_string())?;
+        let services = match table.remove("service") {
+            Some(Value::Array(services)) if !services.is_empty() => services,
+            Some(_) => return Err("'service' must be an array of tables".to_string()),
+            None => return Err("at least one [[service]] is required".to_string()),
+        };
+        if let Some(key) = table.keys().next() {
+            return Err(format!("unknown key '{}'", key));
+        }
+
+        let mut bundle = Bundle { services: Vec::new(), };
+        for service in services {
+            let service = match service {
+                Value::Table(service) => parse_service(service)?,
+                _ => return Err("'service' must be an array of tables".to_string()),
+            };
+            // The Supervisor names spec files, and so services, after their packages
+            if bundle.services
+                     .iter()
+                     .any(|s| s.ident.name == service.ident.name)
+            {
+                return Err(format!("more than one service runs the '{}' package",
+                                   service.ident.name));
+            }
+            bundle.services.push(service);
+        }
+        Ok(bundle)
+    }
+}
+
+/// Parses and validates the table of a service.
+fn parse_service(mut table: Table) -> result::Result<BundleService, String> {
+    if let Some(key) = table.keys()
+                            .find(|key| !SERVICE_KEYS.contains(&key.as_str()))
+    {
+        return Err(format!("unknown key '{}' in [[service]]", key));
+    }
+    let ident = match table.remove("ident") {
+        Some(Value::String(ident)) => {
+            PackageIdent::from_str(&ident).map_err(|_| {
+                                              format!("invalid package identifier '{}'", ident)
+                                          })?
+        }
+        Some(_) => return Err("'ident' must be a string".to_string()),
+        None => return Err("every [[service]] requires an 'ident'".to_string()),
+    };
+    let group = string_value(&mut table, "group")?.unwrap_or_else(|| "default".to_string());
+    ServiceGroup::from_str(&format!("{}.{}", ident.name, group)).map_err(|_| {
+                                                                    format!("invalid group '{}' \
+                                                                             for {}",
+                                                                            group, ident)
+                                                                })?;
+    let topology =
+        string_value(&mut table, "topology")?.unwrap_or_else(|| "standalone".to_string());
+    if topology != "standalone" && topology != "leader" {
+        return Err(format!("invalid topology '{}' for {}; topologies are \
+                            standalone or leader",
+                           topology, ident));
+    }
+    let binds = match table.remove("binds") {
+        Some(Value::Array(binds)) => {
+            binds.iter()
+                 .map(|bind| {
+                     bind.as_str()
+                         .and_then(|b| ServiceBind::from_str(b).ok())
+                         .ok_or_else(|| {
+                             format!("invalid bind {} for {}; binds have the form \
+                                      NAME:SERVICE.GROUP",
+                                     bind, ident)
+                         })
+                 })
+                 .collect::<result::Result<_, _>>()?
+        }
+        Some(_) => return Err(format!("'binds' for {} must be an array of strings", ident)),
+        None => Vec::new(),
+    };
+    let binding_mode = match string_value(&mut table, "binding_mode")? {
+        Some(mode) => {
+            BindingMode::from_str(&mode).map_err(|_| {
+                                            format!("invalid binding mode '{}' for {}; binding \
+                                                     modes are strict or relaxed",
+                                                    mode, ident)
+                                        })?
+        }
+        None => BindingMode::default(),
+    };
+    Ok(BundleService { ident,
+                       group,
+                       topology,
+                       binds,
+                       binding_mode })
+}
+
+/// Removes an optional string value from a table.
+fn string_value(table: &mut Table, key: &str) -> result::Result<Option<String>, String> {
+    match table.remove(key) {
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(format!("'{}' must be a string", key)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bundle_parses_services_with_defaults() {
+        let bundle: Bundle = r#"
+            [[service]]
+            ident = "core/redis"
+
+            [[service]]
+            ident = "acme/app/1.0.0"
+            group = "prod"
+            topology = "leader"
+            binds = ["cache:redis.default"]
+            binding_mode = "relaxed"
+            "#.parse()
+                             .unwrap();
+
+        let redis = &bundle.services()[0];
+        assert_eq!(PackageIdent::from_str("core/redis").unwrap(), redis.ident);
+        assert_eq!("default", redis.group);
+        assert_eq!("standalone", redis.topology);
+        assert!(redis.binds.is_empty());
+        assert_eq!(BindingMode::Strict, redis.binding_mode);
+
+        let app = &bundle.services()[1];
+        assert_eq!("prod", app.group);
+        assert_eq!("leader", app.topology);
+        assert_eq!(vec![ServiceBind::from_str("cache:redis.default").unwrap()],
+                   app.binds);
+        assert_eq!(BindingMode::Relaxed, app.binding_mode);
+        assert_eq!(vec!["core/redis", "acme/app/1.0.0"], bundle.idents());
+    }
+
+    #[test]
+    fn bundle_rejects_invalid_specs() {
+        for contents in &["",
+                          "service = \"core/redis\"",
+                          "[[service]]\ngroup = \"prod\"",
+                          "[[service]]\nident = \"redis\"",
+                          "[[service]]\nident = \"core/redis\"\ntopology = \"mesh\"",
+                          "[[service]]\nident = \"core/redis\"\nbinds = [\"cache\"]",
+                          "[[service]]\nident = \"core/redis\"\nbinding_mode = \"lax\"",
+                          "[[service]]\nident = \"core/redis\"\ngroup = \"a.b\"",
+                          "[[service]]\nident = \"core/redis\"\nchannel = \"stable\"",
+                          "[[service]]\nident = \"core/redis\"\n[[service]]\nident = \
+                           \"acme/redis\"",
+                          "[[service]\nident = \"core/redis\""]
+        {
+            assert!(contents.parse::<Bundle>().is_err(),
+                    "Expected an error for {}",
+                    contents);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Types for representing the Linux capabilities a service requires, which are recorded in an
+//! image label for deployment tooling to read.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// The names of the Linux capabilities, without their `CAP_` prefix.
+const CAPABILITY_NAMES: &[&str] = &["CHOWN",
+                                    "DAC_OVERRIDE",
+                                    "DAC_READ_SEARCH",
+                                    "FOWNER",
+                                    "FSETID",
+                                    "KILL",
+                                    "SETGID",
+                                    "SETUID",
+                                    "SETPCAP",
+                                    "LINUX_IMMUTABLE",
+                                    "NET_BIND_SERVICE",
+                                    "NET_BROADCAST",
+                                    "NET_ADMIN",
+                                    "NET_RAW",
+                                    "IPC_LOCK",
+                                    "IPC_OWNER",
+                                    "SYS_MODULE",
+                                    "SYS_RAWIO",
+                                    "SYS_CHROOT",
+                                    "SYS_PTRACE",
+                                    "SYS_PACCT",
+                                    "SYS_ADMIN",
+                                    "SYS_BOOT",
+                                    "SYS_NICE",
+                                    "SYS_RESOURCE",
+                                    "SYS_TIME",
+                                    "SYS_TTY_CONFIG",
+                                    "MKNOD",
+                                    "LEASE",
+                                    "AUDIT_WRITE",
+                                    "AUDIT_CONTROL",
+                                    "SETFCAP",
+                                    "MAC_OVERRIDE",
+                                    "MAC_ADMIN",
+                                    "SYSLOG",
+                                    "WAKE_ALARM",
+                                    "BLOCK_SUSPEND",
+                                    "AUDIT_READ",
+                                    "PERFMON",
+                                    "BPF",
+                                    "CHECKPOINT_RESTORE"];
+
+/// A Linux capability, named as in a Kubernetes security context (ex: `NET_ADMIN`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capability(String);
+
+impl FromStr for Capability {
+    type Err = Error;
+
+    /// Parses a capability name, with or without its `CAP_` prefix and in any case (ex:
+    /// `CAP_NET_ADMIN` or `net_admin`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let name = value.to_uppercase();
+        let name = if name.starts_with("CAP_") {
+            &name[4..]
+        } else {
+            &name
+        };
+        if CAPABILITY_NAMES.contains(&name) {
+            Ok(Capability(name.to_string()))
+        } else {
+            Err(Error::InvalidCapability(value.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.0) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn capability_parses_names() {
+        assert_eq!("NET_ADMIN".parse::<Capability>().unwrap().to_string(),
+                   "NET_ADMIN");
+        assert_eq!("CAP_SYS_PTRACE".parse::<Capability>().unwrap().to_string(),
+                   "SYS_PTRACE");
+        assert_eq!("net_bind_service".parse::<Capability>()
+                                     .unwrap()
+                                     .to_string(),
+                   "NET_BIND_SERVICE");
+    }
+
+    #[test]
+    fn invalid_capabilities_are_rejected() {
+        assert!("".parse::<Capability>().is_err());
+        assert!("CAP_".parse::<Capability>().is_err());
+        assert!("ALL".parse::<Capability>().is_err());
+        assert!("NET_ADMINISTRATOR".parse::<Capability>().is_err());
+        assert!("CAP_CAP_NET_ADMIN".parse::<Capability>().is_err());
+    }
+}
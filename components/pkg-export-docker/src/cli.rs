@@ -1,7 +1,22 @@
-use crate::RegistryType;
+use crate::{annotation::Annotation,
+            build_arg::BuildArg,
+            capability::Capability,
+            docker,
+            env_vars::EnvVar,
+            hosts::EtcHostsEntry,
+            init::InitSystem,
+            platform::Platform,
+            size::{ByteSize,
+                   SwapLimit},
+            stop_signal::StopSignal,
+            userns::UsernsRemap,
+            Naming,
+            RegistryType};
 use clap::{App,
            Arg};
-use habitat_core::package::PackageIdent;
+use habitat_core::package::{PackageIdent,
+                            PackageTarget};
+use habitat_http_client::SSL_CERT_FILE_ENVVAR;
 use std::{path::Path,
           result,
           str::FromStr};
@@ -32,7 +47,8 @@ impl<'a, 'b> Cli<'a, 'b> {
               (author: "\nAuthors: The Habitat Maintainers <humans@habitat.sh>\n\n")
               (@arg IMAGE_NAME: --("image-name") -i +takes_value
                   "Image name (default: \"{{pkg_origin}}/{{pkg_name}}\" supports: \
-                   {{pkg_origin}}, {{pkg_name}}, {{pkg_version}}, {{pkg_release}}, {{channel}})")
+                   {{pkg_origin}}, {{pkg_name}}, {{pkg_version}}, {{pkg_release}}, {{channel}}, \
+                   {origin}, {name}, {version}, {release})")
               ), }
     }
 
@@ -73,6 +89,24 @@ impl<'a, 'b> Cli<'a, 'b> {
                          /home/ore-hab-sup-0.39.1-20171118011657-x86_64-linux.hart) to install \
                          (default: core/hab-sup)",
                     ),
+            )
+            .arg(
+                Arg::with_name("BUSYBOX_PKG")
+                    .long("busybox-pkg")
+                    .value_name("BUSYBOX_PKG")
+                    .help(
+                        "Busybox package identifier (ex: core/busybox-static) providing the \
+                         image's shell on Linux (default: core/busybox-static)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("CACERTS_PKG")
+                    .long("cacerts-pkg")
+                    .value_name("CACERTS_PKG")
+                    .help(
+                        "CA certificates package identifier (ex: core/cacerts) linked into \
+                         /etc/ssl (default: core/cacerts)",
+                    ),
             );
 
         Cli { app }
@@ -85,11 +119,12 @@ impl<'a, 'b> Cli<'a, 'b> {
                 Arg::with_name("BLDR_URL")
                     .long("url")
                     .short("u")
+                    .alias("bldr-url")
                     .value_name("BLDR_URL")
                     .validator(valid_url)
                     .help(
                         "Install packages from Builder at the specified URL \
-                         (default: https://bldr.habitat.sh)",
+                         (default: $HAB_BLDR_URL or https://bldr.habitat.sh)",
                     ),
             )
             .arg(
@@ -97,6 +132,7 @@ impl<'a, 'b> Cli<'a, 'b> {
                     .long("channel")
                     .short("c")
                     .value_name("CHANNEL")
+                    .validator(valid_channel)
                     .help("Install packages from the specified release channel (default: stable)"),
             )
             .arg(
@@ -113,6 +149,7 @@ impl<'a, 'b> Cli<'a, 'b> {
                 Arg::with_name("BASE_PKGS_CHANNEL")
                     .long("base-pkgs-channel")
                     .value_name("BASE_PKGS_CHANNEL")
+                    .validator(valid_channel)
                     .help(
                         "Install base packages from the specified release channel \
                          (default: stable)",
@@ -146,6 +183,16 @@ impl<'a, 'b> Cli<'a, 'b> {
                         "Do not tag image with :\"{{pkg_version}}-{{pkg_release}}\" (default: no)",
                     ),
             )
+            .arg(
+                Arg::with_name("TAG_VERSION_FORMAT")
+                    .long("tag-version-format")
+                    .value_name("FMT")
+                    .validator(valid_tag_version_format)
+                    .help(
+                        "Format of the version-release tag, with {version} and {release} \
+                         placeholders (default: {version}-{release})",
+                    ),
+            )
             .arg(
                 Arg::with_name("TAG_VERSION")
                     .long("tag-version")
@@ -178,6 +225,14 @@ impl<'a, 'b> Cli<'a, 'b> {
                         "Tag image with additional custom tag (supports: {{pkg_origin}}, \
                          {{pkg_name}}, {{pkg_version}}, {{pkg_release}}, {{channel}})",
                     ),
+            )
+            .arg(
+                Arg::with_name("TAG_DIGEST_SHORT")
+                    .long("tag-digest-short")
+                    .help(
+                        "Tag image with :\"sha-{{digest}}\", using the first 12 characters of \
+                         the image digest (default: no)",
+                    ),
             );
 
         Cli { app }
@@ -190,7 +245,6 @@ impl<'a, 'b> Cli<'a, 'b> {
                 Arg::with_name("PUSH_IMAGE")
                     .long("push-image")
                     .conflicts_with("NO_PUSH_IMAGE")
-                    .requires_all(&["REGISTRY_USERNAME", "REGISTRY_PASSWORD"])
                     .help("Push image to remote registry (default: no)"),
             )
             .arg(
@@ -204,9 +258,9 @@ impl<'a, 'b> Cli<'a, 'b> {
                     .long("username")
                     .short("U")
                     .value_name("REGISTRY_USERNAME")
-                    .requires("REGISTRY_PASSWORD")
                     .help(
-                        "Remote registry username, required for pushing image to remote registry",
+                        "Remote registry username, required for pushing image to remote registry \
+                         (default: the value of HAB_REGISTRY_USERNAME)",
                     ),
             )
             .arg(
@@ -214,9 +268,18 @@ impl<'a, 'b> Cli<'a, 'b> {
                     .long("password")
                     .short("P")
                     .value_name("REGISTRY_PASSWORD")
-                    .requires("REGISTRY_USERNAME")
                     .help(
-                        "Remote registry password, required for pushing image to remote registry",
+                        "Remote registry password, required for pushing image to remote registry \
+                         (default: the value of HAB_REGISTRY_PASSWORD)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("INTERACTIVE_PASSWORD_PROMPT")
+                    .long("interactive-password-prompt")
+                    .conflicts_with("REGISTRY_PASSWORD")
+                    .help(
+                        "Prompt for the remote registry password, without echoing it, when it is \
+                         not provided and the session is interactive",
                     ),
             )
             .arg(
@@ -233,11 +296,102 @@ impl<'a, 'b> Cli<'a, 'b> {
                     // making a mistake when inputing an ECR URL
                     .required_if("REGISTRY_TYPE", "amazon")
                     .required_if("REGISTRY_TYPE", "azure")
+                    .required_if("REGISTRY_TYPE", "custom")
                     .long("registry-url")
                     .short("G")
                     .value_name("REGISTRY_URL")
                     .help("Remote registry url"),
             )
+            .arg(
+                Arg::with_name("TOKEN_ENDPOINT")
+                    .long("token-endpoint")
+                    .value_name("TOKEN_ENDPOINT")
+                    .validator(valid_url)
+                    .help(
+                        "URL of a custom registry's token endpoint, which exchanges the registry \
+                         username and password for a token (default: use the password)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("TOKEN_JSON_PATH")
+                    .long("token-json-path")
+                    .value_name("TOKEN_JSON_PATH")
+                    .requires("TOKEN_ENDPOINT")
+                    .help(
+                        "Dot-separated path to the token in the token endpoint's JSON response \
+                         (default: token)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("REGISTRY_TIMEOUT")
+                    .long("registry-timeout")
+                    .value_name("SECONDS")
+                    .default_value("30")
+                    .validator(valid_timeout)
+                    .help(
+                        "Seconds to wait when connecting to a registry's API, and for each \
+                         response, before giving up",
+                    ),
+            )
+            .arg(
+                Arg::with_name("REGISTRY_CLIENT_CERT")
+                    .long("registry-client-cert")
+                    .value_name("PATH")
+                    .validator(file_exists)
+                    .requires_all(&["REGISTRY_CLIENT_KEY", "PUSH_IMAGE"])
+                    .help(
+                        "A PEM-encoded client certificate to present to registries which require \
+                         mutual TLS. It is installed as the registry's client.cert in the \
+                         certs.d directory of the Docker configuration used for the push, and \
+                         also presented to the Amazon ECR API when requesting an authorization \
+                         token",
+                    ),
+            )
+            .arg(
+                Arg::with_name("REGISTRY_CLIENT_KEY")
+                    .long("registry-client-key")
+                    .value_name("PATH")
+                    .validator(file_exists)
+                    .requires_all(&["REGISTRY_CLIENT_CERT", "PUSH_IMAGE"])
+                    .help("The PEM-encoded PKCS #8 private key of --registry-client-cert"),
+            )
+            .arg(
+                Arg::with_name("ECR_TOKEN_TIMEOUT")
+                    .long("ecr-token-timeout")
+                    .value_name("SECONDS")
+                    .default_value("30")
+                    .validator(valid_timeout)
+                    .help(
+                        "Seconds to wait for Amazon ECR to issue an authorization token, \
+                         including resolving the credentials, before giving up",
+                    ),
+            )
+            .arg(
+                Arg::with_name("FAIL_ON_EXISTING_TAG")
+                    .long("fail-on-existing-tag")
+                    .requires("PUSH_IMAGE")
+                    .help(
+                        "Do not push the image if any of its tags, other than \"latest\", \
+                         already exist in the remote registry (default: no)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("RESUME_PUSH")
+                    .long("resume-push")
+                    .requires("PUSH_IMAGE")
+                    .help(
+                        "Skip tags which a previous push of the same image already published, \
+                         as recorded in results/last_docker_push.json (default: no)",
+                    ),
+            )
+            .arg(
+                Arg::with_name("PUSH_CONCURRENCY")
+                    .long("push-concurrency")
+                    .value_name("N")
+                    .default_value("4")
+                    .validator(valid_push_concurrency)
+                    .help("The most tags of the image to push to the remote registry at once"),
+            )
             // Cleanup
             .arg(
                 Arg::with_name("RM_IMAGE")
@@ -248,6 +402,101 @@ impl<'a, 'b> Cli<'a, 'b> {
         Cli { app }
     }
 
+    pub fn add_scan_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("SCAN_COMMAND").value_name("CMD")
+                                                         .long("scan-command")
+                                                         .help("A command, run through the shell, \
+                                                                which scans the built image \
+                                                                before it is pushed. The image \
+                                                                reference is appended as the \
+                                                                final argument and the export \
+                                                                fails if the command exits \
+                                                                unsuccessfully"))
+                      .arg(Arg::with_name("SCAN_ALLOW_FAILURE").long("scan-allow-failure")
+                                                               .requires("SCAN_COMMAND")
+                                                               .help("Continue the export when \
+                                                                      the scan command exits \
+                                                                      unsuccessfully"));
+
+        Cli { app }
+    }
+
+    pub fn add_smoke_test_args(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("SMOKE_TEST").long("smoke-test")
+                                                 .conflicts_with_all(&["RENDER_DOCKERFILE",
+                                                                       "CLOSURE_ONLY",
+                                                                       "LIST_TAGS"])
+                                                 .help("Run the built image briefly before it is \
+                                                        pushed, failing the export if the \
+                                                        container exits or its health check \
+                                                        reports unhealthy"))
+                .arg(Arg::with_name("SMOKE_TEST_ARGS").value_name("ARGS")
+                                                      .long("smoke-test-args")
+                                                      .requires("SMOKE_TEST")
+                                                      .allow_hyphen_values(true)
+                                                      .validator(valid_run_args)
+                                                      .help("Arguments, in shell-words form, \
+                                                             passed to docker run for the smoke \
+                                                             test (ex: \"--env \
+                                                             HAB_LICENSE=accept --publish 8080\")"))
+                .arg(Arg::with_name("SMOKE_TEST_TIMEOUT").value_name("SECONDS")
+                                                         .long("smoke-test-timeout")
+                                                         .default_value("10")
+                                                         .validator(valid_timeout)
+                                                         .help("Seconds the container must stay \
+                                                                up during the smoke test, \
+                                                                unless its health check reports \
+                                                                healthy sooner"));
+
+        Cli { app }
+    }
+
+    pub fn add_dockerfile_policy_args(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("DOCKERFILE_POLICY").value_name("CMD")
+                                                        .long("dockerfile-policy")
+                                                        .help("A command, run through the shell, \
+                                                               which checks the generated \
+                                                               Dockerfile before the image is \
+                                                               built. The Dockerfile is given on \
+                                                               stdin and its path is appended as \
+                                                               the final argument, and the \
+                                                               export fails if the command exits \
+                                                               unsuccessfully"))
+                .arg(Arg::with_name("POLICY_ALLOW_FAILURE").long("policy-allow-failure")
+                                                           .requires("DOCKERFILE_POLICY")
+                                                           .help("Continue the export when the \
+                                                                  Dockerfile policy command \
+                                                                  exits unsuccessfully"));
+
+        Cli { app }
+    }
+
+    pub fn add_sign_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("SIGN_COMMAND").value_name("CMD")
+                                                         .long("sign-command")
+                                                         .requires("PUSH_IMAGE")
+                                                         .help("A command, run through the shell, \
+                                                                which signs the image once it is \
+                                                                pushed (ex: cosign sign). The \
+                                                                image's digest-pinned reference \
+                                                                is appended as the final argument \
+                                                                and the export fails if the \
+                                                                command exits unsuccessfully"))
+                      .arg(Arg::with_name("SIGN_ALLOW_FAILURE").long("sign-allow-failure")
+                                                               .requires("SIGN_COMMAND")
+                                                               .help("Continue the export when \
+                                                                      the sign command exits \
+                                                                      unsuccessfully"));
+
+        Cli { app }
+    }
+
     pub fn add_pkg_ident_arg(self, options: PkgIdentArgOptions) -> Self {
         let help = if options.multiple {
             "One or more Habitat package identifiers (ex: acme/redis) and/or filepaths to a \
@@ -260,20 +509,517 @@ impl<'a, 'b> Cli<'a, 'b> {
         let app =
             self.app
                 .arg(Arg::with_name("PKG_IDENT_OR_ARTIFACT").value_name("PKG_IDENT_OR_ARTIFACT")
-                                                            .required(true)
+                                                            .required_unless("BUNDLE_SPEC")
                                                             .multiple(options.multiple)
                                                             .help(help));
 
         Cli { app }
     }
 
+    pub fn add_lock_file_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("PKG_LOCK_FILE").value_name("PATH")
+                                                          .long("pkg-lock-file")
+                                                          .validator(file_exists)
+                                                          .help("A lock file, as written by \
+                                                                 --emit-lock-file, whose exact \
+                                                                 packages are installed in place \
+                                                                 of resolving the latest packages \
+                                                                 in the channel"))
+                      .arg(Arg::with_name("EMIT_LOCK_FILE").value_name("PATH")
+                                                           .long("emit-lock-file")
+                                                           .help("Write the fully qualified \
+                                                                  identifiers of every package \
+                                                                  resolved into the image, direct \
+                                                                  and transitive, to PATH for use \
+                                                                  with --pkg-lock-file"));
+
+        Cli { app }
+    }
+
+    pub fn add_strict_ident_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("STRICT_IDENT").long("strict-ident")
+                                                         .help("Require every package identifier \
+                                                                to be fully qualified (ex: \
+                                                                core/redis/3.0.7/20200101000000) \
+                                                                for reproducible builds. Habitat \
+                                                                Artifact filepaths are always \
+                                                                accepted"));
+
+        Cli { app }
+    }
+
+    pub fn add_warn_as_error_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("WARN_AS_ERROR").long("warn-as-error")
+                                                          .alias("strict")
+                                                          .help("Fail once the export completes \
+                                                                 if it emitted any warnings, \
+                                                                 listing them (default: no)"));
+
+        Cli { app }
+    }
+
+    pub fn add_collect_errors_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("COLLECT_ERRORS").long("collect-errors")
+                                                           .help("Try to install every package \
+                                                                  and report all failures \
+                                                                  together, rather than stopping \
+                                                                  at the first failure"));
+
+        Cli { app }
+    }
+
+    pub fn add_target_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("TARGET").value_name("TARGET")
+                                                       .long("target")
+                                                       .validator(valid_target)
+                                                       .help("The package target (ex: \
+                                                              x86_64-linux) which determines \
+                                                              the image's OS and architecture \
+                                                              (default: the primary service \
+                                                              package's target)"));
+
+        Cli { app }
+    }
+
     pub fn add_memory_arg(self) -> Self {
         let app = self.app
                       .arg(Arg::with_name("MEMORY_LIMIT").value_name("MEMORY_LIMIT")
                                                          .long("memory")
                                                          .short("m")
+                                                         .validator(valid_size)
                                                          .help("Memory limit passed to docker \
-                                                                build's --memory arg (ex: 2bg)"));
+                                                                build's --memory arg (ex: 2gb)"))
+                      .arg(Arg::with_name("MEMORY_SWAP").value_name("MEMORY_SWAP")
+                                                        .long("memory-swap")
+                                                        .requires("MEMORY_LIMIT")
+                                                        .allow_hyphen_values(true)
+                                                        .validator(valid_swap_limit)
+                                                        .help("Limit on memory plus swap passed \
+                                                               to docker build's --memory-swap \
+                                                               arg, which must be at least the \
+                                                               --memory limit, or -1 for \
+                                                               unlimited swap (ex: 4gb)"));
+
+        Cli { app }
+    }
+
+    pub fn add_max_image_size_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("MAX_IMAGE_SIZE").value_name("SIZE")
+                                                           .long("max-image-size")
+                                                           .validator(valid_size)
+                                                           .help("Fail the export if the built \
+                                                                  image is larger than this size \
+                                                                  (ex: 500m)"));
+
+        Cli { app }
+    }
+
+    pub fn add_cache_from_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("CACHE_FROM").value_name("IMAGE")
+                                                       .long("cache-from")
+                                                       .multiple(true)
+                                                       .number_of_values(1)
+                                                       .help("An image to use as a source of \
+                                                              cached layers, passed to docker \
+                                                              build's --cache-from arg (ex: \
+                                                              acme/redis:latest)"));
+
+        Cli { app }
+    }
+
+    pub fn add_annotation_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("ANNOTATION").value_name("KEY=VALUE")
+                                                       .long("annotation")
+                                                       .multiple(true)
+                                                       .number_of_values(1)
+                                                       .validator(valid_annotation)
+                                                       .help("An annotation for the image \
+                                                              manifest (ex: \
+                                                              org.opencontainers.image.source=\
+                                                              https://github.com/acme/app). The \
+                                                              Docker daemon does not support \
+                                                              annotations, so they are only \
+                                                              applied to the Kubernetes \
+                                                              Deployment written by \
+                                                              --emit-k8s, with a warning"));
+
+        Cli { app }
+    }
+
+    pub fn add_build_arg_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("BUILD_ARG").value_name("KEY=VALUE")
+                                                      .long("build-arg")
+                                                      .multiple(true)
+                                                      .number_of_values(1)
+                                                      .validator(valid_build_arg)
+                                                      .help("A build arg passed to docker build's \
+                                                             --build-arg arg, which a custom \
+                                                             --base-image may reference as $KEY \
+                                                             or ${KEY} (ex: --base-image \
+                                                             'acme/base:${BASE_TAG}' --build-arg \
+                                                             BASE_TAG=3.11)"));
+
+        Cli { app }
+    }
+
+    pub fn add_engine_opt_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("ENGINE_OPT").value_name("ARG")
+                                                           .long("engine-opt")
+                                                           .multiple(true)
+                                                           .number_of_values(1)
+                                                           .allow_hyphen_values(true)
+                                                           .help("An argument passed verbatim \
+                                                                  to docker build. These are \
+                                                                  not validated; repeat the \
+                                                                  option for each argument (ex: \
+                                                                  --engine-opt --network \
+                                                                  --engine-opt host). Global \
+                                                                  options for every docker \
+                                                                  invocation can be set in \
+                                                                  HAB_CONTAINER_ENGINE_OPTS"));
+
+        Cli { app }
+    }
+
+    pub fn add_render_dockerfile_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("RENDER_DOCKERFILE").value_name("PATH")
+                                                              .long("render-dockerfile")
+                                                              .conflicts_with_all(&["PUSH_IMAGE",
+                                                                                    "RM_IMAGE"])
+                                                              .help("Write the generated \
+                                                                     Dockerfile to PATH and exit \
+                                                                     without building an image"));
+
+        Cli { app }
+    }
+
+    pub fn add_list_tags_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("LIST_TAGS").long("list-tags")
+                                                      .conflicts_with_all(&["PUSH_IMAGE",
+                                                                            "RM_IMAGE",
+                                                                            "RENDER_DOCKERFILE"])
+                                                      .help("Print the image's name:tag \
+                                                             references, one per line, and exit \
+                                                             without building an image. Requires \
+                                                             a fully qualified package identifier \
+                                                             or a Habitat artifact"));
+
+        Cli { app }
+    }
+
+    pub fn add_closure_only_arg(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("CLOSURE_ONLY").value_name("PATH")
+                                                   .long("closure-only")
+                                                   .conflicts_with_all(&["PUSH_IMAGE",
+                                                                         "RM_IMAGE",
+                                                                         "RENDER_DOCKERFILE",
+                                                                         "LIST_TAGS"])
+                                                   .help("Resolve and download the packages, \
+                                                          write a manifest of every package \
+                                                          with the checksum and size of its \
+                                                          artifact to PATH, and exit without \
+                                                          building an image"));
+
+        Cli { app }
+    }
+
+    pub fn add_ignore_file_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("IGNORE_FILE").value_name("PATH")
+                                                        .long("ignore-file")
+                                                        .validator(file_exists)
+                                                        .help("A file of .dockerignore-style glob \
+                                                               patterns, one per line, for paths \
+                                                               to exclude from the image's root \
+                                                               filesystem. Lines starting with # \
+                                                               are comments and patterns starting \
+                                                               with ! re-include paths"));
+
+        Cli { app }
+    }
+
+    pub fn add_ca_bundle_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("CA_BUNDLE").value_name("PATH")
+                                                          .long("ca-bundle")
+                                                          .env(SSL_CERT_FILE_ENVVAR)
+                                                          .validator(file_exists)
+                                                          .help("A file of PEM-encoded CA \
+                                                                 certificates, such as that of \
+                                                                 a TLS-intercepting proxy, to \
+                                                                 trust when downloading \
+                                                                 packages from Builder and \
+                                                                 calling registry APIs"));
+
+        Cli { app }
+    }
+
+    pub fn add_build_root_dir_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("BUILD_ROOT_DIR").value_name("PATH")
+                                                           .long("build-root-dir")
+                                                           .env("HAB_TMP")
+                                                           .validator(dir_exists)
+                                                           .help("A writable directory in which \
+                                                                  to stage the image's root \
+                                                                  filesystem (default: the system \
+                                                                  temporary directory, which is \
+                                                                  usually $TMPDIR)"));
+
+        Cli { app }
+    }
+
+    pub fn add_provenance_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("PROVENANCE").value_name("PATH")
+                                                           .long("provenance")
+                                                           .help("Write a JSON provenance \
+                                                                  document describing how the \
+                                                                  image was built to this path, \
+                                                                  relative to the results \
+                                                                  directory of the build report"));
+
+        Cli { app }
+    }
+
+    pub fn add_digest_file_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("DIGEST_FILE").value_name("PATH")
+                                                        .long("digest-file")
+                                                        .conflicts_with_all(&["RENDER_DOCKERFILE",
+                                                                              "CLOSURE_ONLY",
+                                                                              "LIST_TAGS"])
+                                                        .help("Write the digest of the image (ex: \
+                                                               sha256:...) to PATH once it is \
+                                                               built and, if it is pushed, \
+                                                               pushed. The digest of a pushed \
+                                                               image is that of its manifest in \
+                                                               the remote registry"));
+
+        Cli { app }
+    }
+
+    pub fn add_emit_k8s_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("EMIT_K8S").value_name("PATH")
+                                                         .long("emit-k8s")
+                                                         .help("Write a starter Kubernetes \
+                                                                Deployment manifest which runs \
+                                                                the image to this path, \
+                                                                relative to the results \
+                                                                directory of the build report"));
+
+        Cli { app }
+    }
+
+    pub fn add_workdir_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("WORKDIR").value_name("PATH")
+                                                        .long("workdir")
+                                                        .validator(absolute_path)
+                                                        .help("The absolute path of the working \
+                                                               directory for the image's \
+                                                               entrypoint (default: the primary \
+                                                               service's directory, \
+                                                               /hab/svc/<name>)"));
+
+        Cli { app }
+    }
+
+    pub fn add_binary_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("BINARY").value_name("NAME")
+                                                       .long("binary")
+                                                       .validator(valid_binary_name)
+                                                       .help("The name of a program in the \
+                                                              primary service package's bin \
+                                                              path to use as the image's \
+                                                              entrypoint, rather than the \
+                                                              Supervisor"));
+
+        Cli { app }
+    }
+
+    pub fn add_bundle_spec_arg(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("BUNDLE_SPEC").value_name("PATH")
+                                                  .long("bundle-spec")
+                                                  .validator(file_exists)
+                                                  .conflicts_with_all(&["BINARY",
+                                                                        "PKG_IDENT_OR_ARTIFACT"])
+                                                  .help("A TOML file with a [[service]] table \
+                                                         for each package to run under the \
+                                                         Supervisor, giving its ident and \
+                                                         optionally its group, topology, binds, \
+                                                         and binding_mode, rather than packages \
+                                                         given as arguments"));
+
+        Cli { app }
+    }
+
+    pub fn add_volume_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("VOLUME").value_name("PATH")
+                                                       .long("volume")
+                                                       .multiple(true)
+                                                       .number_of_values(1)
+                                                       .validator(absolute_path)
+                                                       .help("The absolute path of a mount \
+                                                              point to declare as a volume in \
+                                                              the image; repeat the option for \
+                                                              each volume"));
+
+        Cli { app }
+    }
+
+    pub fn add_secret_mount_arg(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("SECRET_MOUNT").value_name("PATH")
+                                                   .long("secret-mount")
+                                                   .multiple(true)
+                                                   .number_of_values(1)
+                                                   .validator(absolute_path)
+                                                   .help("The absolute path of a file a secret \
+                                                          is mounted onto at runtime, which is \
+                                                          created empty and readable only by \
+                                                          its owner, and recorded in the \
+                                                          sh.habitat.secret-mounts label; \
+                                                          repeat the option for each secret"));
+
+        Cli { app }
+    }
+
+    pub fn add_env_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("ENV").value_name("KEY=VALUE")
+                                                .long("env")
+                                                .multiple(true)
+                                                .number_of_values(1)
+                                                .validator(valid_env_var)
+                                                .help("Set an environment variable in the image; \
+                                                       repeat the option for each variable. Takes \
+                                                       precedence over --env-file"))
+                      .arg(Arg::with_name("ENV_FILE").value_name("PATH")
+                                                     .long("env-file")
+                                                     .validator(file_exists)
+                                                     .help("A dotenv-style file of KEY=VALUE \
+                                                            lines for environment variables to \
+                                                            set in the image. Lines starting with \
+                                                            # are comments and values may be \
+                                                            quoted"));
+
+        Cli { app }
+    }
+
+    pub fn add_render_config_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("RENDER_CONFIG").value_name("TOML")
+                                                          .long("render-config")
+                                                          .validator(file_exists)
+                                                          .help("Render the primary service's \
+                                                                 configuration templates into the \
+                                                                 image with the values in this \
+                                                                 TOML file, which override the \
+                                                                 package's default.toml and are \
+                                                                 also installed as the service's \
+                                                                 user.toml. The templates may not \
+                                                                 refer to sys, svc or bind data, \
+                                                                 which is only known at runtime"));
+
+        Cli { app }
+    }
+
+    pub fn add_exclude_transitive_dep_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("EXCLUDE_TRANSITIVE_DEP").value_name("IDENT")
+                                                                   .long("exclude-transitive-dep")
+                                                                   .multiple(true)
+                                                                   .number_of_values(1)
+                                                                   .validator(valid_ident)
+                                                                   .help("Leave a transitive \
+                                                                          dependency that isn't \
+                                                                          needed at runtime, \
+                                                                          along with any of its \
+                                                                          own dependencies that \
+                                                                          nothing else requires, \
+                                                                          out of the image (ex: \
+                                                                          core/perl); repeat the \
+                                                                          option for each \
+                                                                          dependency"));
+
+        Cli { app }
+    }
+
+    pub fn add_verbose_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("VERBOSE").long("verbose")
+                                                        .short("v")
+                                                        .help("Show the output of docker build \
+                                                               as it runs, rather than only \
+                                                               when the build fails"));
+
+        Cli { app }
+    }
+
+    pub fn add_quiet_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("QUIET").long("quiet")
+                                                      .short("q")
+                                                      .conflicts_with("VERBOSE")
+                                                      .help("Print only the reference of the \
+                                                             resulting image to stdout. \
+                                                             Warnings and errors are still \
+                                                             written to stderr"));
+
+        Cli { app }
+    }
+
+    pub fn add_report_stdout_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("REPORT_STDOUT").long("report-stdout")
+                                                          .conflicts_with("VERBOSE")
+                                                          .help("Print the build report to stdout \
+                                                                 instead of writing it to the \
+                                                                 results directory. Only warnings \
+                                                                 and errors are written \
+                                                                 otherwise, to stderr"));
+
+        Cli { app }
+    }
+
+    pub fn add_keep_reports_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("KEEP_REPORTS").value_name("COUNT")
+                                                         .long("keep-reports")
+                                                         .default_value("1")
+                                                         .validator(valid_report_count)
+                                                         .help("Number of build reports to keep \
+                                                                in the results directory, \
+                                                                counting the new one. Previous \
+                                                                reports are rotated out to \
+                                                                last_docker_export.env.1, .2, and \
+                                                                so on. A count of 1 overwrites \
+                                                                the previous report"));
+
+        Cli { app }
+    }
+
+    pub fn add_no_results_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("NO_RESULTS").long("no-results")
+                                                           .conflicts_with_all(&["PROVENANCE",
+                                                                                 "EMIT_K8S",
+                                                                                 "RESUME_PUSH"])
+                                                           .help("Do not write the build \
+                                                                  report, or any other report, \
+                                                                  to the results directory, \
+                                                                  which is not created \
+                                                                  (default: no)"));
 
         Cli { app }
     }
@@ -290,6 +1036,131 @@ impl<'a, 'b> Cli<'a, 'b> {
         Cli { app }
     }
 
+    pub fn add_dns_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("ADD_HOST").long("add-host")
+                                                     .value_name("NAME:IP")
+                                                     .multiple(true)
+                                                     .number_of_values(1)
+                                                     .validator(valid_host_entry)
+                                                     .help("Add a custom host-to-IP mapping to \
+                                                            the image's /etc/hosts (ex: \
+                                                            db.example.com:10.0.0.5)"))
+                      .arg(Arg::with_name("RESOLV_CONF").long("resolv-conf")
+                                                        .value_name("RESOLV_CONF")
+                                                        .validator(file_exists)
+                                                        .help("Use the given file as the image's \
+                                                               /etc/resolv.conf"));
+
+        Cli { app }
+    }
+
+    pub fn add_no_setuid_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("NO_SETUID").long("no-setuid")
+                                                          .help("Clear the setuid and setgid \
+                                                                 bits from all files in the \
+                                                                 image's root filesystem \
+                                                                 (default: no)"));
+
+        Cli { app }
+    }
+
+    pub fn add_strip_build_artifacts_arg(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("STRIP_BUILD_ARTIFACTS").long("strip-build-artifacts")
+                                                            .help("Remove the metadata files \
+                                                                   which only describe how the \
+                                                                   installed packages were \
+                                                                   built, such as BUILD_DEPS \
+                                                                   and BUILDTIME_ENVIRONMENT, \
+                                                                   from the image's root \
+                                                                   filesystem (default: no)"));
+
+        Cli { app }
+    }
+
+    pub fn add_userns_remap_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("USERNS_REMAP").value_name("START:COUNT")
+                                                         .long("userns-remap")
+                                                         .validator(valid_userns_remap)
+                                                         .help("Give the ownership of the files \
+                                                                copied into the image to the \
+                                                                first of the given range of host \
+                                                                user and group ids, for rootless \
+                                                                container runtimes, and record \
+                                                                the range in an image label (ex: \
+                                                                100000:65536)"));
+
+        Cli { app }
+    }
+
+    pub fn add_no_shell_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("NO_SHELL").long("no-shell")
+                                                         .help("Leave busybox out of the image, \
+                                                                so that it has no shell, and \
+                                                                run the Supervisor directly as \
+                                                                the entrypoint. Every hook of \
+                                                                the service packages must be a \
+                                                                program which can be run \
+                                                                without a shell (default: no)"));
+
+        Cli { app }
+    }
+
+    pub fn add_init_args(self) -> Self {
+        let app =
+            self.app
+                .arg(Arg::with_name("INIT").value_name("INIT")
+                                           .long("init")
+                                           .possible_values(InitSystem::variants())
+                                           .default_value("none")
+                                           .help("An init program to run as PID 1, which \
+                                                  forwards signals to the entrypoint and reaps \
+                                                  zombie processes"))
+                .arg(Arg::with_name("INIT_PKG").value_name("INIT_PKG")
+                                               .long("init-pkg")
+                                               .validator(valid_ident_or_hart)
+                                               .help("Package identifier (ex: core/tini) or \
+                                                      filepath to a Habitat artifact providing \
+                                                      the --init program (default: core/tini or \
+                                                      core/dumb-init)"));
+
+        Cli { app }
+    }
+
+    pub fn add_stop_signal_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("STOP_SIGNAL").value_name("SIGNAL")
+                                                        .long("stop-signal")
+                                                        .validator(valid_stop_signal)
+                                                        .help("The signal name (ex: SIGTERM) or \
+                                                               number (ex: 15) sent to the \
+                                                               container to stop it (default: the \
+                                                               container engine's default)"));
+
+        Cli { app }
+    }
+
+    pub fn add_required_capability_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("REQUIRED_CAPABILITY").value_name("CAP")
+                                                                .long("required-capability")
+                                                                .multiple(true)
+                                                                .number_of_values(1)
+                                                                .validator(valid_capability)
+                                                                .help("A Linux capability (ex: \
+                                                                       NET_ADMIN) required by the \
+                                                                       image's services, recorded \
+                                                                       in the sh.habitat.\
+                                                                       required-capabilities \
+                                                                       label; repeat the option \
+                                                                       for each capability"));
+
+        Cli { app }
+    }
+
     pub fn add_layer_arg(self) -> Self {
         let app =
             self.app
@@ -329,6 +1200,196 @@ fn valid_ident_or_hart(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_ident(val: String) -> result::Result<(), String> {
+    match PackageIdent::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_channel(val: String) -> result::Result<(), String> {
+    if !val.is_empty()
+       && val.chars()
+             .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        Ok(())
+    } else {
+        Err(format!("Channel: '{}' is not valid. Channel names may \
+                     only contain letters, numbers, '-', '_', and '.'",
+                    &val))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_host_entry(val: String) -> result::Result<(), String> {
+    match EtcHostsEntry::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_env_var(val: String) -> result::Result<(), String> {
+    match EnvVar::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_annotation(val: String) -> result::Result<(), String> {
+    match Annotation::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_build_arg(val: String) -> result::Result<(), String> {
+    match BuildArg::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_stop_signal(val: String) -> result::Result<(), String> {
+    match StopSignal::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_userns_remap(val: String) -> result::Result<(), String> {
+    match UsernsRemap::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_target(val: String) -> result::Result<(), String> {
+    match PackageTarget::from_str(&val) {
+        Ok(target) => {
+            match Platform::for_target(target) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("{}", e)),
+            }
+        }
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_capability(val: String) -> result::Result<(), String> {
+    match Capability::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_swap_limit(val: String) -> result::Result<(), String> {
+    match SwapLimit::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_size(val: String) -> result::Result<(), String> {
+    match ByteSize::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_tag_version_format(val: String) -> result::Result<(), String> {
+    match Naming::expand_tag_version_format(&val, "1.0.0", "20200101000000") {
+        Ok(ref tag) if docker::is_valid_tag(tag) => Ok(()),
+        Ok(tag) => {
+            Err(format!("Tag version format: '{}' renders invalid tags, \
+                         such as '{}'",
+                        val, tag))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_timeout(val: String) -> result::Result<(), String> {
+    match val.parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number of seconds", val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_run_args(val: String) -> result::Result<(), String> {
+    match shlex::split(&val) {
+        Some(_) => Ok(()),
+        None => Err(format!("'{}' has unbalanced quotes", val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_report_count(val: String) -> result::Result<(), String> {
+    match val.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number of reports", val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_push_concurrency(val: String) -> result::Result<(), String> {
+    match val.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number of pushes", val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn file_exists(val: String) -> result::Result<(), String> {
+    if Path::new(&val).is_file() {
+        Ok(())
+    } else {
+        Err(format!("File: '{}' cannot be found", &val))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn dir_exists(val: String) -> result::Result<(), String> {
+    if Path::new(&val).is_dir() {
+        Ok(())
+    } else {
+        Err(format!("Directory: '{}' cannot be found", &val))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn absolute_path(val: String) -> result::Result<(), String> {
+    if Path::new(&val).has_root() {
+        Ok(())
+    } else {
+        Err(format!("Path: '{}' is not absolute", &val))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_binary_name(val: String) -> result::Result<(), String> {
+    if !val.is_empty() && !val.contains('/') && !val.contains('\\') {
+        Ok(())
+    } else {
+        Err(format!("Binary: '{}' is not valid. Provide the name of a \
+                     program, not a path",
+                    &val))
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_url(val: String) -> result::Result<(), String> {
     match Url::parse(&val) {
@@ -0,0 +1,84 @@
+//! Closure manifests, which record every package resolved into an image, direct and transitive,
+//! along with the checksum and size of its artifact, so that the closure can be audited or
+//! mirrored without building the image.
+
+use crate::{error::{Error,
+                    Result},
+            graph::Graph,
+            util};
+use habitat_core::{crypto::hash,
+                   package::{PackageIdent,
+                             PackageTarget}};
+use std::{fs,
+          path::Path};
+
+/// A package of the closure and its artifact.
+#[derive(Debug, PartialEq)]
+pub struct ClosureEntry {
+    pub ident:    PackageIdent,
+    /// The BLAKE2b checksum of the artifact, as a hex string.
+    pub checksum: String,
+    /// The size of the artifact in bytes.
+    pub size:     u64,
+}
+
+/// The packages of an image, direct and transitive, in the order they are added to it.
+#[derive(Debug, PartialEq)]
+pub struct ClosureManifest {
+    target:  PackageTarget,
+    entries: Vec<ClosureEntry>,
+}
+
+impl ClosureManifest {
+    /// Creates a manifest of the packages in a dependency graph from their artifacts in the
+    /// given artifact cache.
+    ///
+    /// # Errors
+    ///
+    /// * If the artifact of a package is not in the artifact cache
+    /// * If an artifact cannot be read
+    pub fn from_graph<P>(graph: &Graph, artifact_cache: P, target: PackageTarget) -> Result<Self>
+        where P: AsRef<Path>
+    {
+        let mut entries = Vec::new();
+        for ident in graph.reverse_topological_sort() {
+            let artifact = artifact_cache.as_ref()
+                                         .join(ident.archive_name_with_target(target)?);
+            let size = match fs::metadata(&artifact) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => return Err(Error::ArtifactNotCached(ident.to_string()).into()),
+            };
+            entries.push(ClosureEntry { checksum: hash::hash_file(&artifact)?,
+                                        ident,
+                                        size });
+        }
+        Ok(ClosureManifest { target, entries })
+    }
+
+    /// Returns the packages of the closure.
+    pub fn entries(&self) -> &[ClosureEntry] { &self.entries }
+
+    /// Writes the manifest as JSON, replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be written
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let packages: Vec<_> = self.entries
+                                   .iter()
+                                   .map(|entry| {
+                                       json!({
+                                           "ident": entry.ident.to_string(),
+                                           "checksum": entry.checksum,
+                                           "size": entry.size,
+                                       })
+                                   })
+                                   .collect();
+        let manifest = json!({
+            "target": self.target.to_string(),
+            "packages": packages,
+        });
+        util::write_file(path, &serde_json::to_string_pretty(&manifest)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+//! Rendering of a service package's configuration templates while the root file system is
+//! assembled, so that an image carries its configuration rather than rendering it at startup.
+
+use crate::{error::{Error,
+                    Result},
+            util};
+use habitat_common::templating::TemplateRenderer;
+use habitat_core::{fs::{pkg_install_path,
+                        svc_config_path,
+                        svc_data_path,
+                        svc_files_path,
+                        svc_path,
+                        svc_pid_file,
+                        svc_static_path,
+                        svc_var_path,
+                        user_config_path,
+                        USER_CONFIG_FILE},
+                   package::PackageInstall};
+use std::{fs,
+          path::{Component,
+                 Path,
+                 PathBuf}};
+use toml::value::{Table,
+                  Value};
+
+/// The top-level template data which is only known once the Supervisor runs the service, and so
+/// can't be rendered into an image.
+const RUNTIME_ONLY_KEYS: &[&str] = &["sys", "svc", "bind"];
+
+/// Reads the configuration values to render with from a TOML file.
+///
+/// # Errors
+///
+/// * If the file cannot be read or is not valid TOML
+pub fn values_from_file<P: AsRef<Path>>(path: P) -> Result<Table> {
+    let path = path.as_ref();
+    let invalid = |reason: String| {
+        Error::InvalidRenderConfig { path: path.display().to_string(),
+                                     reason }
+    };
+    let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    Ok(toml::from_str(&contents).map_err(|e| invalid(e.to_string()))?)
+}
+
+/// Renders the configuration templates of a service package installed under the given root file
+/// system path into the service's configuration directory, with the package's `default.toml`
+/// overridden by the given values, returning the paths of the rendered files within the image.
+///
+/// The values are also written as the service's `user.toml`, so the Supervisor renders the same
+/// configuration if it renders the templates again at runtime.
+///
+/// # Errors
+///
+/// * If the package's `default.toml` cannot be read or parsed
+/// * If a configuration template refers to `sys`, `svc` or `bind` data, which is only known at
+///   runtime
+/// * If a configuration template cannot be loaded or rendered
+/// * If a rendered file or the `user.toml` cannot be written
+pub fn render<T: AsRef<Path>>(root: T,
+                              pkg_install: &PackageInstall,
+                              values: &Table)
+                              -> Result<Vec<PathBuf>> {
+    let root = root.as_ref();
+    let ident = pkg_install.ident();
+    let render_failed = |reason: String| {
+        Error::ConfigRenderFailed { ident: ident.to_string(),
+                                    reason }
+    };
+
+    let default_toml = pkg_install.installed_path().join("default.toml");
+    let mut cfg = if default_toml.is_file() {
+        toml::from_str(&fs::read_to_string(&default_toml)?).map_err(|e| {
+                                                               render_failed(e.to_string())
+                                                           })?
+    } else {
+        Table::new()
+    };
+    merge(&mut cfg, values);
+
+    let mut renderer = TemplateRenderer::new();
+    let templates_dir = pkg_install.installed_path().join("config");
+    let templates = templates(&templates_dir)?;
+    for template in &templates {
+        let path = templates_dir.join(template);
+        if let Some(reference) = runtime_only_reference(&fs::read_to_string(&path)?) {
+            return Err(render_failed(format!("{} refers to '{}', which is only known when the \
+                                              Supervisor runs the service",
+                                             template.display(),
+                                             reference)).into());
+        }
+        renderer.register_template_file(&template.to_string_lossy(), path)
+                .map_err(|e| render_failed(e.to_string()))?;
+    }
+
+    let ctx = json!({
+        "cfg": cfg,
+        "pkg": pkg_context(pkg_install)?,
+    });
+    let config_path = svc_config_path(&ident.name);
+    let mut rendered = Vec::new();
+    for template in &templates {
+        let contents = renderer.render(&template.to_string_lossy(), &ctx)
+                               .map_err(|e| render_failed(e.to_string()))?;
+        let path = config_path.join(template);
+        util::write_file(in_root(root, &path), &contents)?;
+        rendered.push(path);
+    }
+
+    let user_toml = in_root(root, &user_config_path(&ident.name).join(USER_CONFIG_FILE));
+    util::write_file(user_toml,
+                     &toml::to_string(values).map_err(|e| render_failed(e.to_string()))?)?;
+
+    Ok(rendered)
+}
+
+/// Returns the `pkg` template data for a package, with paths as they are within the image.
+fn pkg_context(pkg_install: &PackageInstall) -> Result<serde_json::Value> {
+    let ident = pkg_install.ident();
+    let name = &ident.name;
+    Ok(json!({
+        "ident": ident.to_string(),
+        "origin": ident.origin,
+        "name": name,
+        "version": ident.version,
+        "release": ident.release,
+        "path": pkg_install_path(ident, None::<&Path>),
+        "svc_path": svc_path(name),
+        "svc_config_path": svc_config_path(name),
+        "svc_data_path": svc_data_path(name),
+        "svc_files_path": svc_files_path(name),
+        "svc_static_path": svc_static_path(name),
+        "svc_var_path": svc_var_path(name),
+        "svc_pid_file": svc_pid_file(name),
+        "svc_user": pkg_install.svc_user()?.unwrap_or_else(|| String::from("hab")),
+        "svc_group": pkg_install.svc_group()?.unwrap_or_else(|| String::from("hab")),
+    }))
+}
+
+/// Returns the first reference in a template's expressions to top-level data which is only known
+/// at runtime, if there is one. Comments are ignored.
+fn runtime_only_reference(template: &str) -> Option<String> {
+    template.split("{{")
+            .skip(1)
+            .filter_map(|s| s.split("}}").next())
+            .filter(|expr| !expr.trim_start_matches('~').trim_start().starts_with('!'))
+            .flat_map(|expr| {
+                expr.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '@'))
+            })
+            .map(|token| token.trim_start_matches("@root."))
+            .find(|token| {
+                RUNTIME_ONLY_KEYS.iter()
+                                 .any(|key| token.split('.').next() == Some(*key))
+            })
+            .map(str::to_string)
+}
+
+/// Returns the paths of all template files under a package's configuration directory, relative
+/// to that directory, or no paths if the package has no configuration directory.
+fn templates(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut templates = Vec::new();
+    if !dir.is_dir() {
+        return Ok(templates);
+    }
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        for entry in fs::read_dir(dir.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                templates.push(path);
+            }
+        }
+    }
+    templates.sort();
+    Ok(templates)
+}
+
+/// Merges the values of one table into another, replacing values other than tables and merging
+/// tables recursively.
+fn merge(into: &mut Table, from: &Table) {
+    for (key, value) in from {
+        match (into.get_mut(key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => merge(existing, table),
+            _ => {
+                into.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Returns an absolute path within the image as a path under the given root file system path.
+fn in_root(root: &Path, path: &Path) -> PathBuf {
+    let relative: PathBuf = path.components()
+                                .filter_map(|c| {
+                                    match c {
+                                        Component::Normal(c) => Some(c),
+                                        _ => None,
+                                    }
+                                })
+                                .collect();
+    root.join(relative)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table(contents: &str) -> Table { toml::from_str(contents).unwrap() }
+
+    #[test]
+    fn values_override_defaults_recursively() {
+        let mut cfg = table("port = 6379\n[tls]\nenabled = false\nport = 6380\n");
+        merge(&mut cfg, &table("[tls]\nenabled = true\n"));
+
+        assert_eq!(cfg,
+                   table("port = 6379\n[tls]\nenabled = true\nport = 6380\n"));
+    }
+
+    #[test]
+    fn runtime_only_data_is_found_in_template_expressions() {
+        assert_eq!(runtime_only_reference("bind {{sys.ip}}:{{cfg.port}}"),
+                   Some(String::from("sys.ip")));
+        assert_eq!(runtime_only_reference("{{#eachAlive bind.database.members as \
+                                           |m|}}{{m.sys.ip}}{{/eachAlive}}"),
+                   Some(String::from("bind.database.members")));
+        assert_eq!(runtime_only_reference("{{~ @root.svc.me.leader}}"),
+                   Some(String::from("svc.me.leader")));
+    }
+
+    #[test]
+    fn build_time_data_and_comments_are_not_runtime_only() {
+        assert_eq!(runtime_only_reference("{{!-- sys.ip is not used --}}port {{cfg.port}}\npath \
+                                           {{pkg.svc_config_path}} {{cfg.sys_name}}"),
+                   None);
+    }
+
+    #[test]
+    fn invalid_values_file_is_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("values.toml");
+        fs::write(&path, "port = ").unwrap();
+
+        let err = values_from_file(&path).unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::InvalidRenderConfig { .. }) => {}
+            _ => panic!("Expected InvalidRenderConfig, got {:?}", err),
+        }
+    }
+}
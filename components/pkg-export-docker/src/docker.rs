@@ -1,22 +1,53 @@
-use crate::{build::BuildRoot,
+#[cfg(unix)]
+use crate::rootfs;
+use crate::{annotation::Annotation,
+            build::BuildRoot,
+            build_arg::{self,
+                        BuildArg},
             error::{Error,
                     Result},
+            size::ByteSize,
+            timings::Timings,
             util,
+            BuildOptions,
             Credentials,
-            Naming};
+            DockerfilePolicy,
+            Naming,
+            VERSION};
+use chrono::{DateTime,
+             Utc};
 use failure::SyncFailure;
-use habitat_common::ui::{Status,
-                         UIWriter,
-                         UI};
-use habitat_core::{package::PackageIdent,
-                   util::docker};
+use habitat_common::{types::HttpListenAddr,
+                     ui::{Status,
+                          UIWriter,
+                          UI},
+                     PROGRAM_NAME};
+use habitat_core::{env as henv,
+                   package::PackageIdent,
+                   util::docker,
+                   ChannelIdent};
 use handlebars::Handlebars;
 use serde_json;
-use std::{fs,
+use std::{cmp,
+          fs,
+          io::{self,
+               BufRead,
+               BufReader,
+               Read,
+               Write},
+          iter,
           path::{Path,
                  PathBuf},
-          process::Command,
-          str::FromStr};
+          process::{Command,
+                    ExitStatus,
+                    Stdio},
+          str::FromStr,
+          sync::{mpsc::{self,
+                        Sender},
+                 Arc},
+          thread,
+          time::{Duration,
+                 Instant}};
 
 // This code makes heavy use of `#[cfg(unix)]` and `#[cfg(windows)]`. This should potentially be
 // changed to use the various target feature flags.
@@ -28,17 +59,61 @@ const DOCKERFILE: &str = include_str!("../defaults/Dockerfile.hbs");
 const DOCKERFILE: &str = include_str!("../defaults/Dockerfile_win.hbs");
 /// The build report template.
 const BUILD_REPORT: &str = include_str!("../defaults/last_docker_export.env.hbs");
+/// The Kubernetes `Deployment` manifest template.
+const K8S_DEPLOYMENT: &str = include_str!("../defaults/k8s_deployment.yaml.hbs");
+
+/// The environment variable containing global options, in shell-words form, which are passed to
+/// every invocation of the Docker program (ex: `--host tcp://10.0.0.5:2376 --tlsverify`).
+pub const ENGINE_OPTS_ENVVAR: &str = "HAB_CONTAINER_ENGINE_OPTS";
+
+/// The file name of the build report.
+const EXPORT_REPORT: &str = "last_docker_export.env";
+
+/// The file name of the record of tags pushed to a remote registry.
+const PUSH_REPORT: &str = "last_docker_push.json";
+
+/// The registry which images are pushed to when no registry URL is given.
+const DEFAULT_REGISTRY_URL: &str = "https://index.docker.io/v1/";
+
+/// Tags which are expected to move between builds, and so may be overwritten in a registry.
+const MUTABLE_TAGS: &[&str] = &["latest"];
+
+/// How often the state of a smoke test container is checked.
+const SMOKE_TEST_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the output of a Docker build is presented.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BuildOutput {
+    /// Output is captured, and only shown if the build fails.
+    Buffered,
+    /// Output is shown, line by line, as the build runs.
+    Streamed,
+}
 
 /// A builder used to create a Docker image.
 pub struct DockerBuilder<'a> {
     /// The base workdir which hosts the root file system.
-    workdir: &'a Path,
+    workdir:     &'a Path,
     /// The name for the image.
-    name:    String,
+    name:        String,
     /// A list of tags for the image.
-    tags:    Vec<String>,
+    tags:        Vec<String>,
     /// Optional memory limit to pass to pass to the docker build
-    memory:  Option<&'a str>,
+    memory:      Option<&'a str>,
+    /// Optional limit on memory plus swap to pass to the docker build, where -1 is unlimited
+    memory_swap: Option<&'a str>,
+    /// A list of images to use as layer cache sources for the docker build
+    cache_from:  Vec<&'a str>,
+    /// A list of build arguments for the docker build
+    build_args:  Vec<&'a BuildArg>,
+    /// Additional arguments passed verbatim to the docker build
+    engine_opts: Vec<&'a str>,
+    /// The OS and architecture declared by the image, as `os/arch`
+    platform:    Option<String>,
+    /// How the output of the docker build is presented
+    output:      BuildOutput,
+    /// Whether the output of Docker commands run on the built image is hidden
+    quiet:       bool,
 }
 
 impl<'a> DockerBuilder<'a> {
@@ -48,7 +123,14 @@ impl<'a> DockerBuilder<'a> {
         DockerBuilder { workdir,
                         name: name.into(),
                         tags: Vec::new(),
-                        memory: None }
+                        memory: None,
+                        memory_swap: None,
+                        cache_from: Vec::new(),
+                        build_args: Vec::new(),
+                        engine_opts: Vec::new(),
+                        platform: None,
+                        output: BuildOutput::Buffered,
+                        quiet: false }
     }
 
     /// Adds a tag for the Docker image.
@@ -63,27 +145,61 @@ impl<'a> DockerBuilder<'a> {
         self
     }
 
+    /// Specifies a limit on memory plus swap for the build, or -1 for unlimited swap
+    pub fn memory_swap(mut self, memory_swap: &'a str) -> Self {
+        self.memory_swap = Some(memory_swap);
+        self
+    }
+
+    /// Adds an image to use as a source of cached layers for the build.
+    pub fn cache_from(mut self, image: &'a str) -> Self {
+        self.cache_from.push(image);
+        self
+    }
+
+    /// Adds a build argument for the build.
+    pub fn build_arg(mut self, arg: &'a BuildArg) -> Self {
+        self.build_args.push(arg);
+        self
+    }
+
+    /// Adds an argument which is passed, unvalidated, to the Docker build.
+    pub fn engine_opt(mut self, opt: &'a str) -> Self {
+        self.engine_opts.push(opt);
+        self
+    }
+
+    /// Sets the OS and architecture (ex: `linux/amd64`) declared by the image.
+    pub fn platform<S: Into<String>>(mut self, platform: S) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    /// Shows the output of the Docker build as it runs, rather than only when it fails.
+    pub fn verbose(mut self) -> Self {
+        self.output = BuildOutput::Streamed;
+        self
+    }
+
+    /// Hides the output of the Docker commands run on the built image, such as pushes.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
     /// Builds the Docker image locally and returns the corresponding `DockerImage`.
     ///
     /// # Errors
     ///
     /// * If building the Docker image fails
-    pub fn build(self) -> Result<DockerImage> {
+    pub fn build(self, ui: &mut UI) -> Result<DockerImage> {
         let mut cmd = docker_cmd();
-        cmd.current_dir(self.workdir).arg("build").arg("--force-rm");
-        if let Some(mem) = self.memory {
-            cmd.arg("--memory").arg(mem);
-        }
-        if self.tags.is_empty() {
-            cmd.arg("--tag").arg(&self.name);
-        } else {
-            for tag in &self.tags {
-                cmd.arg("--tag").arg(format!("{}:{}", &self.name, tag));
-            }
-        }
-        cmd.arg(".");
+        cmd.current_dir(self.workdir).args(self.build_args());
         debug!("Running: {:?}", &cmd);
-        let exit_status = cmd.spawn()?.wait()?;
+        let exit_status = match self.output {
+            BuildOutput::Buffered => run_buffered(ui, cmd)?,
+            BuildOutput::Streamed => run_streamed(ui, cmd)?,
+        };
         if !exit_status.success() {
             return Err(Error::BuildFailed(exit_status).into());
         }
@@ -96,7 +212,51 @@ impl<'a> DockerBuilder<'a> {
         Ok(DockerImage { id,
                          name: self.name,
                          tags: self.tags,
-                         workdir: self.workdir.to_owned() })
+                         workdir: self.workdir.to_owned(),
+                         rootfs_size_bytes: None,
+                         packages: Vec::new(),
+                         exposes: Vec::new(),
+                         health_check_path: None,
+                         annotations: Vec::new(),
+                         quiet: self.quiet })
+    }
+
+    /// Returns the arguments for the `docker build` invocation.
+    fn build_args(&self) -> Vec<String> {
+        let mut args = vec!["build".to_string(), "--force-rm".to_string()];
+        if let Some(mem) = self.memory {
+            args.push("--memory".to_string());
+            args.push(mem.to_string());
+        }
+        if let Some(swap) = self.memory_swap {
+            args.push("--memory-swap".to_string());
+            args.push(swap.to_string());
+        }
+        for image in &self.cache_from {
+            args.push("--cache-from".to_string());
+            args.push(image.to_string());
+        }
+        for arg in &self.build_args {
+            args.push("--build-arg".to_string());
+            args.push(arg.to_string());
+        }
+        if let Some(ref platform) = self.platform {
+            args.push("--platform".to_string());
+            args.push(platform.clone());
+        }
+        if self.tags.is_empty() {
+            args.push("--tag".to_string());
+            args.push(self.name.clone());
+        } else {
+            for tag in &self.tags {
+                args.push("--tag".to_string());
+                args.push(format!("{}:{}", &self.name, tag));
+            }
+        }
+        // Extra arguments are options to `docker build`, so they must precede the build context
+        args.extend(self.engine_opts.iter().map(ToString::to_string));
+        args.push(".".to_string());
+        args
     }
 
     fn image_id(&self, image_tag: &str) -> Result<String> {
@@ -116,39 +276,88 @@ impl<'a> DockerBuilder<'a> {
 /// A built Docker image which exists locally.
 pub struct DockerImage {
     /// The image ID for this image.
-    id:      String,
+    id:                String,
     /// The name of this image.
-    name:    String,
+    name:              String,
     /// The list of tags for this image.
-    tags:    Vec<String>,
+    tags:              Vec<String>,
     /// The base workdir which hosts the root file system.
-    workdir: PathBuf,
+    workdir:           PathBuf,
+    /// The uncompressed size of the root file system the image was built from, if known.
+    rootfs_size_bytes: Option<u64>,
+    /// The fully qualified identifiers of the Habitat packages in the image, in dependency order.
+    packages:          Vec<String>,
+    /// The ports exposed by the service packages in the image.
+    exposes:           Vec<String>,
+    /// The Supervisor HTTP gateway path reporting the primary service's health, if the service
+    /// has a health check.
+    health_check_path: Option<String>,
+    /// Annotations for the image, which are applied to the Kubernetes `Deployment` manifest, as
+    /// the Docker daemon cannot apply them to the image manifest.
+    annotations:       Vec<Annotation>,
+    /// Whether the output of Docker commands run on this image is hidden.
+    quiet:             bool,
 }
 
 impl<'a> DockerImage {
     /// Pushes the Docker image, with all tags, to a remote registry using the provided
-    /// `Credentials`.
+    /// `Credentials`. When `fail_on_existing_tag` is set, nothing is pushed if any immutable tag
+    /// already exists in the registry.
+    ///
+    /// Each pushed tag is recorded in a push report in the given directory, if any. When `resume`
+    /// is set, immutable tags which the report shows were already pushed for this image are
+    /// skipped. At most `concurrency` tags are pushed at once.
     ///
     /// # Errors
     ///
     /// * If a registry login is not successful
+    /// * If the push report cannot be read or written
+    /// * If an immutable tag already exists and `fail_on_existing_tag` is set
     /// * If a pushing one or more of the image tags fails
     /// * If a registry logout is not successful
+    #[allow(clippy::too_many_arguments)]
     pub fn push(&self,
                 ui: &mut UI,
                 credentials: &Credentials,
-                registry_url: Option<&str>)
+                registry_url: Option<&str>,
+                fail_on_existing_tag: bool,
+                report_dir: Option<&Path>,
+                resume: bool,
+                concurrency: usize)
                 -> Result<()> {
         ui.begin(format!("Pushing Docker image '{}' with all tags to remote registry",
                          self.name()))?;
         self.create_docker_config_file(credentials, registry_url)
             .unwrap();
+        let report = report_dir.map(|dir| dir.join(PUSH_REPORT));
+        let pushed = match report {
+            Some(ref report) if resume => self.read_pushed_tags(report)?,
+            _ => Vec::new(),
+        };
+        let (skipped, pending): (Vec<String>, Vec<String>) =
+            self.tags.iter().cloned().partition(|t| pushed.contains(t));
+        for tag in &skipped {
+            ui.status(Status::Skipping,
+                      format!("image '{}:{}', which was already pushed", &self.name, tag))?;
+        }
+        if fail_on_existing_tag {
+            self.ensure_tags_are_new(&pending, |image_tag| {
+                    self.remote_tag_exists(docker_cmd, image_tag)
+                })?;
+        }
         if self.tags.is_empty() {
-            self.push_image(ui, None)?;
+            ui.status(Status::Uploading,
+                      format!("image '{}' to remote registry", &self.name))?;
+            push_image(&self.name, &self.workdir, self.quiet)?;
+            ui.status(Status::Uploaded, format!("image '{}'", &self.name))?;
         } else {
-            for tag in &self.tags {
-                self.push_image(ui, Some(tag))?;
-            }
+            let (name, workdir, quiet) = (self.name.clone(), self.workdir.clone(), self.quiet);
+            self.push_tags(ui,
+                           &pending,
+                           pushed,
+                           report.as_deref(),
+                           concurrency,
+                           move |tag| push_image(&format!("{}:{}", name, tag), &workdir, quiet))?;
         }
         ui.end(format!("Docker image '{}' published with tags: {}",
                        self.name(),
@@ -157,331 +366,3141 @@ impl<'a> DockerImage {
         Ok(())
     }
 
-    /// Removes the image from the local Docker engine along with all tags.
+    /// Tags the image with a short, content-addressable tag derived from its image digest (ex:
+    /// `sha-0123456789ab`), so that the tag always refers to this exact build.
     ///
     /// # Errors
     ///
-    /// * If one or more of the image tags cannot be removed
-    pub fn rm(self, ui: &mut UI) -> Result<()> {
-        ui.begin(format!("Cleaning up local Docker image '{}' with all tags",
-                         self.name()))?;
-        if self.tags.is_empty() {
-            self.rm_image(ui, None)?;
-        } else {
-            for tag in &self.tags {
-                self.rm_image(ui, Some(tag))?;
-            }
+    /// * If the image digest cannot be read
+    /// * If tagging the image fails
+    pub fn tag_digest_short(&mut self, ui: &mut UI) -> Result<()> {
+        let digest = self.digest()?;
+        let tag = digest_short_tag(&digest).ok_or_else(|| Error::DockerImageIdNotFound(digest))?;
+        let image_tag = format!("{}:{}", &self.name, &tag);
+        ui.status(Status::Creating, format!("image tag '{}'", &image_tag))?;
+        let mut cmd = docker_cmd();
+        cmd.arg("tag").arg(&self.id).arg(&image_tag);
+        debug!("Running: {:?}", &cmd);
+        let exit_status = cmd.stdout(self.child_stdout()).spawn()?.wait()?;
+        if !exit_status.success() {
+            return Err(Error::TagImageFailed(exit_status).into());
         }
-        ui.end(format!("Local Docker image '{}' with tags: {} cleaned up",
-                       self.name(),
-                       self.tags().join(", "),))?;
+        self.tags.push(tag);
 
         Ok(())
     }
 
-    /// Returns the ID of this image.
-    pub fn id(&self) -> &str { self.id.as_str() }
+    /// Returns the full content digest of the image (ex: `sha256:...`).
+    pub(crate) fn digest(&self) -> Result<String> { self.digest_with(docker_cmd) }
 
-    /// Returns the name of this image.
-    pub fn name(&self) -> &str { self.name.as_str() }
+    /// Returns the full content digest of the image, running the Docker program given by
+    /// `engine`.
+    fn digest_with<F>(&self, engine: F) -> Result<String>
+        where F: Fn() -> Command
+    {
+        let mut cmd = engine();
+        cmd.arg("inspect")
+           .arg("--format")
+           .arg("{{.Id}}")
+           .arg(&self.id);
+        debug!("Running: {:?}", &cmd);
+        let output = cmd.output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
 
-    /// Returns the list of tags for this image.
-    pub fn tags(&self) -> &[String] { &self.tags }
+        match stdout.lines().next() {
+            Some(digest) if output.status.success() => Ok(digest.trim().to_string()),
+            _ => Err(Error::DockerImageIdNotFound(self.id.clone()).into()),
+        }
+    }
 
-    /// Create a build report with image metadata in the given path.
+    /// Returns the digest-pinned reference of the image in the registry it was pushed to (ex:
+    /// `acme/redis@sha256:...`), as recorded by the Docker engine when pushing it.
+    pub(crate) fn repo_digest(&self) -> Result<String> {
+        self.repo_digest_with(docker_cmd)?
+            .ok_or_else(|| Error::RepoDigestNotFound(self.name.clone()).into())
+    }
+
+    /// Returns the digest-pinned reference of the pushed image, or `None` if the Docker engine
+    /// has not recorded one, running the Docker program given by `engine`.
+    fn repo_digest_with<F>(&self, engine: F) -> Result<Option<String>>
+        where F: Fn() -> Command
+    {
+        let mut cmd = engine();
+        cmd.arg("inspect")
+           .arg("--format")
+           .arg("{{range .RepoDigests}}{{println .}}{{end}}")
+           .arg(&self.id);
+        debug!("Running: {:?}", &cmd);
+        let output = cmd.output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let prefix = format!("{}@", self.name);
+
+        Ok(stdout.lines()
+                 .map(str::trim)
+                 .find(|r| r.starts_with(&prefix))
+                 .map(str::to_string))
+    }
+
+    /// Checks that the image is no larger than the given size.
     ///
     /// # Errors
     ///
-    /// * If the destination directory cannot be created
-    /// * If the report file cannot be written
-    pub fn create_report<P: AsRef<Path>>(&self, ui: &mut UI, dst: P) -> Result<()> {
-        let report = dst.as_ref().join("last_docker_export.env");
-        ui.status(Status::Creating,
-                  format!("build report {}", report.display()))?;
-        fs::create_dir_all(&dst)?;
-        let name_tags: Vec<_> = self.tags
-                                    .iter()
-                                    .map(|t| format!("{}:{}", &self.name, t))
-                                    .collect();
-        let json = json!({
-            "id": &self.id,
-            "name": &self.name,
-            "tags": self.tags.join(","),
-            "name_tags": name_tags.join(","),
-        });
-        util::write_file(&report,
-                         &Handlebars::new().template_render(BUILD_REPORT, &json)
-                                           .map_err(SyncFailure::new)?)?;
-        Ok(())
+    /// * If the image size cannot be read
+    /// * If the image is larger than the given size
+    pub fn ensure_size_within(&self, limit: ByteSize) -> Result<()> {
+        check_image_size(self.size_bytes()?, limit)
     }
 
-    pub fn create_docker_config_file(&self,
-                                     credentials: &Credentials,
-                                     registry_url: Option<&str>)
-                                     -> Result<()> {
-        let config = self.workdir.join("config.json");
-        fs::create_dir_all(&self.workdir)?;
-        let registry = match registry_url {
-            Some(url) => url,
-            None => "https://index.docker.io/v1/",
-        };
-        debug!("Using registry: {:?}", registry);
-        let json = json!({
-            "auths": {
-                registry: {
-                    "auth": credentials.token
+    /// Returns the size, in bytes, of the image as reported by the Docker engine.
+    pub(crate) fn size_bytes(&self) -> Result<u64> {
+        let mut cmd = docker_cmd();
+        cmd.arg("inspect")
+           .arg("--format")
+           .arg("{{.Size}}")
+           .arg(&self.id);
+        debug!("Running: {:?}", &cmd);
+        let output = cmd.output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        match stdout.lines().next().map(|size| size.trim().parse()) {
+            Some(Ok(size)) if output.status.success() => Ok(size),
+            _ => Err(Error::DockerImageIdNotFound(self.id.clone()).into()),
+        }
+    }
+
+    /// Pushes each of the given tags with the given function, from a new thread for each tag, with
+    /// at most `concurrency` pushes running at once. Every successfully pushed tag, along with
+    /// those already pushed, is recorded in the push report as soon as its push completes.
+    ///
+    /// Once a push fails, no further pushes are started, but those already running are awaited
+    /// so that their outcome is reported and recorded.
+    ///
+    /// # Errors
+    ///
+    /// * If pushing a tag fails, in which case the first failure is returned
+    /// * If the push report cannot be written
+    fn push_tags<F>(&self,
+                    ui: &mut UI,
+                    tags: &[String],
+                    mut pushed: Vec<String>,
+                    report: Option<&Path>,
+                    concurrency: usize,
+                    push: F)
+                    -> Result<()>
+        where F: Fn(&str) -> Result<()> + Send + Sync + 'static
+    {
+        let push = Arc::new(push);
+        let (tx, rx) = mpsc::channel();
+        let mut tags = tags.iter();
+        let mut running = 0;
+        let mut failure = None;
+        loop {
+            while failure.is_none() && running < concurrency.max(1) {
+                let tag = match tags.next() {
+                    Some(tag) => tag.to_string(),
+                    None => break,
+                };
+                ui.status(Status::Uploading,
+                          format!("image '{}:{}' to remote registry", &self.name, &tag))?;
+                let (push, tx) = (Arc::clone(&push), tx.clone());
+                thread::spawn(move || {
+                    let result = push(&tag);
+                    // The receiver is only dropped if reporting a result failed
+                    let _ = tx.send((tag, result));
+                });
+                running += 1;
+            }
+            if running == 0 {
+                break;
+            }
+            let (tag, result) = rx.recv().expect("a sender is held for pushes");
+            running -= 1;
+            let image_tag = format!("{}:{}", &self.name, &tag);
+            match result {
+                Ok(()) => {
+                    ui.status(Status::Uploaded, format!("image '{}'", &image_tag))?;
+                    pushed.push(tag);
+                    if let Some(report) = report {
+                        let json = json!({
+                            "id": &self.id,
+                            "name": &self.name,
+                            "pushed_tags": &pushed,
+                        });
+                        util::write_file(report, &serde_json::to_string(&json)?)?;
+                    }
                 }
+                // Only the first failure is returned, so any others are reported here
+                Err(err) if failure.is_some() => {
+                    ui.warn(format!("Failed to push image '{}': {}", &image_tag, err))?;
+                }
+                Err(err) => failure = Some(err),
             }
-        });
-        util::write_file(&config, &serde_json::to_string(&json).unwrap())?;
-        Ok(())
+        }
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
     }
 
-    fn push_image(&self, ui: &mut UI, tag: Option<&str>) -> Result<()> {
-        let image_tag = match tag {
-            Some(tag) => format!("{}:{}", &self.name, tag),
-            None => self.name.to_string(),
-        };
-        ui.status(Status::Uploading,
-                  format!("image '{}' to remote registry", &image_tag))?;
-        let mut cmd = docker_cmd();
-        cmd.arg("--config");
-        cmd.arg(self.workdir.to_str().unwrap());
-        cmd.arg("push").arg(&image_tag);
-        debug!("Running: {:?}", &cmd);
-        let exit_status = cmd.spawn()?.wait()?;
-        if !exit_status.success() {
-            return Err(Error::PushImageFailed(exit_status).into());
+    /// Returns the immutable tags which a push report shows were already pushed for this image.
+    /// Mutable tags, such as `latest`, are always pushed again, and a missing report or a report
+    /// for a different image, including an earlier build of the same image name, means no tags
+    /// were pushed.
+    ///
+    /// # Errors
+    ///
+    /// * If the push report cannot be read or parsed
+    fn read_pushed_tags(&self, report: &Path) -> Result<Vec<String>> {
+        if !report.is_file() {
+            return Ok(Vec::new());
+        }
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(report)?)?;
+        if json["id"] != self.id.as_str() || json["name"] != self.name.as_str() {
+            return Ok(Vec::new());
         }
-        ui.status(Status::Uploaded, format!("image '{}'", &image_tag))?;
+        let tags = json["pushed_tags"].as_array()
+                                      .into_iter()
+                                      .flatten()
+                                      .filter_map(serde_json::Value::as_str)
+                                      .filter(|t| !MUTABLE_TAGS.contains(t))
+                                      .map(str::to_string)
+                                      .collect();
+        Ok(tags)
+    }
 
+    /// Checks that none of the given immutable tags already exist, using the given function to
+    /// query whether a `name:tag` reference exists in the remote registry. Mutable tags, such as
+    /// `latest`, are expected to be overwritten and are not checked.
+    ///
+    /// # Errors
+    ///
+    /// * If an immutable tag already exists
+    /// * If querying the remote registry fails
+    fn ensure_tags_are_new<F>(&self, tags: &[String], mut exists: F) -> Result<()>
+        where F: FnMut(&str) -> Result<bool>
+    {
+        for tag in tags.iter().filter(|t| !MUTABLE_TAGS.contains(&t.as_str())) {
+            let image_tag = format!("{}:{}", &self.name, tag);
+            if exists(&image_tag)? {
+                return Err(Error::TagAlreadyExists { tag: image_tag }.into());
+            }
+        }
         Ok(())
     }
 
-    fn rm_image(&self, ui: &mut UI, tag: Option<&str>) -> Result<()> {
-        let image_tag = match tag {
-            Some(tag) => format!("{}:{}", &self.name, tag),
-            None => self.name.to_string(),
-        };
-        ui.status(Status::Deleting, format!("local image '{}'", &image_tag))?;
-        let mut cmd = docker_cmd();
-        cmd.arg("rmi").arg(&image_tag);
+    /// Returns `true` if the `name:tag` reference exists in the remote registry, running the
+    /// Docker program given by `engine`.
+    ///
+    /// # Errors
+    ///
+    /// * If the manifest lookup fails for any reason other than the manifest being unknown, such as
+    ///   failed authentication or an unreachable registry
+    fn remote_tag_exists<F>(&self, engine: F, image_tag: &str) -> Result<bool>
+        where F: Fn() -> Command
+    {
+        let mut cmd = engine();
+        cmd.arg("--config")
+           .arg(&self.workdir)
+           .arg("manifest")
+           .arg("inspect")
+           .arg(image_tag)
+           .stdout(Stdio::null());
         debug!("Running: {:?}", &cmd);
-        let exit_status = cmd.spawn()?.wait()?;
+        let output = cmd.output()?;
+        if output.status.success() {
+            return Ok(true);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_manifest_unknown(&stderr) {
+            Ok(false)
+        } else {
+            Err(Error::TagLookupFailed { tag:    image_tag.to_string(),
+                                         reason: stderr.trim().to_string(), }.into())
+        }
+    }
+
+    /// Runs an external scanner command against the image, with the image's first `name:tag`
+    /// reference appended as the final argument. The reference and image ID are also available
+    /// to the command as the `HAB_DOCKER_IMAGE` and `HAB_DOCKER_IMAGE_ID` environment variables.
+    ///
+    /// # Errors
+    ///
+    /// * If the command cannot be started
+    /// * If the command exits unsuccessfully and failures are not allowed
+    pub fn scan(&self, ui: &mut UI, command: &str, allow_failure: bool) -> Result<()> {
+        let image_tag = self.reference();
+        ui.status(Status::Executing,
+                  format!("image scan '{}' against '{}'", command, &image_tag))?;
+        let exit_status = self.run_image_command(command, &image_tag)?;
         if !exit_status.success() {
-            return Err(Error::RemoveImageFailed(exit_status).into());
+            if !allow_failure {
+                return Err(Error::ScanFailed(exit_status).into());
+            }
+            ui.warn(format!("Image scan of '{}' failed with exit code: {}, continuing",
+                            &image_tag, exit_status))?;
         }
 
         Ok(())
     }
-}
-
-/// A temporary file system build root for building a Docker image, based on Habitat packages.
-pub struct DockerBuildRoot(BuildRoot);
 
-impl DockerBuildRoot {
-    /// Builds a completed Docker build root from a `BuildRoot`, performing any final tasks on the
-    /// root file system.
+    /// Runs the image in a detached container for up to `timeout`, with `run_args` passed to
+    /// `docker run` before the image reference, then removes the container. The smoke test
+    /// passes early once the image's health check reports healthy, and otherwise passes if the
+    /// container is still running when the timeout elapses.
     ///
     /// # Errors
     ///
-    /// * If any remaining tasks cannot be performed in the build root
-    #[cfg(unix)]
-    pub fn from_build_root(build_root: BuildRoot, ui: &mut UI) -> Result<Self> {
-        let root = DockerBuildRoot(build_root);
-        root.add_users_and_groups(ui)?;
-        root.create_entrypoint(ui)?;
-        root.create_dockerfile(ui)?;
-
-        Ok(root)
+    /// * If the container cannot be started
+    /// * If the container exits, or its health check reports unhealthy, before the timeout
+    pub fn smoke_test(&self, ui: &mut UI, run_args: &[String], timeout: Duration) -> Result<()> {
+        self.smoke_test_with(ui, docker_cmd, run_args, timeout)
     }
 
-    #[cfg(windows)]
-    pub fn from_build_root(build_root: BuildRoot, ui: &mut UI) -> Result<Self> {
-        let root = DockerBuildRoot(build_root);
-        root.create_dockerfile(ui)?;
+    /// Runs the smoke test of the image, running the Docker program given by `engine`.
+    fn smoke_test_with<F>(&self,
+                          ui: &mut UI,
+                          engine: F,
+                          run_args: &[String],
+                          timeout: Duration)
+                          -> Result<()>
+        where F: Fn() -> Command
+    {
+        let image_tag = self.reference();
+        ui.status(Status::Executing,
+                  format!("smoke test of '{}' for up to {}s",
+                          &image_tag,
+                          timeout.as_secs()))?;
+        let mut cmd = engine();
+        cmd.arg("run")
+           .arg("--detach")
+           .args(run_args)
+           .arg(&image_tag);
+        debug!("Running: {:?}", &cmd);
+        let output = cmd.stderr(Stdio::inherit()).output()?;
+        if !output.status.success() {
+            return Err(Error::SmokeTestFailed(format!("the container could not be started, \
+                                                       docker run exited with code: {}",
+                                                      output.status)).into());
+        }
+        let container = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let result = Self::watch_container(&engine, &container, timeout);
+        let mut cmd = engine();
+        cmd.arg("rm").arg("--force").arg(&container);
+        debug!("Running: {:?}", &cmd);
+        match cmd.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(ref status) if status.success() => {}
+            _ => ui.warn(format!("Could not remove smoke test container '{}'", &container))?,
+        }
+        result?;
+        ui.status(Status::Verified, format!("smoke test of '{}'", &image_tag))?;
 
-        Ok(root)
+        Ok(())
     }
 
-    /// Destroys the temporary build root.
-    ///
-    /// Note that the build root will automatically destroy itself when it falls out of scope, so
-    /// a call to this method is not required, but calling this will provide more user-facing
-    /// progress and error reporting.
-    ///
-    /// # Errors
-    ///
-    /// * If the temporary work directory cannot be removed
-    pub fn destroy(self, ui: &mut UI) -> Result<()> { self.0.destroy(ui) }
+    /// Polls the state of a smoke test container until its health check reports healthy or the
+    /// timeout elapses, failing if it exits or reports unhealthy in the meantime.
+    fn watch_container<F>(engine: &F, container: &str, timeout: Duration) -> Result<()>
+        where F: Fn() -> Command
+    {
+        let started = Instant::now();
+        loop {
+            let mut cmd = engine();
+            cmd.arg("inspect")
+               .arg("--format")
+               .arg("{{.State.Status}} {{.State.ExitCode}} {{if \
+                     .State.Health}}{{.State.Health.Status}}{{end}}")
+               .arg(container);
+            debug!("Running: {:?}", &cmd);
+            let output = cmd.stderr(Stdio::null()).output()?;
+            if !output.status.success() {
+                return Err(Error::SmokeTestFailed(format!("the state of container '{}' could \
+                                                           not be inspected",
+                                                          container)).into());
+            }
+            let state = String::from_utf8_lossy(&output.stdout);
+            let mut fields = state.split_whitespace();
+            let status = fields.next().unwrap_or_default();
+            let exit_code = fields.next().unwrap_or_default();
+            match (status, fields.next()) {
+                (_, Some("healthy")) => return Ok(()),
+                (_, Some("unhealthy")) => {
+                    return Err(Error::SmokeTestFailed(String::from("the health check of the \
+                                                                    container reported \
+                                                                    unhealthy")).into());
+                }
+                ("created", _) | ("running", _) => {}
+                (status, _) => {
+                    return Err(Error::SmokeTestFailed(format!("the container stopped in state \
+                                                               '{}' with exit code: {}",
+                                                              status, exit_code)).into());
+                }
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Ok(());
+            }
+            thread::sleep(cmp::min(SMOKE_TEST_POLL_INTERVAL, timeout - elapsed));
+        }
+    }
 
-    /// Build the Docker image locally using the provided naming policy.
+    /// Runs an external signing command, such as `cosign sign`, against the pushed image, with
+    /// its digest-pinned reference (ex: `acme/redis@sha256:...`) appended as the final argument.
+    /// The reference and image ID are also available to the command as the `HAB_DOCKER_IMAGE`
+    /// and `HAB_DOCKER_IMAGE_ID` environment variables.
     ///
     /// # Errors
     ///
-    /// * If the Docker image cannot be created successfully
-    #[cfg(unix)]
-    pub fn export(&self,
-                  ui: &mut UI,
-                  naming: &Naming,
-                  memory: Option<&str>)
-                  -> Result<DockerImage> {
-        self.build_docker_image(ui, naming, memory)
+    /// * If the digest of the pushed image cannot be determined
+    /// * If the command cannot be started
+    /// * If the command exits unsuccessfully and failures are not allowed
+    pub fn sign(&self, ui: &mut UI, command: &str, allow_failure: bool) -> Result<()> {
+        let reference = self.repo_digest()?;
+        self.sign_reference(ui, command, &reference, allow_failure)
     }
 
-    #[cfg(windows)]
-    pub fn export(&self,
-                  ui: &mut UI,
-                  naming: &Naming,
-                  memory: Option<&str>)
-                  -> Result<DockerImage> {
-        let mut cmd = docker_cmd();
-        cmd.arg("version").arg("--format='{{.Server.Os}}'");
-        debug!("Running command: {:?}", cmd);
-        let result = cmd.output().expect("Docker command failed to spawn");
-        let os = String::from_utf8_lossy(&result.stdout);
-        if !os.contains("windows") {
-            return Err(Error::DockerNotInWindowsMode(os.to_string()).into());
+    /// Runs a signing command against the given digest-pinned reference of the image.
+    fn sign_reference(&self,
+                      ui: &mut UI,
+                      command: &str,
+                      reference: &str,
+                      allow_failure: bool)
+                      -> Result<()> {
+        ui.status(Status::Executing,
+                  format!("image signing '{}' against '{}'", command, reference))?;
+        let exit_status = self.run_image_command(command, reference)?;
+        if !exit_status.success() {
+            if !allow_failure {
+                return Err(Error::SignFailed(exit_status).into());
+            }
+            ui.warn(format!("Image signing of '{}' failed with exit code: {}, continuing",
+                            reference, exit_status))?;
         }
 
-        self.build_docker_image(ui, naming, memory)
+        Ok(())
     }
 
-    #[cfg(unix)]
-    fn add_users_and_groups(&self, ui: &mut UI) -> Result<()> {
-        use std::{fs::OpenOptions,
-                  io::Write};
+    /// Runs a command through the shell with an image reference appended as the final argument,
+    /// and available along with the image ID in the environment.
+    fn run_image_command(&self, command: &str, reference: &str) -> Result<ExitStatus> {
+        let mut cmd = shell_cmd(command, reference);
+        cmd.env("HAB_DOCKER_IMAGE", reference)
+           .env("HAB_DOCKER_IMAGE_ID", &self.id);
+        debug!("Running: {:?}", &cmd);
+        Ok(cmd.stdout(self.child_stdout()).spawn()?.wait()?)
+    }
 
-        let ctx = self.0.ctx();
-        let (users, groups) = ctx.svc_users_and_groups()?;
-        {
-            let file = "etc/passwd";
-            let mut f = OpenOptions::new().append(true)
-                                          .open(ctx.rootfs().join(&file))?;
-            for user in users {
-                ui.status(Status::Creating,
-                          format!("user '{}' in /{}", user.name, &file))?;
-                writeln!(f, "{}", user)?;
+    /// Removes the tags this export created from the local Docker engine. Other tags of the same
+    /// image, such as those it had before it was rebuilt, are left alone, and the image itself is
+    /// only removed once no tags remain.
+    ///
+    /// # Errors
+    ///
+    /// * If one or more of the image tags cannot be removed
+    /// * If the image is left untagged and cannot be removed
+    pub fn rm(self, ui: &mut UI) -> Result<()> { self.rm_with(ui, docker_cmd) }
+
+    /// Removes the tags this export created, running the Docker program given by `engine`.
+    fn rm_with<F>(self, ui: &mut UI, engine: F) -> Result<()>
+        where F: Fn() -> Command
+    {
+        ui.begin(format!("Cleaning up the tags of local Docker image '{}' created by this \
+                          export",
+                         self.name()))?;
+        if self.tags.is_empty() {
+            self.rm_image(ui, &engine, &self.name)?;
+        } else {
+            for tag in &self.tags {
+                self.rm_image(ui, &engine, &format!("{}:{}", &self.name, tag))?;
             }
         }
-        {
-            let file = "etc/group";
-            let mut f = OpenOptions::new().append(true)
-                                          .open(ctx.rootfs().join(&file))?;
-            for group in groups {
-                ui.status(Status::Creating,
-                          format!("group '{}' in /{}", group.name, &file))?;
-                writeln!(f, "{}", group)?;
+        // Removing an image's last tag removes the image, so it only remains if it was tagged
+        // before this export, or if the engine keeps untagged images.
+        match self.remaining_tags(&engine) {
+            Some(ref tags) if !tags.is_empty() => {
+                ui.status(Status::Skipping,
+                          format!("local image '{}', which is still tagged {}",
+                                  &self.id,
+                                  tags.join(", ")))?;
             }
+            Some(_) => self.rm_image(ui, &engine, &self.id)?,
+            None => {}
         }
+        ui.end(format!("Local Docker image '{}' tags: {} cleaned up",
+                       self.name(),
+                       self.tags().join(", "),))?;
+
         Ok(())
     }
 
-    #[cfg(unix)]
-    fn create_entrypoint(&self, ui: &mut UI) -> Result<()> {
-        use habitat_core::util::posix_perm;
+    /// Returns the tags which remain on the image, or `None` if the image no longer exists.
+    fn remaining_tags<F>(&self, engine: &F) -> Option<Vec<String>>
+        where F: Fn() -> Command
+    {
+        let mut cmd = engine();
+        cmd.arg("image")
+           .arg("inspect")
+           .arg("--format")
+           .arg("{{range .RepoTags}}{{println .}}{{end}}")
+           .arg(&self.id);
+        debug!("Running: {:?}", &cmd);
+        match cmd.stderr(Stdio::null()).output() {
+            Ok(ref output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).split_whitespace()
+                                                            .map(str::to_string)
+                                                            .collect())
+            }
+            _ => None,
+        }
+    }
 
-        /// The entrypoint script template.
-        const INIT_SH: &str = include_str!("../defaults/init.sh.hbs");
+    /// Returns the ID of this image.
+    pub fn id(&self) -> &str { self.id.as_str() }
 
-        ui.status(Status::Creating, "entrypoint script")?;
-        let ctx = self.0.ctx();
-        let busybox_shell =
-            util::pkg_path_for(&util::busybox_ident()?, ctx.rootfs())?.join("bin/sh");
+    /// Returns the name of this image.
+    pub fn name(&self) -> &str { self.name.as_str() }
+
+    /// Returns the image's first `name:tag` reference, or its name if it has no tags.
+    pub fn reference(&self) -> String {
+        match self.tags.first() {
+            Some(tag) => format!("{}:{}", &self.name, tag),
+            None => self.name.to_string(),
+        }
+    }
+
+    /// Returns the destination for the standard output of Docker commands run on this image.
+    fn child_stdout(&self) -> Stdio {
+        if self.quiet {
+            Stdio::null()
+        } else {
+            Stdio::inherit()
+        }
+    }
+
+    /// Returns the uncompressed size of the root file system the image was built from, if known.
+    pub fn rootfs_size_bytes(&self) -> Option<u64> { self.rootfs_size_bytes }
+
+    /// Returns the list of tags for this image.
+    pub fn tags(&self) -> &[String] { &self.tags }
+
+    /// Returns the fully qualified identifiers of the Habitat packages in this image, in
+    /// dependency order.
+    pub fn packages(&self) -> &[String] { &self.packages }
+
+    /// Returns each `name:tag` reference for this image, decomposed into its parts.
+    pub fn parsed_tags(&self) -> Vec<ParsedTag> {
+        if self.tags.is_empty() {
+            vec![ParsedTag::parse(&self.name)]
+        } else {
+            self.tags
+                .iter()
+                .map(|tag| ParsedTag::parse(&format!("{}:{}", &self.name, tag)))
+                .collect()
+        }
+    }
+
+    /// Create a build report with image metadata in the given path.
+    ///
+    /// At most `keep` reports, counting the new one, are kept in the path. Previous reports are
+    /// rotated out to `last_docker_export.env.1`, `last_docker_export.env.2`, and so on, the
+    /// most recent first, so a `keep` of 1 overwrites the previous report.
+    ///
+    /// # Errors
+    ///
+    /// * If the destination directory cannot be created
+    /// * If previous reports cannot be rotated
+    /// * If the report file cannot be written
+    pub fn create_report<P: AsRef<Path>>(&self, ui: &mut UI, dst: P, keep: usize) -> Result<()> {
+        let contents = self.render_report()?;
+        let report = dst.as_ref().join(EXPORT_REPORT);
+        ui.status(Status::Creating,
+                  format!("build report {}", report.display()))?;
+        fs::create_dir_all(&dst)?;
+        rotate_reports(&report, keep)?;
+        util::write_file(&report, &contents)?;
+        Ok(())
+    }
+
+    /// Writes the build report with image metadata to the given writer, rather than to a
+    /// results directory.
+    ///
+    /// # Errors
+    ///
+    /// * If the writer cannot be written
+    pub fn write_report(&self, out: &mut dyn Write) -> Result<()> {
+        out.write_all(self.render_report()?.as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Renders the build report with image metadata.
+    fn render_report(&self) -> Result<String> {
+        let name_tags: Vec<_> = self.tags
+                                    .iter()
+                                    .map(|t| format!("{}:{}", &self.name, t))
+                                    .collect();
         let json = json!({
-            "busybox_shell": busybox_shell,
-            "path": ctx.env_path(),
-            "sup_bin": format!("{} sup", ctx.bin_path().join("hab").display()),
-            "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+            "id": &self.id,
+            "name": &self.name,
+            "tags": self.tags.join(","),
+            "name_tags": name_tags.join(","),
+            "rootfs_size_bytes": self.rootfs_size_bytes,
         });
-        let init = ctx.rootfs().join("init.sh");
-        util::write_file(&init,
-                         &Handlebars::new().template_render(INIT_SH, &json)
-                                           .map_err(SyncFailure::new)?)?;
-        posix_perm::set_permissions(init.to_string_lossy().as_ref(), 0o0755)?;
+        Ok(Handlebars::new().template_render(BUILD_REPORT, &json)
+                            .map_err(SyncFailure::new)?)
+    }
+
+    /// Creates a provenance document at the given path, describing how the image was built and
+    /// where it is destined, so that it can be signed and attached to the image.
+    ///
+    /// # Errors
+    ///
+    /// * If the image digest cannot be read
+    /// * If the provenance document cannot be written
+    pub fn create_provenance<P: AsRef<Path>>(&self,
+                                             ui: &mut UI,
+                                             dst: P,
+                                             registry_url: Option<&str>)
+                                             -> Result<()> {
+        let dst = dst.as_ref();
+        ui.status(Status::Creating,
+                  format!("provenance document {}", dst.display()))?;
+        let provenance = self.provenance(&self.digest()?, registry_url, Utc::now());
+        util::write_file(dst, &serde_json::to_string_pretty(&provenance)?)?;
         Ok(())
     }
 
-    fn create_dockerfile(&self, ui: &mut UI) -> Result<()> {
-        ui.status(Status::Creating, "image Dockerfile")?;
-        let ctx = self.0.ctx();
+    /// Returns the provenance document for the image with the given digest.
+    fn provenance(&self,
+                  digest: &str,
+                  registry_url: Option<&str>,
+                  finished_on: DateTime<Utc>)
+                  -> serde_json::Value {
+        json!({
+            "builder": {
+                "id": &*PROGRAM_NAME,
+                "version": VERSION.trim(),
+            },
+            "subject": {
+                "name": &self.name,
+                "digest": digest,
+                "tags": &self.tags,
+            },
+            "materials": &self.packages,
+            "build_finished_on": finished_on.to_rfc3339(),
+            "destination": {
+                "registry": registry_url,
+                "references": self.tags
+                                  .iter()
+                                  .map(|t| format!("{}:{}", &self.name, t))
+                                  .collect::<Vec<_>>(),
+            },
+        })
+    }
+
+    /// Writes the digest of the image (ex: `sha256:...`) to the given path. Once the image has been
+    /// pushed, this is the digest of its manifest in the remote registry, as recorded by the
+    /// Docker engine when pushing it, otherwise it is the image's content digest.
+    ///
+    /// # Errors
+    ///
+    /// * If the digest of the image, or of the pushed image, cannot be determined
+    /// * If the file cannot be written
+    pub fn create_digest_file<P: AsRef<Path>>(&self,
+                                              ui: &mut UI,
+                                              dst: P,
+                                              pushed: bool)
+                                              -> Result<()> {
+        self.create_digest_file_with(ui, docker_cmd, dst.as_ref(), pushed)
+    }
+
+    /// Writes the digest of the image to the given path, running the Docker program given by
+    /// `engine`.
+    fn create_digest_file_with<F>(&self,
+                                  ui: &mut UI,
+                                  engine: F,
+                                  dst: &Path,
+                                  pushed: bool)
+                                  -> Result<()>
+        where F: Fn() -> Command
+    {
+        let digest = if pushed {
+            self.repo_digest_with(engine)?
+                .as_deref()
+                .and_then(reference_digest)
+                .map(str::to_string)
+                .ok_or_else(|| Error::PushedDigestNotFound(self.name.clone()))?
+        } else {
+            self.digest_with(engine)?
+        };
+        write_digest_file(ui, dst, &digest)
+    }
+
+    /// Creates a Kubernetes `Deployment` manifest at the given path, which runs the image and
+    /// exposes its ports. If the primary service has a health check, it is used as the container's
+    /// liveness probe.
+    ///
+    /// # Errors
+    ///
+    /// * If the manifest cannot be written
+    pub fn create_k8s_deployment<P: AsRef<Path>>(&self, ui: &mut UI, dst: P) -> Result<()> {
+        let dst = dst.as_ref();
+        ui.status(Status::Creating,
+                  format!("Kubernetes deployment manifest {}", dst.display()))?;
+        util::write_file(dst, &self.k8s_deployment()?)?;
+        Ok(())
+    }
+
+    /// Returns the Kubernetes `Deployment` manifest for the image.
+    fn k8s_deployment(&self) -> Result<String> {
+        let repository = self.name.rsplit('/').next().unwrap_or(&self.name);
+        let http_gateway_port = HttpListenAddr::default().port().to_string();
+        let ports: Vec<_> = iter::once(&http_gateway_port).chain(&self.exposes)
+                                                          .collect();
+        // Values are rendered as JSON strings, which are also valid double-quoted YAML strings
+        let annotations: Vec<_> =
+            self.annotations
+                .iter()
+                .map(|a| json!({ "key": a.key, "value": json!(a.value).to_string() }))
+                .collect();
         let json = json!({
-            "base_image": ctx.base_image(),
-            "rootfs": ctx.rootfs().file_name().expect("file_name exists")
-                .to_string_lossy()
-                .as_ref(),
-            "path": ctx.env_path(),
-            "hab_path": util::pkg_path_for(
-                &PackageIdent::from_str("core/hab")?,
-                ctx.rootfs())?.join("bin/hab")
-                .to_string_lossy()
-                .replace("\\", "/"),
-            "exposes": ctx.svc_exposes().join(" "),
-            "multi_layer": ctx.multi_layer(),
-            "primary_svc_ident": ctx.primary_svc_ident().to_string(),
-            "installed_primary_svc_ident": ctx.installed_primary_svc_ident()?.to_string(),
-            "environment": ctx.environment,
-            "packages": self.0.graph().reverse_topological_sort().iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "name": repository.replace(|c: char| c == '_' || c == '.', "-"),
+            "image": self.reference(),
+            "ports": ports,
+            "http_gateway_port": http_gateway_port,
+            "health_check_path": self.health_check_path,
+            "annotations": annotations,
         });
-        util::write_file(self.0.workdir().join("Dockerfile"),
-                         &Handlebars::new().template_render(DOCKERFILE, &json)
-                                           .map_err(SyncFailure::new)?)?;
-        Ok(())
+        Ok(Handlebars::new().template_render(K8S_DEPLOYMENT, &json)
+                            .map_err(SyncFailure::new)?)
     }
 
-    fn build_docker_image(&self,
-                          ui: &mut UI,
-                          naming: &Naming,
-                          memory: Option<&str>)
-                          -> Result<DockerImage> {
-        ui.status(Status::Creating, "Docker image")?;
-        let ident = self.0.ctx().installed_primary_svc_ident()?;
-        let version = &ident.version.expect("version exists");
-        let release = &ident.release.expect("release exists");
+    pub fn create_docker_config_file(&self,
+                                     credentials: &Credentials,
+                                     registry_url: Option<&str>)
+                                     -> Result<()> {
+        let config = self.workdir.join("config.json");
+        fs::create_dir_all(&self.workdir)?;
+        let registry = registry_url.unwrap_or(DEFAULT_REGISTRY_URL);
+        debug!("Using registry: {:?}", registry);
         let json = json!({
-            "pkg_origin": ident.origin,
-            "pkg_name": ident.name,
-            "pkg_version": &version,
-            "pkg_release": &release,
-            "channel": self.0.ctx().channel().as_str(),
+            "auths": {
+                registry: {
+                    "auth": credentials.token
+                }
+            }
         });
-        let image_name = match naming.custom_image_name {
-                             Some(ref custom) => {
-                                 // TODO (CM): why is this handlebars???
-                                 Handlebars::new().template_render(custom, &json)
-                                                  .map_err(SyncFailure::new)?
-                             }
-                             None => format!("{}/{}", ident.origin, ident.name),
-                         }.to_lowercase();
+        util::write_file(&config, &serde_json::to_string(&json).unwrap())?;
+        Ok(())
+    }
 
-        let image_name = match naming.registry_url {
-                             Some(ref url) => format!("{}/{}", url, image_name),
-                             None => image_name,
-                         }.to_lowercase();
+    /// Installs a client certificate and key for the registry, as `client.cert` and `client.key`
+    /// in the `certs.d/<registry-host>` directory of the Docker configuration used for the push.
+    ///
+    /// # Errors
+    ///
+    /// * If the certificate or key cannot be copied
+    pub fn create_registry_client_cert<C, K>(&self,
+                                             registry_url: Option<&str>,
+                                             cert: C,
+                                             key: K)
+                                             -> Result<()>
+        where C: AsRef<Path>,
+              K: AsRef<Path>
+    {
+        let host = registry_host(registry_url);
+        // Docker on Windows drops the `:` before the port, which file names cannot contain
+        #[cfg(windows)]
+        let host = host.replace(':', "");
+        let dir = self.workdir.join("certs.d").join(host);
+        debug!("Installing registry client certificate in {}",
+               dir.display());
+        fs::create_dir_all(&dir)?;
+        fs::copy(cert, dir.join("client.cert"))?;
+        fs::copy(key, dir.join("client.key"))?;
+        Ok(())
+    }
 
-        let mut builder = DockerBuilder::new(self.0.workdir(), image_name);
-        if naming.version_release_tag {
-            builder = builder.tag(format!("{}-{}", &version, &release));
-        }
-        if naming.version_tag {
-            builder = builder.tag(version.clone());
-        }
-        if naming.latest_tag {
-            builder = builder.tag("latest".to_string());
+    /// Removes a tag of the image, or the image itself when given its ID. The image is only
+    /// removed along with a tag if no other tags remain.
+    fn rm_image<F>(&self, ui: &mut UI, engine: &F, image_tag: &str) -> Result<()>
+        where F: Fn() -> Command
+    {
+        ui.status(Status::Deleting, format!("local image '{}'", image_tag))?;
+        let mut cmd = engine();
+        cmd.arg("rmi").arg(image_tag);
+        debug!("Running: {:?}", &cmd);
+        let exit_status = cmd.stdout(self.child_stdout()).spawn()?.wait()?;
+        if !exit_status.success() {
+            return Err(Error::RemoveImageFailed(exit_status).into());
         }
-        if let Some(memory) = memory {
-            builder = builder.memory(memory);
+
+        Ok(())
+    }
+}
+
+/// Everything known about an exported image: the image itself, its tags, digest, packages, sizes,
+/// and how long each phase of the export took.
+#[derive(Debug)]
+pub struct ExportSummary {
+    /// The exported image.
+    image:            DockerImage,
+    /// The full content digest of the image (ex: `sha256:...`).
+    digest:           String,
+    /// The size, in bytes, of the image as reported by the Docker engine.
+    image_size_bytes: u64,
+    /// The name and duration of every phase of the export, in order.
+    phases:           Vec<(&'static str, Duration)>,
+}
+
+impl ExportSummary {
+    pub(crate) fn new(image: DockerImage,
+                      digest: String,
+                      image_size_bytes: u64,
+                      timings: &Timings)
+                      -> Self {
+        ExportSummary { image,
+                        digest,
+                        image_size_bytes,
+                        phases: timings.phases().to_vec() }
+    }
+
+    /// Returns the exported image.
+    pub fn image(&self) -> &DockerImage { &self.image }
+
+    /// Returns the exported image, consuming the summary.
+    pub fn into_image(self) -> DockerImage { self.image }
+
+    /// Returns the list of tags for the image.
+    pub fn tags(&self) -> &[String] { self.image.tags() }
+
+    /// Returns the full content digest of the image (ex: `sha256:...`).
+    pub fn digest(&self) -> &str { &self.digest }
+
+    /// Returns the fully qualified identifiers of the Habitat packages in the image, in dependency
+    /// order.
+    pub fn packages(&self) -> &[String] { self.image.packages() }
+
+    /// Returns the uncompressed size of the root file system the image was built from, if known.
+    pub fn rootfs_size_bytes(&self) -> Option<u64> { self.image.rootfs_size_bytes() }
+
+    /// Returns the size, in bytes, of the image as reported by the Docker engine.
+    pub fn image_size_bytes(&self) -> u64 { self.image_size_bytes }
+
+    /// Returns the name and duration of every phase of the export, in order.
+    pub fn phases(&self) -> &[(&'static str, Duration)] { &self.phases }
+
+    /// Returns the total duration of the export.
+    pub fn duration(&self) -> Duration { self.phases.iter().map(|(_, d)| *d).sum() }
+}
+
+/// Checks that an image size, in bytes, is no larger than the given limit.
+fn check_image_size(actual: u64, limit: ByteSize) -> Result<()> {
+    if actual > limit.bytes() {
+        Err(Error::ImageTooLarge { actual,
+                                   limit: limit.bytes() }.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Warns that annotations are not applied to the image, as the Docker daemon cannot set
+/// annotations on an image manifest. They are only applied to the Kubernetes `Deployment`
+/// manifest, if one is written.
+fn warn_annotations_not_applied(ui: &mut UI, annotations: &[Annotation]) -> Result<()> {
+    if !annotations.is_empty() {
+        let annotations: Vec<_> = annotations.iter().map(ToString::to_string).collect();
+        ui.warn(format!("Not applying annotations to the image, which the Docker daemon does \
+                         not support: {}",
+                        annotations.join(", ")))?;
+    }
+    Ok(())
+}
+
+/// Returns the short tag for an image digest, made from the first 12 hex characters of the
+/// digest (ex: `sha-0123456789ab`), or `None` if the digest is too short or not hex.
+fn digest_short_tag(digest: &str) -> Option<String> {
+    let hex = digest.trim_start_matches("sha256:");
+    match hex.get(..12) {
+        Some(short) if short.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Some(format!("sha-{}", short.to_lowercase()))
         }
-        if let Some(ref custom) = naming.custom_tag {
-            builder = builder.tag(Handlebars::new().template_render(custom, &json)
-                                                   .map_err(SyncFailure::new)?
-                                                   .to_lowercase());
+        _ => None,
+    }
+}
+
+/// Returns the digest of a digest-pinned reference (ex: `sha256:...` from
+/// `acme/redis@sha256:...`), or `None` if the reference is not pinned to a SHA-256 digest.
+fn reference_digest(reference: &str) -> Option<&str> {
+    let digest = &reference[reference.rfind('@')? + 1..];
+    if is_valid_digest(digest) {
+        Some(digest)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the digest is `sha256:` followed by 64 lowercase hex characters.
+fn is_valid_digest(digest: &str) -> bool {
+    digest.starts_with("sha256:")
+    && digest.len() == 71
+    && digest[7..].chars()
+                  .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+/// Writes a digest to the given path, followed by a newline.
+fn write_digest_file(ui: &mut UI, dst: &Path, digest: &str) -> Result<()> {
+    ui.status(Status::Creating, format!("digest file {}", dst.display()))?;
+    util::write_file(dst, &format!("{}\n", digest))
+}
+
+/// Returns `true` if the name is a valid repository name in the Docker reference grammar: an
+/// optional registry host followed by `/`-separated path components.
+fn is_valid_repository(name: &str) -> bool {
+    let components: Vec<&str> = name.split('/').collect();
+    let path = match components.split_first() {
+        Some((host, path)) if !path.is_empty() && is_registry_host(host) => {
+            let valid_host = host.chars()
+                                 .all(|c| c.is_ascii_alphanumeric() || ".-:".contains(c));
+            if !valid_host {
+                return false;
+            }
+            path
         }
-        builder.build()
+        _ => &components[..],
+    };
+    path.iter().all(|c| is_valid_path_component(c))
+}
+
+/// Returns `true` if the tag is valid in the Docker reference grammar: up to 128 letters, digits,
+/// `_`, `.`, and `-`, not starting with `.` or `-`.
+pub(crate) fn is_valid_tag(tag: &str) -> bool {
+    tag.len() <= 128
+    && tag.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+    && tag.chars()
+          .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+}
+
+/// Returns `true` if the path component is lowercase alphanumerics, with single separators of
+/// `.`, `_`, or `__`, or any number of `-`, between them.
+fn is_valid_path_component(component: &str) -> bool {
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    component.starts_with(is_alphanumeric)
+    && component.ends_with(is_alphanumeric)
+    && component.split(is_alphanumeric)
+                .filter(|sep| !sep.is_empty())
+                .all(|sep| sep == "." || sep == "_" || sep == "__" || sep.chars().all(|c| c == '-'))
+}
+
+/// An image reference decomposed into its registry, repository, tag, and digest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedTag {
+    /// The registry host (and optional port), if the reference names one.
+    pub registry:   Option<String>,
+    /// The repository path within the registry (ex: `acme/redis`).
+    pub repository: String,
+    /// The tag, if the reference has one.
+    pub tag:        Option<String>,
+    /// The content digest, if the reference has one (ex: `sha256:...`).
+    pub digest:     Option<String>,
+}
+
+impl ParsedTag {
+    /// Decomposes an image reference of the form `[REGISTRY/]REPOSITORY[:TAG][@DIGEST]`.
+    ///
+    /// As with the Docker CLI, the first path component is only treated as a registry host if it
+    /// contains a `.` or a `:`, or is `localhost`.
+    pub fn parse(reference: &str) -> Self {
+        let (name, digest) = match reference.find('@') {
+            Some(i) => (&reference[..i], Some(reference[i + 1..].to_string())),
+            None => (reference, None),
+        };
+        // A colon after the last slash separates the tag; any earlier colon is a registry port
+        let (name, tag) = match name.rfind(':') {
+            Some(i) if !name[i..].contains('/') => (&name[..i], Some(name[i + 1..].to_string())),
+            _ => (name, None),
+        };
+        let (registry, repository) = match name.find('/') {
+            Some(i) if is_registry_host(&name[..i]) => {
+                (Some(name[..i].to_string()), &name[i + 1..])
+            }
+            _ => (None, name),
+        };
+
+        ParsedTag { registry,
+                    repository: repository.to_string(),
+                    tag,
+                    digest }
     }
 }
 
-/// Returns a `Command` for the Docker program.
-fn docker_cmd() -> Command {
-    Command::new(docker::command_path().expect("Unable to locate docker"))
+/// Returns the host, and port if any, of a registry URL, which names the registry's `certs.d`
+/// directory (ex: `registry.acme.io:5000` for `https://registry.acme.io:5000/v2/`).
+fn registry_host(registry_url: Option<&str>) -> &str {
+    let url = registry_url.unwrap_or(DEFAULT_REGISTRY_URL);
+    let url = url.splitn(2, "://").last().unwrap_or(url);
+    url.split('/').next().unwrap_or(url)
+}
+
+fn is_registry_host(component: &str) -> bool {
+    component.contains('.') || component.contains(':') || component == "localhost"
+}
+
+/// A temporary file system build root for building a Docker image, based on Habitat packages.
+pub struct DockerBuildRoot(BuildRoot);
+
+impl DockerBuildRoot {
+    /// Builds a completed Docker build root from a `BuildRoot`, performing any final tasks on the
+    /// root file system.
+    ///
+    /// # Errors
+    ///
+    /// * If any remaining tasks cannot be performed in the build root
+    #[cfg(unix)]
+    pub fn from_build_root(build_root: BuildRoot, ui: &mut UI) -> Result<Self> {
+        let root = DockerBuildRoot(build_root);
+        root.add_users_and_groups(ui)?;
+        root.create_workdir(ui)?;
+        if !root.0.ctx().no_shell() {
+            root.create_entrypoint(ui)?;
+        }
+        root.create_dockerfile(ui)?;
+
+        Ok(root)
+    }
+
+    #[cfg(windows)]
+    pub fn from_build_root(build_root: BuildRoot, ui: &mut UI) -> Result<Self> {
+        let root = DockerBuildRoot(build_root);
+        root.create_dockerfile(ui)?;
+
+        Ok(root)
+    }
+
+    /// Returns the uncompressed size, in bytes, of the assembled root file system.
+    ///
+    /// # Errors
+    ///
+    /// * If the root file system cannot be read
+    pub fn size_bytes(&self) -> Result<u64> { tree_size(self.0.ctx().rootfs()) }
+
+    /// Saves a copy of the generated `Dockerfile` to the given path.
+    ///
+    /// # Errors
+    ///
+    /// * If the destination directory cannot be created
+    /// * If the `Dockerfile` cannot be copied
+    pub fn save_dockerfile<P: AsRef<Path>>(&self, ui: &mut UI, dst: P) -> Result<()> {
+        let dst = dst.as_ref();
+        ui.status(Status::Creating, format!("Dockerfile {}", dst.display()))?;
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(self.dockerfile_path(), dst)?;
+        Ok(())
+    }
+
+    /// Checks the generated `Dockerfile` against a policy, such as a Dockerfile linter.
+    ///
+    /// # Errors
+    ///
+    /// * If the policy command cannot be started
+    /// * If the policy command exits unsuccessfully and failures are not allowed
+    pub fn check_dockerfile_policy(&self, ui: &mut UI, policy: &DockerfilePolicy) -> Result<()> {
+        check_dockerfile_policy(ui, &self.dockerfile_path(), policy)
+    }
+
+    /// Destroys the temporary build root.
+    ///
+    /// Note that the build root will automatically destroy itself when it falls out of scope, so
+    /// a call to this method is not required, but calling this will provide more user-facing
+    /// progress and error reporting.
+    ///
+    /// # Errors
+    ///
+    /// * If the temporary work directory cannot be removed
+    pub fn destroy(self, ui: &mut UI) -> Result<()> { self.0.destroy(ui) }
+
+    /// Build the Docker image locally using the provided naming policy and build options.
+    ///
+    /// # Errors
+    ///
+    /// * If the Docker image cannot be created successfully
+    #[cfg(unix)]
+    pub fn export(&self,
+                  ui: &mut UI,
+                  naming: &Naming,
+                  options: &BuildOptions)
+                  -> Result<DockerImage> {
+        self.build_docker_image(ui, naming, options)
+    }
+
+    #[cfg(windows)]
+    pub fn export(&self,
+                  ui: &mut UI,
+                  naming: &Naming,
+                  options: &BuildOptions)
+                  -> Result<DockerImage> {
+        let mut cmd = docker_cmd();
+        cmd.arg("version").arg("--format='{{.Server.Os}}'");
+        debug!("Running command: {:?}", cmd);
+        let result = cmd.output().expect("Docker command failed to spawn");
+        let os = String::from_utf8_lossy(&result.stdout);
+        if !os.contains("windows") {
+            return Err(Error::DockerNotInWindowsMode(os.to_string()).into());
+        }
+
+        self.build_docker_image(ui, naming, options)
+    }
+
+    #[cfg(unix)]
+    fn add_users_and_groups(&self, ui: &mut UI) -> Result<()> {
+        use std::fs::OpenOptions;
+
+        let ctx = self.0.ctx();
+        let (users, groups) = ctx.svc_users_and_groups()?;
+        {
+            let file = "etc/passwd";
+            let mut f = OpenOptions::new().append(true)
+                                          .open(ctx.rootfs().join(&file))?;
+            for user in users {
+                ui.status(Status::Creating,
+                          format!("user '{}' in /{}", user.name, &file))?;
+                writeln!(f, "{}", user)?;
+            }
+        }
+        {
+            let file = "etc/group";
+            let mut f = OpenOptions::new().append(true)
+                                          .open(ctx.rootfs().join(&file))?;
+            for group in groups {
+                ui.status(Status::Creating,
+                          format!("group '{}' in /{}", group.name, &file))?;
+                writeln!(f, "{}", group)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn create_workdir(&self, ui: &mut UI) -> Result<()> {
+        let ctx = self.0.ctx();
+        let workdir = ctx.image_workdir();
+        ui.status(Status::Creating,
+                  format!("working directory {}", workdir.display()))?;
+        rootfs::create_workdir(ctx.rootfs(), workdir)
+    }
+
+    #[cfg(unix)]
+    fn create_entrypoint(&self, ui: &mut UI) -> Result<()> {
+        use habitat_core::util::posix_perm;
+
+        /// The entrypoint script template.
+        const INIT_SH: &str = include_str!("../defaults/init.sh.hbs");
+
+        ui.status(Status::Creating, "entrypoint script")?;
+        let ctx = self.0.ctx();
+        let busybox_shell = util::pkg_path_for(ctx.busybox_ident(), ctx.rootfs())?.join("bin/sh");
+        let json = json!({
+            "busybox_shell": busybox_shell,
+            "path": ctx.env_path(),
+            "sup_bin": format!("{} sup", ctx.bin_path().join("hab").display()),
+            "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+            "bundled": ctx.bundled(),
+        });
+        let init = ctx.rootfs().join("init.sh");
+        util::write_file(&init,
+                         &Handlebars::new().template_render(INIT_SH, &json)
+                                           .map_err(SyncFailure::new)?)?;
+        posix_perm::set_permissions(init.to_string_lossy().as_ref(), 0o0755)?;
+        Ok(())
+    }
+
+    fn create_dockerfile(&self, ui: &mut UI) -> Result<()> {
+        ui.status(Status::Creating, "image Dockerfile")?;
+        let ctx = self.0.ctx();
+        let json = json!({
+            "base_image": ctx.base_image(),
+            "base_image_args": build_arg::referenced_keys(ctx.base_image()),
+            "rootfs": ctx.rootfs().file_name().expect("file_name exists")
+                .to_string_lossy()
+                .as_ref(),
+            "path": ctx.env_path(),
+            "hab_path": util::pkg_path_for(
+                &PackageIdent::from_str("core/hab")?,
+                ctx.rootfs())?.join("bin/hab")
+                .to_string_lossy()
+                .replace("\\", "/"),
+            "exposes": ctx.svc_exposes().join(" "),
+            "multi_layer": ctx.multi_layer(),
+            "workdir": ctx.image_workdir().to_string_lossy().replace("\\", "/"),
+            "stop_signal": ctx.stop_signal().map(ToString::to_string),
+            "entrypoint": ctx.entrypoint().map(|p| p.to_string_lossy().replace("\\", "/")),
+            "volumes": ctx.volumes(),
+            "required_capabilities": ctx.capabilities()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            "env_vars": ctx.env_vars()
+                .iter()
+                .map(|v| json!({ "name": v.name, "value": v.quoted_value() }))
+                .collect::<Vec<_>>(),
+            "no_shell": ctx.no_shell(),
+            "secret_mounts": ctx.secret_mounts().join(","),
+            "userns_remap": ctx.userns_remap().map(|r| r.to_string()),
+            "userns_owner": ctx.userns_remap().map(|r| format!("{0}:{0}", r.start())),
+            "config_rendered": ctx.config_rendered(),
+            "bundled": ctx.bundled(),
+            "init": ctx.init().entrypoint(),
+            "target": ctx.platform()?.target().to_string(),
+            "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+            "installed_primary_svc_ident": ctx.installed_primary_svc_ident()?.to_string(),
+            "environment": ctx.environment,
+            "packages": self.0.graph().reverse_topological_sort().iter().map(ToString::to_string).collect::<Vec<_>>(),
+        });
+        util::write_file(self.dockerfile_path(), &render_dockerfile(&json)?)?;
+        Ok(())
+    }
+
+    /// Returns the path to the generated `Dockerfile` in the build root.
+    fn dockerfile_path(&self) -> PathBuf { self.0.workdir().join("Dockerfile") }
+
+    fn build_docker_image<'a>(&'a self,
+                              ui: &mut UI,
+                              naming: &Naming,
+                              options: &'a BuildOptions)
+                              -> Result<DockerImage> {
+        ui.status(Status::Creating, "Docker image")?;
+        let ident = self.0.ctx().installed_primary_svc_ident()?;
+        let (image_name, tags) = expand_tags(ui, naming, &ident, self.0.ctx().channel())?;
+        let mut builder = DockerBuilder::new(self.0.workdir(), image_name);
+        if let Some(platform) = self.0.ctx().build_platform()? {
+            builder = builder.platform(platform.to_string());
+        }
+        for tag in tags {
+            builder = builder.tag(tag);
+        }
+        if let Some(memory) = options.memory {
+            builder = builder.memory(memory);
+        }
+        if let Some(memory_swap) = options.memory_swap {
+            builder = builder.memory_swap(memory_swap);
+        }
+        for image in &options.cache_from {
+            builder = builder.cache_from(image);
+        }
+        let base_image = self.0.ctx().base_image();
+        let base_image_args = build_arg::referenced_keys(base_image);
+        for arg in &options.build_args {
+            if !base_image_args.contains(&arg.key) {
+                ui.warn(format!("The build arg {} is not referenced by the base image {}, so \
+                                 it has no effect. Use --base-image with a custom base image \
+                                 which references ${{{}}}",
+                                arg.key, base_image, arg.key))?;
+            }
+            builder = builder.build_arg(arg);
+        }
+        if !options.engine_opts.is_empty() {
+            ui.warn(format!("Passing unvalidated arguments to docker build: {}",
+                            options.engine_opts.join(" ")))?;
+            for opt in &options.engine_opts {
+                builder = builder.engine_opt(opt);
+            }
+        }
+        warn_annotations_not_applied(ui, &options.annotations)?;
+        if options.verbose {
+            builder = builder.verbose();
+        }
+        if options.quiet {
+            builder = builder.quiet();
+        }
+        let mut image = builder.build(ui)?;
+        image.rootfs_size_bytes = Some(self.size_bytes()?);
+        image.packages = self.0
+                             .graph()
+                             .reverse_topological_sort()
+                             .iter()
+                             .map(ToString::to_string)
+                             .collect();
+        image.exposes = self.0
+                            .ctx()
+                            .svc_exposes()
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect();
+        if self.0.ctx().primary_svc_has_health_check()? {
+            image.health_check_path = Some(format!("/services/{}/default/health", ident.name));
+        }
+        image.annotations = options.annotations.clone();
+        if naming.digest_tag {
+            image.tag_digest_short(ui)?;
+        }
+        Ok(image)
+    }
+}
+
+/// Returns the image name, and the tags in the order they are applied, which a naming policy gives
+/// the image of a package installed from a channel. The short digest tag is left out, as it is
+/// only known once the image is built.
+///
+/// # Errors
+///
+/// * If the custom image name or tag cannot be rendered
+/// * If the custom image name is not a valid repository name
+/// * If a warning cannot be written
+pub(crate) fn expand_tags(ui: &mut UI,
+                          naming: &Naming,
+                          ident: &PackageIdent,
+                          channel: &ChannelIdent)
+                          -> Result<(String, Vec<String>)> {
+    let json = json!({
+        "pkg_origin": ident.origin,
+        "pkg_name": ident.name,
+        "pkg_version": ident.version,
+        "pkg_release": ident.release,
+        "channel": channel.as_str(),
+    });
+    let image_name = match naming.custom_image_name {
+                         Some(ref custom) => {
+                             // TODO (CM): why is this handlebars???
+                             let rendered = Handlebars::new().template_render(custom, &json)
+                                                             .map_err(SyncFailure::new)?;
+                             let expanded =
+                                 Naming::expand_image_name(&rendered, &ident)?.to_lowercase();
+                             if !is_valid_repository(&expanded) {
+                                 return Err(Error::InvalidImageName(expanded).into());
+                             }
+                             expanded
+                         }
+                         None => format!("{}/{}", ident.origin, ident.name),
+                     }.to_lowercase();
+
+    let image_name = match naming.registry_url {
+                         Some(ref url) => format!("{}/{}", url, image_name),
+                         None => image_name,
+                     }.to_lowercase();
+
+    let mut tags = naming.version_tags(ui, &ident)?;
+    if naming.latest_tag {
+        tags.push("latest".to_string());
+    }
+    if let Some(ref custom) = naming.custom_tag {
+        tags.push(Handlebars::new().template_render(custom, &json)
+                                   .map_err(SyncFailure::new)?
+                                   .to_lowercase());
+    }
+    Ok((image_name, tags))
+}
+
+/// Returns the total size, in bytes, of the files under a directory. Symbolic links are counted
+/// by their own size rather than followed.
+fn tree_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.path().symlink_metadata()?;
+        size += if metadata.is_dir() {
+            tree_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Renders the `Dockerfile` template with the given build root context.
+fn render_dockerfile(json: &serde_json::Value) -> Result<String> {
+    Ok(Handlebars::new().template_render(DOCKERFILE, json)
+                        .map_err(SyncFailure::new)?)
+}
+
+/// Checks that the Docker program is installed and that the Docker daemon is reachable.
+///
+/// # Errors
+///
+/// * If the Docker program cannot be found
+/// * If the Docker daemon cannot be reached
+pub fn available() -> Result<()> {
+    let path = docker::command_path().map_err(|e| Error::DockerUnavailable(e.to_string()))?;
+    let mut cmd = Command::new(path);
+    cmd.args(env_engine_opts()?);
+    probe(cmd)
+}
+
+/// Returns the global Docker options set in the `HAB_CONTAINER_ENGINE_OPTS` environment
+/// variable, or no options if it is not set.
+///
+/// # Errors
+///
+/// * If the options have unbalanced quotes
+fn env_engine_opts() -> Result<Vec<String>> {
+    match henv::var(ENGINE_OPTS_ENVVAR) {
+        Ok(value) => parse_engine_opts(&value),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Splits options into separate arguments following shell quoting rules, so that a quoted value
+/// containing whitespace remains a single argument.
+///
+/// # Errors
+///
+/// * If the options have unbalanced quotes
+fn parse_engine_opts(value: &str) -> Result<Vec<String>> {
+    shlex::split(value).ok_or_else(|| Error::InvalidEngineOpts(value.to_string()).into())
+}
+
+/// Rotates the reports at `report` so that a new report can be written there with at most `keep`
+/// reports remaining: `report` moves to `report.1`, `report.1` to `report.2`, and so on, and the
+/// oldest report beyond the count is removed.
+fn rotate_reports(report: &Path, keep: usize) -> Result<()> {
+    let rotated = |n: usize| {
+        let mut path = report.as_os_str().to_owned();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    };
+    if keep <= 1 {
+        // The new report simply overwrites the previous one
+        return Ok(());
+    }
+    let oldest = rotated(keep - 1);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..keep - 1).rev() {
+        let previous = rotated(n);
+        if previous.exists() {
+            fs::rename(&previous, rotated(n + 1))?;
+        }
+    }
+    if report.exists() {
+        fs::rename(report, rotated(1))?;
+    }
+    Ok(())
+}
+
+/// Runs `docker info` with the given Docker program, which only succeeds when the daemon is up.
+fn probe(mut cmd: Command) -> Result<()> {
+    cmd.arg("info");
+    debug!("Running: {:?}", &cmd);
+    let output =
+        cmd.output()
+           .map_err(|e| Error::DockerUnavailable(format!("failed to run {:?}: {}", cmd, e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::DockerUnavailable(format!("the Docker daemon could not be reached: {}",
+                                             String::from_utf8_lossy(&output.stderr).trim())).into())
+    }
+}
+
+/// Runs a command to completion with its output captured, writing the output through the UI only
+/// if the command fails.
+fn run_buffered(ui: &mut UI, mut cmd: Command) -> Result<ExitStatus> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            ui.info(line)?;
+        }
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            ui.warn(line)?;
+        }
+    }
+    Ok(output.status)
+}
+
+/// Runs a command to completion, writing each line of its output through the UI as it arrives.
+///
+/// The output is always piped, so the command never sees a terminal and does not emit escape
+/// codes of its own.
+fn run_streamed(ui: &mut UI, mut cmd: Command) -> Result<ExitStatus> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let (tx, rx) = mpsc::channel();
+    forward_lines(child.stdout.take().expect("stdout is piped"), tx.clone());
+    forward_lines(child.stderr.take().expect("stderr is piped"), tx);
+    // The channel closes once both streams have been read to the end
+    for line in rx {
+        ui.info(line)?;
+    }
+    Ok(child.wait()?)
+}
+
+/// Sends each line read from `reader` to `tx` from a new thread.
+fn forward_lines<R>(reader: R, tx: Sender<String>)
+    where R: Read + Send + 'static
+{
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().filter_map(io::Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Returns a `Command` which runs the given command line through the system shell, with `arg`
+/// appended as its final argument.
+#[cfg(unix)]
+fn shell_cmd(command: &str, arg: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+       .arg(format!("{} \"$@\"", command))
+       .arg("sh")
+       .arg(arg);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_cmd(command: &str, arg: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(format!("{} {}", command, arg));
+    cmd
+}
+
+/// Runs a policy command against a `Dockerfile`, which is given to the command on its stdin and
+/// with its path appended as the final argument. The command's output is written to stderr, so
+/// that it is kept apart from any references written to stdout.
+fn check_dockerfile_policy(ui: &mut UI,
+                           dockerfile: &Path,
+                           policy: &DockerfilePolicy)
+                           -> Result<()> {
+    ui.status(Status::Executing,
+              format!("Dockerfile policy '{}'", policy.command))?;
+    let mut cmd = shell_cmd(policy.command, &dockerfile.to_string_lossy());
+    cmd.stdin(fs::File::open(dockerfile)?)
+       .stderr(Stdio::inherit());
+    debug!("Running: {:?}", &cmd);
+    let output = cmd.output()?;
+    io::stderr().write_all(&output.stdout)?;
+    let exit_status = output.status;
+    if !exit_status.success() {
+        if !policy.allow_failure {
+            return Err(Error::DockerfilePolicyFailed(exit_status).into());
+        }
+        ui.warn(format!("Dockerfile policy '{}' failed with exit code: {}, continuing",
+                        policy.command, exit_status))?;
+    }
+    Ok(())
+}
+
+/// Pushes an image reference with the Docker configuration, holding the registry credentials, in
+/// the given directory.
+fn push_image(image_tag: &str, config_dir: &Path, quiet: bool) -> Result<()> {
+    let mut cmd = docker_cmd();
+    cmd.arg("--config");
+    cmd.arg(config_dir.to_str().unwrap());
+    cmd.arg("push").arg(image_tag);
+    debug!("Running: {:?}", &cmd);
+    let stdout = if quiet {
+        Stdio::null()
+    } else {
+        Stdio::inherit()
+    };
+    let exit_status = cmd.stdout(stdout).spawn()?.wait()?;
+    if !exit_status.success() {
+        return Err(Error::PushImageFailed(exit_status).into());
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if the error output of `docker manifest inspect` reports that the manifest
+/// does not exist, rather than that it could not be looked up.
+fn is_manifest_unknown(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    ["no such manifest",
+     "manifest unknown",
+     "not found: manifest"].iter()
+                           .any(|m| stderr.contains(m))
+}
+
+/// Returns a `Command` for the Docker program.
+fn docker_cmd() -> Command {
+    let mut cmd = Command::new(docker::command_path().expect("Unable to locate docker"));
+    // The options were validated when checking that Docker is available
+    cmd.args(env_engine_opts().unwrap_or_default());
+    cmd
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::init::InitSystem;
+    use lazy_static::lazy_static;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    habitat_core::locked_env_var!(HAB_CONTAINER_ENGINE_OPTS, lock_engine_opts_env_var);
+
+    fn image(name: &str, tags: &[&str]) -> DockerImage {
+        DockerImage { id:                "abc123".to_string(),
+                      name:              name.to_string(),
+                      tags:              tags.iter().map(ToString::to_string).collect(),
+                      workdir:           PathBuf::from("/tmp"),
+                      rootfs_size_bytes: None,
+                      packages:          Vec::new(),
+                      exposes:           Vec::new(),
+                      health_check_path: None,
+                      annotations:       Vec::new(),
+                      quiet:             false, }
+    }
+
+    #[test]
+    fn provenance_describes_the_build_and_destination() {
+        let mut image = image("registry.acme.io/acme/redis", &["4.0.14", "latest"]);
+        image.packages = vec!["core/glibc/2.27/20190115002733".to_string(),
+                              "acme/redis/4.0.14/20190319155852".to_string()];
+        let finished_on = "2019-03-19T16:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let provenance = image.provenance("sha256:0123", Some("registry.acme.io"), finished_on);
+
+        assert_eq!(provenance["builder"]["id"], json!(&*PROGRAM_NAME));
+        assert_eq!(provenance["builder"]["version"], json!(VERSION.trim()));
+        assert_eq!(provenance["subject"],
+                   json!({ "name": "registry.acme.io/acme/redis",
+                           "digest": "sha256:0123",
+                           "tags": ["4.0.14", "latest"] }));
+        assert_eq!(provenance["materials"],
+                   json!(["core/glibc/2.27/20190115002733",
+                          "acme/redis/4.0.14/20190319155852"]));
+        assert_eq!(provenance["build_finished_on"],
+                   json!("2019-03-19T16:00:00+00:00"));
+        assert_eq!(provenance["destination"],
+                   json!({ "registry": "registry.acme.io",
+                           "references": ["registry.acme.io/acme/redis:4.0.14",
+                                          "registry.acme.io/acme/redis:latest"] }));
+    }
+
+    #[test]
+    fn k8s_deployment_references_image_and_ports() {
+        let mut image = image("registry.acme.io/acme/redis", &["4.0.14", "latest"]);
+        image.exposes = vec!["6379".to_string(), "16379".to_string()];
+
+        let deployment = image.k8s_deployment().unwrap();
+
+        assert!(deployment.contains("  name: redis\n"), "{}", deployment);
+        assert!(deployment.contains("image: \"registry.acme.io/acme/redis:4.0.14\"\n"),
+                "{}",
+                deployment);
+        assert!(deployment.contains(&["          ports:",
+                                      "            - containerPort: 9631",
+                                      "            - containerPort: 6379",
+                                      "            - containerPort: 16379\n"].join("\n")),
+                "{}",
+                deployment);
+        assert!(!deployment.contains("livenessProbe"), "{}", deployment);
+    }
+
+    #[test]
+    fn k8s_deployment_probes_the_health_check() {
+        let mut image = image("acme/redis", &["latest"]);
+        image.health_check_path = Some("/services/redis/default/health".to_string());
+
+        let deployment = image.k8s_deployment().unwrap();
+
+        assert!(deployment.ends_with(&["          livenessProbe:",
+                                       "            httpGet:",
+                                       "              path: /services/redis/default/health",
+                                       "              port: 9631\n"].join("\n")),
+                "{}",
+                deployment);
+    }
+
+    #[test]
+    fn k8s_deployment_is_annotated() {
+        let mut image = image("acme/redis", &["latest"]);
+        image.annotations =
+            vec!["org.opencontainers.image.source=https://github.com/acme/redis".parse()
+                                                                                .unwrap(),
+                 "sh.habitat.note=say \"hi\"".parse().unwrap()];
+
+        let deployment = image.k8s_deployment().unwrap();
+
+        assert!(deployment.starts_with(&["apiVersion: apps/v1",
+                                         "kind: Deployment",
+                                         "metadata:",
+                                         "  name: redis",
+                                         "  labels:",
+                                         "    app: redis",
+                                         "  annotations:",
+                                         "    org.opencontainers.image.source: \
+                                          \"https://github.com/acme/redis\"",
+                                         "    sh.habitat.note: \"say \\\"hi\\\"\"",
+                                         "spec:\n"].join("\n")),
+                "{}",
+                deployment);
+        assert!(!image("acme/redis", &["latest"]).k8s_deployment()
+                                                 .unwrap()
+                                                 .contains("annotations:"));
+    }
+
+    #[test]
+    fn annotations_are_not_applied_to_the_image_with_a_warning() {
+        let mut ui = UI::with_sinks();
+        ui.record_warnings();
+        warn_annotations_not_applied(&mut ui, &[]).unwrap();
+        assert!(ui.warnings().is_empty());
+
+        let annotation = "org.opencontainers.image.source=https://github.com/acme/redis".parse()
+                                                                                        .unwrap();
+        warn_annotations_not_applied(&mut ui, &[annotation]).unwrap();
+
+        assert_eq!(ui.warnings().len(), 1);
+        assert!(ui.warnings()[0].contains("org.opencontainers.image.source=\
+                                           https://github.com/acme/redis"),
+                "{:?}",
+                ui.warnings());
+    }
+
+    #[test]
+    fn registry_client_cert_is_laid_out_for_the_registry_host() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let cert = fixtures.join("client-cert.pem");
+        let key = fixtures.join("client-key.pem");
+        let workdir = TempDir::new().unwrap();
+        let mut image = image("registry.acme.io:5000/acme/redis", &["latest"]);
+        image.workdir = workdir.path().to_path_buf();
+
+        image.create_registry_client_cert(Some("https://registry.acme.io:5000/v2/"), &cert, &key)
+             .unwrap();
+
+        #[cfg(unix)]
+        let dir = workdir.path().join("certs.d/registry.acme.io:5000");
+        #[cfg(windows)]
+        let dir = workdir.path().join("certs.d/registry.acme.io5000");
+        assert_eq!(fs::read(dir.join("client.cert")).unwrap(),
+                   fs::read(&cert).unwrap());
+        assert_eq!(fs::read(dir.join("client.key")).unwrap(),
+                   fs::read(&key).unwrap());
+    }
+
+    #[test]
+    fn registry_host_is_taken_from_the_registry_url() {
+        assert_eq!(registry_host(Some("registry.acme.io:5000")),
+                   "registry.acme.io:5000");
+        assert_eq!(registry_host(Some("https://registry.acme.io/v2/")),
+                   "registry.acme.io");
+        assert_eq!(registry_host(None), "index.docker.io");
+    }
+
+    #[test]
+    fn tree_size_sums_file_sizes() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir_all(root.path().join("hab/pkgs/acme/redis")).unwrap();
+        fs::create_dir_all(root.path().join("hab/svc")).unwrap();
+        fs::write(root.path().join("hab/pkgs/acme/redis/MANIFEST"),
+                  "0123456789").unwrap();
+        fs::write(root.path().join("hab/pkgs/acme/redis/IDENT"), "acme/redis").unwrap();
+        fs::write(root.path().join("init.sh"), "#!/bin/sh\n").unwrap();
+
+        assert_eq!(tree_size(root.path()).unwrap(), 30);
+    }
+
+    #[test]
+    fn report_includes_rootfs_size() {
+        let dst = TempDir::new().unwrap();
+        let mut image = image("acme/redis", &["latest"]);
+        image.rootfs_size_bytes = Some(1024);
+
+        image.create_report(&mut UI::with_sinks(), dst.path(), 1)
+             .unwrap();
+
+        let report = fs::read_to_string(dst.path().join(EXPORT_REPORT)).unwrap();
+        assert!(report.contains("rootfs_size_bytes=1024\n"), "{}", report);
+    }
+
+    #[test]
+    fn report_is_written_to_provided_writer_instead_of_disk() {
+        let cwd = TempDir::new().unwrap();
+        let matches = crate::cli().get_matches_from(vec!["hab-pkg-export-docker",
+                                                         "--report-stdout",
+                                                         "acme/redis"]);
+        let mut out = Vec::new();
+
+        let results = crate::create_reports(&mut UI::with_sinks(),
+                                            &matches,
+                                            &image("acme/redis", &["latest"]),
+                                            None,
+                                            cwd.path(),
+                                            &mut out).unwrap();
+
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("name=acme/redis\n"), "{}", report);
+        assert_eq!(results, Some(cwd.path().join("results")));
+        assert!(!cwd.path().join("results").exists());
+    }
+
+    #[test]
+    fn export_with_no_results_writes_no_files() {
+        for args in &[vec!["--no-results"],
+                      vec!["--no-results", "--report-stdout"]]
+        {
+            let cwd = TempDir::new().unwrap();
+            let mut argv = vec!["hab-pkg-export-docker"];
+            argv.extend_from_slice(args);
+            argv.push("acme/redis");
+            let matches = crate::cli().get_matches_from(argv);
+            let mut out = Vec::new();
+
+            let results = crate::create_reports(&mut UI::with_sinks(),
+                                                &matches,
+                                                &image("acme/redis", &["latest"]),
+                                                None,
+                                                cwd.path(),
+                                                &mut out).unwrap();
+
+            assert_eq!(results, None, "{:?}", args);
+            assert_eq!(fs::read_dir(cwd.path()).unwrap().count(), 0, "{:?}", args);
+            assert_eq!(!out.is_empty(),
+                       matches.is_present("REPORT_STDOUT"),
+                       "{:?}",
+                       args);
+        }
+    }
+
+    #[test]
+    fn reports_are_rotated_keeping_the_configured_count() {
+        let dst = TempDir::new().unwrap();
+        let report = |name: &str| fs::read_to_string(dst.path().join(name)).ok();
+
+        for name in &["acme/one", "acme/two", "acme/three", "acme/four"] {
+            image(name, &["latest"]).create_report(&mut UI::with_sinks(), dst.path(), 3)
+                                    .unwrap();
+        }
+
+        assert!(report(EXPORT_REPORT).unwrap().contains("name=acme/four\n"));
+        assert!(report("last_docker_export.env.1").unwrap()
+                                                  .contains("name=acme/three\n"));
+        assert!(report("last_docker_export.env.2").unwrap()
+                                                  .contains("name=acme/two\n"));
+        assert_eq!(report("last_docker_export.env.3"), None);
+    }
+
+    #[test]
+    fn report_overwrites_previous_report_by_default() {
+        let dst = TempDir::new().unwrap();
+
+        for name in &["acme/one", "acme/two"] {
+            image(name, &["latest"]).create_report(&mut UI::with_sinks(), dst.path(), 1)
+                                    .unwrap();
+        }
+
+        let report = fs::read_to_string(dst.path().join(EXPORT_REPORT)).unwrap();
+        assert!(report.contains("name=acme/two\n"), "{}", report);
+        assert!(!dst.path().join("last_docker_export.env.1").exists());
+    }
+
+    #[test]
+    fn reference_uses_first_tag() {
+        assert_eq!(image("acme/redis", &["4.0.14", "latest"]).reference(),
+                   "acme/redis:4.0.14");
+        assert_eq!(image("acme/redis", &[]).reference(), "acme/redis");
+    }
+
+    #[test]
+    fn existing_tags_are_rejected() {
+        let image = image("acme/redis", &["4.0.14-20190319155852", "4.0.14", "latest"]);
+
+        let err = image.ensure_tags_are_new(&image.tags, |image_tag| {
+                           Ok(image_tag == "acme/redis:4.0.14")
+                       })
+                       .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::TagAlreadyExists { tag }) => assert_eq!(tag, "acme/redis:4.0.14"),
+            e => panic!("Expected TagAlreadyExists, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn new_tags_proceed() {
+        let image = image("acme/redis", &["4.0.14-20190319155852", "4.0.14"]);
+        let mut queried = Vec::new();
+
+        image.ensure_tags_are_new(&image.tags, |image_tag| {
+                 queried.push(image_tag.to_string());
+                 Ok(false)
+             })
+             .unwrap();
+
+        assert_eq!(queried,
+                   vec!["acme/redis:4.0.14-20190319155852", "acme/redis:4.0.14"]);
+    }
+
+    #[test]
+    fn mutable_tags_are_not_checked() {
+        let image = image("acme/redis", &["latest"]);
+
+        assert!(image.ensure_tags_are_new(&image.tags, |_| Ok(true)).is_ok());
+    }
+
+    /// Returns a stub Docker program which writes `stderr` to its error output and exits with
+    /// `code`.
+    #[cfg(unix)]
+    fn failing_engine(stderr: &'static str, code: i32) -> impl Fn() -> Command {
+        move || {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c")
+               .arg(format!("echo '{}' >&2; exit {}", stderr, code));
+            cmd
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unknown_manifest_is_a_missing_tag() {
+        let image = image("acme/redis", &["4.0.14"]);
+        let engine = failing_engine("no such manifest: docker.io/acme/redis:4.0.14", 1);
+
+        assert!(!image.remote_tag_exists(engine, "acme/redis:4.0.14")
+                      .unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failed_manifest_lookup_is_an_error() {
+        let image = image("acme/redis", &["4.0.14"]);
+        let engine = failing_engine("unauthorized: authentication required", 1);
+
+        let err = image.remote_tag_exists(engine, "acme/redis:4.0.14")
+                       .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::TagLookupFailed { tag, reason }) => {
+                assert_eq!(tag, "acme/redis:4.0.14");
+                assert_eq!(reason, "unauthorized: authentication required");
+            }
+            e => panic!("Expected TagLookupFailed, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn pushed_tags_are_recorded() {
+        let dir = TempDir::new().unwrap();
+        let report = dir.path().join(PUSH_REPORT);
+        let image = image("acme/redis", &["4.0.14", "latest"]);
+        let mut ui = UI::with_sinks();
+
+        let err = image.push_tags(&mut ui, &image.tags, Vec::new(), Some(&report), 1, |tag| {
+                           if tag == "latest" {
+                               Err(failure::err_msg("push failed"))
+                           } else {
+                               Ok(())
+                           }
+                       })
+                       .unwrap_err();
+
+        assert_eq!(err.to_string(), "push failed");
+        assert_eq!(image.read_pushed_tags(&report).unwrap(), vec!["4.0.14"]);
+    }
+
+    #[test]
+    fn resumed_push_only_attempts_remaining_tags() {
+        let dir = TempDir::new().unwrap();
+        let report = dir.path().join(PUSH_REPORT);
+        fs::write(&report,
+                  r#"{"id": "abc123", "name": "acme/redis", "pushed_tags": ["4.0.14", "latest"]}"#)
+            .unwrap();
+        let image = image("acme/redis", &["4.0.14-20190319155852", "4.0.14", "latest"]);
+
+        let pushed = image.read_pushed_tags(&report).unwrap();
+        let pending: Vec<String> = image.tags
+                                        .iter()
+                                        .filter(|t| !pushed.contains(t))
+                                        .cloned()
+                                        .collect();
+        let attempted = Arc::new(Mutex::new(Vec::new()));
+        let attempting = Arc::clone(&attempted);
+        image.push_tags(&mut UI::with_sinks(),
+                        &pending,
+                        pushed,
+                        Some(&report),
+                        1,
+                        move |tag| {
+                            attempting.lock().unwrap().push(tag.to_string());
+                            Ok(())
+                        })
+             .unwrap();
+
+        assert_eq!(*attempted.lock().unwrap(),
+                   vec!["4.0.14-20190319155852", "latest"]);
+        assert_eq!(image.read_pushed_tags(&report).unwrap(),
+                   vec!["4.0.14", "4.0.14-20190319155852"]);
+    }
+
+    #[test]
+    fn push_without_report_pushes_every_tag() {
+        let image = image("acme/redis", &["4.0.14", "latest"]);
+        let attempted = Arc::new(Mutex::new(Vec::new()));
+        let attempting = Arc::clone(&attempted);
+
+        image.push_tags(&mut UI::with_sinks(),
+                        &image.tags,
+                        Vec::new(),
+                        None,
+                        1,
+                        move |tag| {
+                            attempting.lock().unwrap().push(tag.to_string());
+                            Ok(())
+                        })
+             .unwrap();
+
+        assert_eq!(*attempted.lock().unwrap(), vec!["4.0.14", "latest"]);
+    }
+
+    #[test]
+    fn no_more_pushes_than_the_concurrency_run_at_once() {
+        let tags: Vec<String> = (1..=8).map(|n| format!("4.0.{}", n)).collect();
+        let image = image("acme/redis", &[]);
+        // The number of pushes running, the most which ran at once, and the number completed
+        let counts = Arc::new(Mutex::new((0, 0, 0)));
+        let counting = Arc::clone(&counts);
+
+        image.push_tags(&mut UI::with_sinks(),
+                        &tags,
+                        Vec::new(),
+                        None,
+                        3,
+                        move |_| {
+                            {
+                                let mut counts = counting.lock().unwrap();
+                                counts.0 += 1;
+                                counts.1 = counts.1.max(counts.0);
+                            }
+                            thread::sleep(Duration::from_millis(20));
+                            let mut counts = counting.lock().unwrap();
+                            counts.0 -= 1;
+                            counts.2 += 1;
+                            Ok(())
+                        })
+             .unwrap();
+
+        let (running, most, completed) = *counts.lock().unwrap();
+        assert_eq!(0, running);
+        assert!(most > 1 && most <= 3, "{} pushes ran at once", most);
+        assert_eq!(8, completed);
+    }
+
+    #[test]
+    fn concurrent_push_records_tags_which_completed_before_a_failure() {
+        let dir = TempDir::new().unwrap();
+        let report = dir.path().join(PUSH_REPORT);
+        let image = image("acme/redis", &["4.0.14-20190319155852", "4.0.14", "latest"]);
+
+        let err = image.push_tags(&mut UI::with_sinks(),
+                                  &image.tags,
+                                  Vec::new(),
+                                  Some(&report),
+                                  3,
+                                  |tag| {
+                                      if tag == "4.0.14" {
+                                          Err(failure::err_msg("push failed"))
+                                      } else {
+                                          thread::sleep(Duration::from_millis(20));
+                                          Ok(())
+                                      }
+                                  })
+                       .unwrap_err();
+
+        assert_eq!(err.to_string(), "push failed");
+        assert_eq!(image.read_pushed_tags(&report).unwrap(),
+                   vec!["4.0.14-20190319155852"]);
+    }
+
+    #[test]
+    fn push_report_for_another_image_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let report = dir.path().join(PUSH_REPORT);
+        fs::write(&report,
+                  r#"{"id": "def456", "name": "acme/nginx", "pushed_tags": ["1.17.9"]}"#).unwrap();
+
+        assert!(image("acme/redis", &["1.17.9"]).read_pushed_tags(&report)
+                                                .unwrap()
+                                                .is_empty());
+    }
+
+    #[test]
+    fn push_report_for_an_earlier_build_of_the_image_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let report = dir.path().join(PUSH_REPORT);
+        fs::write(&report,
+                  r#"{"id": "def456", "name": "acme/redis", "pushed_tags": ["4.0.14"]}"#).unwrap();
+
+        assert!(image("acme/redis", &["4.0.14"]).read_pushed_tags(&report)
+                                                .unwrap()
+                                                .is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn passing_scan_proceeds() {
+        let mut ui = UI::with_sinks();
+
+        assert!(image("acme/redis", &["latest"]).scan(&mut ui, "true", false)
+                                                .is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn scan_is_given_image_reference() {
+        let mut ui = UI::with_sinks();
+        let image = image("acme/redis", &["4.0.14", "latest"]);
+
+        assert!(image.scan(&mut ui, "test acme/redis:4.0.14 =", false)
+                     .is_ok());
+        assert!(image.scan(&mut ui,
+                           "test \"$HAB_DOCKER_IMAGE_ID\" = abc123 && true",
+                           false)
+                     .is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_scan_aborts_export() {
+        let mut ui = UI::with_sinks();
+        let err = image("acme/redis", &["latest"]).scan(&mut ui, "false", false)
+                                                  .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::ScanFailed(_)) => {}
+            e => panic!("Expected ScanFailed, got {:?}", e),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_scan_proceeds_when_failure_is_allowed() {
+        let mut ui = UI::with_sinks();
+
+        assert!(image("acme/redis", &["latest"]).scan(&mut ui, "false", true)
+                                                .is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn sign_is_given_digest_reference() {
+        let mut ui = UI::with_sinks();
+        let image = image("acme/redis", &["4.0.14"]);
+        let reference = "acme/redis@sha256:0123456789abcdef";
+
+        assert!(image.sign_reference(&mut ui,
+                                     "test acme/redis@sha256:0123456789abcdef =",
+                                     reference,
+                                     false)
+                     .is_ok());
+        assert!(image.sign_reference(&mut ui,
+                                     "test \"$HAB_DOCKER_IMAGE\" = \
+                                      acme/redis@sha256:0123456789abcdef && true",
+                                     reference,
+                                     false)
+                     .is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_sign_aborts_export() {
+        let mut ui = UI::with_sinks();
+        let err = image("acme/redis", &["latest"]).sign_reference(&mut ui,
+                                                                  "false",
+                                                                  "acme/redis@sha256:abc",
+                                                                  false)
+                                                  .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::SignFailed(_)) => {}
+            e => panic!("Expected SignFailed, got {:?}", e),
+        }
+        assert!(image("acme/redis", &["latest"]).sign_reference(&mut ui,
+                                                                "false",
+                                                                "acme/redis@sha256:abc",
+                                                                true)
+                                                .is_ok());
+    }
+
+    #[test]
+    fn images_within_the_size_limit_are_accepted() {
+        let limit = "500m".parse::<ByteSize>().unwrap();
+
+        assert!(check_image_size(100 * 1024 * 1024, limit).is_ok());
+        assert!(check_image_size(limit.bytes(), limit).is_ok());
+    }
+
+    #[test]
+    fn images_over_the_size_limit_are_rejected() {
+        let limit = "500m".parse::<ByteSize>().unwrap();
+
+        let err = check_image_size(limit.bytes() + 1, limit).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::ImageTooLarge { actual, limit }) => {
+                assert_eq!(*actual, 524_288_001);
+                assert_eq!(*limit, 524_288_000);
+            }
+            e => panic!("Expected Error::ImageTooLarge, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn digest_short_tag_uses_first_twelve_hex_characters() {
+        let digest = "sha256:0123456789ABCDEF0123456789abcdef0123456789abcdef0123456789abcdef";
+
+        assert_eq!(digest_short_tag(digest),
+                   Some("sha-0123456789ab".to_string()));
+        assert_eq!(digest_short_tag("0123456789abcdef"),
+                   Some("sha-0123456789ab".to_string()));
+    }
+
+    #[test]
+    fn digest_short_tag_rejects_invalid_digests() {
+        assert_eq!(digest_short_tag("sha256:0123456789a"), None);
+        assert_eq!(digest_short_tag("sha256:0123456789ag0123"), None);
+        assert_eq!(digest_short_tag(""), None);
+    }
+
+    #[test]
+    fn digest_file_holds_the_digest_of_a_pinned_reference() {
+        let dir = TempDir::new().unwrap();
+        let dst = dir.path().join("digest.txt");
+        let digest = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let reference = format!("registry.acme.io:5000/acme/redis@{}", digest);
+
+        write_digest_file(&mut UI::with_sinks(),
+                          &dst,
+                          reference_digest(&reference).unwrap()).unwrap();
+
+        let contents = fs::read_to_string(&dst).unwrap();
+        assert_eq!(contents, format!("{}\n", digest));
+        assert!(is_valid_digest(contents.trim_end()));
+    }
+
+    /// A stub Docker program which inspects image `abc123` as having the given content digest
+    /// and registry references, one per line.
+    #[cfg(unix)]
+    fn inspect_engine(id: &'static str, repo_digests: &'static str) -> impl Fn() -> Command {
+        move || {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c")
+               .arg(format!("case \"$*\" in *RepoDigests*) printf '{}' ;; *) echo {} ;; esac",
+                            repo_digests, id))
+               .arg("docker");
+            cmd
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn digest_file_holds_the_image_or_pushed_digest() {
+        let dir = TempDir::new().unwrap();
+        let dst = dir.path().join("digest.txt");
+        let id = "sha256:1111111111111111111111111111111111111111111111111111111111111111";
+        let pushed = "sha256:2222222222222222222222222222222222222222222222222222222222222222";
+        let repo_digests = "acme/redis-base@sha256:\
+                            3333333333333333333333333333333333333333333333333333333333333333\\
+                            \
+                            nacme/redis@sha256:\
+                            2222222222222222222222222222222222222222222222222222222222222222\n";
+        let image = image("acme/redis", &["4.0.14"]);
+        let mut ui = UI::with_sinks();
+
+        image.create_digest_file_with(&mut ui, inspect_engine(id, repo_digests), &dst, false)
+             .unwrap();
+        assert_eq!(fs::read_to_string(&dst).unwrap(), format!("{}\n", id));
+
+        image.create_digest_file_with(&mut ui, inspect_engine(id, repo_digests), &dst, true)
+             .unwrap();
+        assert_eq!(fs::read_to_string(&dst).unwrap(), format!("{}\n", pushed));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn digest_file_of_an_image_without_a_pushed_digest_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let dst = dir.path().join("digest.txt");
+        let id = "sha256:1111111111111111111111111111111111111111111111111111111111111111";
+        let image = image("acme/redis", &["4.0.14"]);
+
+        let err = image.create_digest_file_with(&mut UI::with_sinks(),
+                                                inspect_engine(id, "acme/redis@sha256:0123\n"),
+                                                &dst,
+                                                true)
+                       .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::PushedDigestNotFound(name)) => assert_eq!(name, "acme/redis"),
+            e => panic!("Expected PushedDigestNotFound, got {:?}", e),
+        }
+        assert!(!dst.exists());
+    }
+
+    #[test]
+    fn references_without_a_valid_digest_are_rejected() {
+        for reference in
+            &["acme/redis:4.0.14",
+              "acme/redis@sha256:0123",
+              "acme/redis@sha512:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+              "acme/redis@sha256:0123456789ABCDEF0123456789abcdef0123456789abcdef0123456789abcdef"]
+        {
+            assert_eq!(reference_digest(reference), None, "{}", reference);
+        }
+    }
+
+    #[test]
+    fn valid_repository_names_are_accepted() {
+        for name in &["redis",
+                      "acme/redis",
+                      "registry/acme-redis",
+                      "acme/redis_server.v2",
+                      "acme/redis__server",
+                      "acme/redis---server",
+                      "localhost/acme/redis",
+                      "registry.example.com:5000/acme/redis"]
+        {
+            assert!(is_valid_repository(name), "'{}' should be valid", name);
+        }
+    }
+
+    #[test]
+    fn invalid_repository_names_are_rejected() {
+        for name in &["",
+                      "Acme/redis",
+                      "acme//redis",
+                      "acme/redis/",
+                      "-acme/redis",
+                      "acme/redis.",
+                      "acme/redis..server",
+                      "acme/redis___server",
+                      "acme/redis:4.0.14",
+                      "registry.example.com:5000"]
+        {
+            assert!(!is_valid_repository(name), "'{}' should be invalid", name);
+        }
+    }
+
+    #[test]
+    fn parsed_tags_without_registry() {
+        assert_eq!(image("acme/redis", &["4.0.14", "latest"]).parsed_tags(),
+                   vec![ParsedTag { registry:   None,
+                                    repository: "acme/redis".to_string(),
+                                    tag:        Some("4.0.14".to_string()),
+                                    digest:     None, },
+                        ParsedTag { registry:   None,
+                                    repository: "acme/redis".to_string(),
+                                    tag:        Some("latest".to_string()),
+                                    digest:     None, },]);
+    }
+
+    #[test]
+    fn parsed_tags_without_tags() {
+        assert_eq!(image("acme/redis", &[]).parsed_tags(),
+                   vec![ParsedTag { registry:   None,
+                                    repository: "acme/redis".to_string(),
+                                    tag:        None,
+                                    digest:     None, }]);
+    }
+
+    #[test]
+    fn parsed_tags_with_registry() {
+        assert_eq!(image("registry.example.com:5000/acme/redis", &["latest"]).parsed_tags(),
+                   vec![ParsedTag { registry:   Some("registry.example.com:5000".to_string()),
+                                    repository: "acme/redis".to_string(),
+                                    tag:        Some("latest".to_string()),
+                                    digest:     None, }]);
+        assert_eq!(ParsedTag::parse("localhost/redis:1.0").registry,
+                   Some("localhost".to_string()));
+    }
+
+    #[test]
+    fn parsed_tag_with_digest() {
+        let digest = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+        assert_eq!(ParsedTag::parse(&format!("quay.io/acme/redis:4.0.14@{}", digest)),
+                   ParsedTag { registry:   Some("quay.io".to_string()),
+                               repository: "acme/redis".to_string(),
+                               tag:        Some("4.0.14".to_string()),
+                               digest:     Some(digest.to_string()), });
+        assert_eq!(ParsedTag::parse(&format!("acme/redis@{}", digest)),
+                   ParsedTag { registry:   None,
+                               repository: "acme/redis".to_string(),
+                               tag:        None,
+                               digest:     Some(digest.to_string()), });
+    }
+
+    #[cfg(unix)]
+    fn dockerfile_policy_check(command: &str, allow_failure: bool) -> Result<()> {
+        let dir = TempDir::new().unwrap();
+        let dockerfile = dir.path().join("Dockerfile");
+        fs::write(&dockerfile, "FROM scratch\nADD rootfs /\n").unwrap();
+        let policy = DockerfilePolicy { command,
+                                        allow_failure };
+
+        check_dockerfile_policy(&mut UI::with_sinks(), &dockerfile, &policy)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_dockerfile_policy_aborts_the_build() {
+        let err = dockerfile_policy_check("! grep -q '^ADD'", false).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::DockerfilePolicyFailed(status)) => assert_eq!(Some(1), status.code()),
+            e => panic!("Expected DockerfilePolicyFailed, got {:?}", e),
+        }
+        assert!(dockerfile_policy_check("! grep -q '^ADD'", true).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_policy_reads_the_dockerfile_from_stdin() {
+        // `cmp -` compares stdin with the Dockerfile, whose path is the final argument
+        assert!(dockerfile_policy_check("grep -q '^FROM scratch' && cmp -", false).is_ok());
+    }
+
+    #[cfg(unix)]
+    fn dockerfile_json() -> serde_json::Value {
+        json!({
+            "base_image": "scratch",
+            "base_image_args": [],
+            "rootfs": "rootfs",
+            "path": "/hab/pkgs/acme/redis/4.0.14/20190319155852/bin:/bin",
+            "hab_path": "/hab/pkgs/core/hab/0.79.1/20190410220617/bin/hab",
+            "exposes": "6379",
+            "multi_layer": false,
+            "workdir": "/hab/svc/redis",
+            "stop_signal": null,
+            "entrypoint": null,
+            "volumes": [],
+            "required_capabilities": "",
+            "env_vars": [],
+            "no_shell": false,
+            "secret_mounts": "",
+            "userns_remap": null,
+            "userns_owner": null,
+            "config_rendered": false,
+            "bundled": false,
+            "init": [],
+            "target": "x86_64-linux",
+            "primary_svc_ident": "acme/redis",
+            "installed_primary_svc_ident": "acme/redis/4.0.14/20190319155852",
+            "environment": { "HAB_LICENSE": "accept-no-persist" },
+            "packages": ["acme/redis/4.0.14/20190319155852"],
+        })
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_matches_golden_file() {
+        let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
+                                                              .join("fixtures")
+                                                              .join("Dockerfile.golden");
+
+        assert_eq!(render_dockerfile(&dockerfile_json()).unwrap(),
+                   fs::read_to_string(golden).unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_includes_stop_signal_when_provided() {
+        let mut json = dockerfile_json();
+        json["stop_signal"] = json!("SIGQUIT");
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("WORKDIR /hab/svc/redis\nSTOPSIGNAL SIGQUIT\nENTRYPOINT"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_uses_binary_as_entrypoint_when_provided() {
+        let mut json = dockerfile_json();
+        json["entrypoint"] = json!("/hab/pkgs/acme/redis/4.0.14/20190319155852/bin/redis-cli");
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.ends_with("WORKDIR /hab/svc/redis\nENTRYPOINT \
+                                      [\"/hab/pkgs/acme/redis/4.0.14/20190319155852/bin/\
+                                      redis-cli\"]\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_runs_supervisor_directly_without_shell() {
+        let mut json = dockerfile_json();
+        json["no_shell"] = json!(true);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(!dockerfile.contains("\nRUN \\"), "{}", dockerfile);
+        assert!(!dockerfile.contains("init.sh"), "{}", dockerfile);
+        assert!(dockerfile.contains("COPY rootfs/root /root\nCOPY rootfs/tmp /tmp\nCOPY \
+                                     rootfs/var/tmp /var/tmp\n\n# Ensure"),
+                "{}",
+                dockerfile);
+        assert!(dockerfile.contains("EXPOSE 9631 6379\nARG HAB_FEAT_OFFLINE_INSTALL=ON\nARG \
+                                     HAB_LICENSE=accept-no-persist\nRUN \
+                                     [\"/hab/pkgs/core/hab/0.79.1/20190410220617/bin/hab\", \
+                                     \"pkg\", \"install\", \
+                                     \"acme/redis/4.0.14/20190319155852\"]\nLABEL"),
+                "{}",
+                dockerfile);
+        assert!(dockerfile.ends_with("ENTRYPOINT \
+                                      [\"/hab/pkgs/core/hab/0.79.1/20190410220617/bin/hab\", \
+                                      \"sup\"]\nCMD [\"run\", \"acme/redis\"]\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_declares_build_args_referenced_by_base_image() {
+        let mut json = dockerfile_json();
+        json["base_image"] = json!("$REGISTRY/acme/base:${BASE_TAG}");
+        json["base_image_args"] = json!(["REGISTRY", "BASE_TAG"]);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.starts_with("ARG REGISTRY\nARG BASE_TAG\nFROM \
+                                        $REGISTRY/acme/base:${BASE_TAG}\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_entrypoint_runs_under_the_init_system() {
+        let mut json = dockerfile_json();
+        json["init"] = json!(InitSystem::Tini.entrypoint());
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.ends_with("ENTRYPOINT [\"/bin/tini\", \"--\", \"/init.sh\"]\nCMD \
+                                      [\"run\", \"acme/redis\"]\n"),
+                "{}",
+                dockerfile);
+
+        json["init"] = json!(InitSystem::DumbInit.entrypoint());
+        json["no_shell"] = json!(true);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.ends_with("ENTRYPOINT [\"/bin/dumb-init\", \"--\", \
+                                      \"/hab/pkgs/core/hab/0.79.1/20190410220617/bin/hab\", \
+                                      \"sup\"]\nCMD [\"run\", \"acme/redis\"]\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_runs_supervisor_without_a_service_when_bundled() {
+        let mut json = dockerfile_json();
+        json["bundled"] = json!(true);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.ends_with("ENTRYPOINT [\"/init.sh\"]\nCMD [\"run\"]\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_copies_bundle_specs_in_multi_layer_mode() {
+        let mut json = dockerfile_json();
+        json["multi_layer"] = json!(true);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(!dockerfile.contains("/hab/sup"), "{}", dockerfile);
+
+        json["bundled"] = json!(true);
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("/hab/pkgs/acme/redis/4.0.14/20190319155852\n\nCOPY \
+                                     rootfs/hab/sup /hab/sup\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_copies_rendered_config_in_multi_layer_mode() {
+        let mut json = dockerfile_json();
+        json["multi_layer"] = json!(true);
+        json["config_rendered"] = json!(true);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("/hab/pkgs/acme/redis/4.0.14/20190319155852\n\nCOPY \
+                                     rootfs/hab/svc /hab/svc\n"),
+                "{}",
+                dockerfile);
+        assert!(dockerfile.contains("\nLABEL sh.habitat.config-rendered=\"true\"\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_labels_required_capabilities_when_provided() {
+        let mut json = dockerfile_json();
+        json["required_capabilities"] = json!("NET_ADMIN,NET_RAW");
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("\nLABEL sh.habitat.required-capabilities=\"NET_ADMIN,\
+                                     NET_RAW\"\nLABEL sh.habitat.target=\"x86_64-linux\"\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_labels_secret_mounts_when_provided() {
+        let mut json = dockerfile_json();
+        json["secret_mounts"] = json!("/run/secrets/db-password,/run/secrets/api-key");
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("\nLABEL sh.habitat.secret-mounts=\"/run/secrets/\
+                                     db-password,/run/secrets/api-key\"\nLABEL \
+                                     sh.habitat.target=\"x86_64-linux\"\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_labels_and_chowns_userns_remap_when_provided() {
+        let mut json = dockerfile_json();
+        json["userns_remap"] = json!("100000:65536");
+        json["userns_owner"] = json!("100000:100000");
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+
+        assert!(dockerfile.contains("\nLABEL sh.habitat.userns-remap=\"100000:65536\"\n"),
+                "{}",
+                dockerfile);
+
+        // Docker gives every copied file the owner given to `--chown`, whatever its owner in the
+        // root file system, so each copy must carry it
+        let assert_copies_chowned = |dockerfile: &str| {
+            let copies = dockerfile.lines()
+                                   .filter(|l| l.starts_with("COPY ") || l.starts_with("ADD "))
+                                   .collect::<Vec<_>>();
+            assert!(!copies.is_empty(), "{}", dockerfile);
+            for copy in copies {
+                assert!(copy.contains(" --chown=100000:100000 rootfs/"),
+                        "{}\n\n{}",
+                        copy,
+                        dockerfile);
+            }
+        };
+        assert_copies_chowned(&dockerfile);
+
+        json["no_shell"] = json!(true);
+        assert_copies_chowned(&render_dockerfile(&json).unwrap());
+
+        json["multi_layer"] = json!(true);
+        json["bundled"] = json!(true);
+        json["config_rendered"] = json!(true);
+        let dockerfile = render_dockerfile(&json).unwrap();
+        assert_copies_chowned(&dockerfile);
+        assert!(dockerfile.contains("\nCOPY --chown=100000:100000 \
+                                     rootfs/hab/pkgs/acme/redis/4.0.14/20190319155852 \
+                                     /hab/pkgs/acme/redis/4.0.14/20190319155852\n"),
+                "{}",
+                dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_declares_volumes_when_provided() {
+        let mut json = dockerfile_json();
+        json["volumes"] = json!(["/hab/svc/redis/data", "/var/log"]);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+        let volumes = ["VOLUME [\"/hab/svc/redis/data\"]",
+                       "VOLUME [\"/var/log\"]",
+                       "LABEL sh.habitat.target=\"x86_64-linux\""].join("\n");
+
+        assert!(dockerfile.contains(&volumes), "{}", dockerfile);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dockerfile_sets_env_vars_when_provided() {
+        let mut json = dockerfile_json();
+        json["env_vars"] = json!([{ "name": "DB_HOST", "value": "\"db.example.com\"" },
+                                  { "name": "GREETING", "value": "\"say \\\"hi\\\"\"" }]);
+
+        let dockerfile = render_dockerfile(&json).unwrap();
+        let env = ["ENV DB_HOST=\"db.example.com\"",
+                   "ENV GREETING=\"say \\\"hi\\\"\"",
+                   "LABEL sh.habitat.target=\"x86_64-linux\""].join("\n");
+
+        assert!(dockerfile.contains(&env), "{}", dockerfile);
+    }
+
+    /// A stub Docker program which keeps the tags of image `abc123` in a `tags` file next to it,
+    /// removing the file along with the image, and logs the commands it is run with. An image
+    /// which loses its last tag is kept if a `keep-untagged` file exists.
+    #[cfg(unix)]
+    const STUB_ENGINE: &str = r#"
+dir=$(dirname "$0")
+echo "$*" >> "$dir/log"
+case "$1" in
+rmi)
+    if [ "$2" = abc123 ]; then
+        [ -s "$dir/tags" ] && exit 1
+        rm "$dir/tags"
+    else
+        grep -qxF "$2" "$dir/tags" || exit 1
+        grep -vxF "$2" "$dir/tags" > "$dir/tags.new"
+        mv "$dir/tags.new" "$dir/tags"
+        [ -s "$dir/tags" ] || [ -f "$dir/keep-untagged" ] || rm "$dir/tags"
+    fi
+    ;;
+image)
+    [ -f "$dir/tags" ] && cat "$dir/tags"
+    ;;
+esac
+"#;
+
+    /// Creates a stub Docker program for image `abc123` with the given tags.
+    #[cfg(unix)]
+    fn stub_engine(dir: &Path, tags: &[&str]) -> impl Fn() -> Command {
+        let tags: String = tags.iter().map(|t| format!("{}\n", t)).collect();
+        fs::write(dir.join("tags"), tags).unwrap();
+        let program = dir.join("docker");
+        fs::write(&program, STUB_ENGINE).unwrap();
+        move || {
+            let mut cmd = Command::new("sh");
+            cmd.arg(&program);
+            cmd
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn rm_preserves_tags_the_export_did_not_create() {
+        let dir = TempDir::new().unwrap();
+        let engine = stub_engine(dir.path(),
+                                 &["acme/app:stable", "acme/app:1.0.0", "acme/app:latest"]);
+
+        image("acme/app", &["1.0.0", "latest"]).rm_with(&mut UI::with_sinks(), engine)
+                                               .unwrap();
+
+        assert_eq!(fs::read_to_string(dir.path().join("tags")).unwrap(),
+                   "acme/app:stable\n");
+        let log = fs::read_to_string(dir.path().join("log")).unwrap();
+        assert!(!log.contains("rmi abc123"), "{}", log);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn rm_removes_the_image_once_no_tags_remain() {
+        let dir = TempDir::new().unwrap();
+        let engine = stub_engine(dir.path(), &["acme/app:1.0.0", "acme/app:latest"]);
+        fs::write(dir.path().join("keep-untagged"), "").unwrap();
+
+        image("acme/app", &["1.0.0", "latest"]).rm_with(&mut UI::with_sinks(), engine)
+                                               .unwrap();
+
+        assert!(!dir.path().join("tags").exists());
+        let log = fs::read_to_string(dir.path().join("log")).unwrap();
+        assert!(log.ends_with("rmi abc123\n"), "{}", log);
+    }
+
+    /// A stub Docker program which starts container `c0ffee`, reports the contents of a `state`
+    /// file next to it when the container is inspected, and logs the commands it is run with.
+    #[cfg(unix)]
+    const SMOKE_TEST_ENGINE: &str = r#"
+dir=$(dirname "$0")
+echo "$*" >> "$dir/log"
+case "$1" in
+run)
+    echo c0ffee
+    ;;
+inspect)
+    cat "$dir/state"
+    ;;
+esac
+"#;
+
+    /// Creates a stub Docker program whose container inspects as the given state.
+    #[cfg(unix)]
+    fn smoke_test_engine(dir: &Path, state: &str) -> impl Fn() -> Command {
+        fs::write(dir.join("state"), state).unwrap();
+        let program = dir.join("docker");
+        fs::write(&program, SMOKE_TEST_ENGINE).unwrap();
+        move || {
+            let mut cmd = Command::new("sh");
+            cmd.arg(&program);
+            cmd
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn smoke_test_fails_when_the_container_crashes() {
+        let dir = TempDir::new().unwrap();
+        let engine = smoke_test_engine(dir.path(), "exited 1 \n");
+
+        let err = image("acme/app", &["1.0.0"]).smoke_test_with(&mut UI::with_sinks(),
+                                                                engine,
+                                                                &[],
+                                                                Duration::from_secs(10))
+                                               .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::SmokeTestFailed(reason)) => assert!(reason.contains("exit code: 1")),
+            e => panic!("Expected SmokeTestFailed, got {:?}", e),
+        }
+        let log = fs::read_to_string(dir.path().join("log")).unwrap();
+        assert!(log.ends_with("rm --force c0ffee\n"), "{}", log);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn smoke_test_passes_when_the_container_is_healthy() {
+        let dir = TempDir::new().unwrap();
+        let engine = smoke_test_engine(dir.path(), "running 0 healthy\n");
+        let run_args = vec!["--env".to_string(), "HAB_LICENSE=accept".to_string()];
+
+        image("acme/app", &["1.0.0"]).smoke_test_with(&mut UI::with_sinks(),
+                                                      engine,
+                                                      &run_args,
+                                                      Duration::from_secs(10))
+                                     .unwrap();
+
+        let log = fs::read_to_string(dir.path().join("log")).unwrap();
+        assert!(log.starts_with("run --detach --env HAB_LICENSE=accept acme/app:1.0.0\n"),
+                "{}",
+                log);
+        assert!(log.ends_with("rm --force c0ffee\n"), "{}", log);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn smoke_test_passes_when_the_container_stays_up() {
+        let dir = TempDir::new().unwrap();
+        let engine = smoke_test_engine(dir.path(), "running 0 \n");
+
+        image("acme/app", &["1.0.0"]).smoke_test_with(&mut UI::with_sinks(),
+                                                      engine,
+                                                      &[],
+                                                      Duration::from_millis(100))
+                                     .unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn probe_succeeds_when_docker_info_succeeds() {
+        assert!(probe(Command::new("true")).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn probe_fails_when_docker_info_fails() {
+        let err = probe(Command::new("false")).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::DockerUnavailable(_)) => {}
+            e => panic!("Expected DockerUnavailable, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn probe_fails_when_docker_is_missing() {
+        let err = probe(Command::new("/no/such/path/to/docker")).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::DockerUnavailable(_)) => {}
+            e => panic!("Expected DockerUnavailable, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn build_output_is_buffered_by_default() {
+        let builder = DockerBuilder::new(Path::new("/tmp"), "acme/app");
+
+        assert_eq!(builder.output, BuildOutput::Buffered);
+    }
+
+    #[test]
+    fn build_output_is_streamed_when_verbose() {
+        let matches =
+            crate::cli().get_matches_from(vec!["hab-pkg-export-docker", "--verbose", "acme/app"]);
+        let options = BuildOptions::new_from_cli_matches(&matches).unwrap();
+        assert!(options.verbose);
+
+        let builder = DockerBuilder::new(Path::new("/tmp"), "acme/app").verbose();
+
+        assert_eq!(builder.output, BuildOutput::Streamed);
+    }
+
+    #[test]
+    fn docker_output_is_hidden_when_reporting_to_stdout() {
+        for flag in &["--quiet", "--report-stdout"] {
+            let matches =
+                crate::cli().get_matches_from(vec!["hab-pkg-export-docker", flag, "acme/app"]);
+            assert!(BuildOptions::new_from_cli_matches(&matches).unwrap().quiet,
+                    "{}",
+                    flag);
+        }
+
+        let matches = crate::cli().get_matches_from(vec!["hab-pkg-export-docker", "acme/app"]);
+        assert!(!BuildOptions::new_from_cli_matches(&matches).unwrap().quiet);
+    }
+
+    #[test]
+    fn quoted_engine_opts_are_single_arguments() {
+        let opts = parse_engine_opts("--host tcp://10.0.0.5:2376 --log-opt \"tag=habitat \
+                                      export\" --config '/etc/docker config'").unwrap();
+
+        assert_eq!(opts,
+                   vec!["--host",
+                        "tcp://10.0.0.5:2376",
+                        "--log-opt",
+                        "tag=habitat export",
+                        "--config",
+                        "/etc/docker config"]);
+    }
+
+    #[test]
+    fn engine_opts_with_unbalanced_quotes_are_rejected() {
+        let err = parse_engine_opts("--log-opt \"tag=habitat").unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::InvalidEngineOpts(_)) => {}
+            e => panic!("Expected InvalidEngineOpts, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn engine_opts_are_read_from_env() {
+        let env_var = lock_engine_opts_env_var();
+
+        env_var.unset();
+        assert!(env_engine_opts().unwrap().is_empty());
+
+        env_var.set("--host 'tcp://10.0.0.5:2376'");
+        assert_eq!(env_engine_opts().unwrap(),
+                   vec!["--host", "tcp://10.0.0.5:2376"]);
+    }
+
+    #[test]
+    fn export_summary_describes_the_image_and_export() {
+        let mut image = image("acme/redis", &["4.0.14", "latest"]);
+        image.rootfs_size_bytes = Some(2048);
+        image.packages = vec!["core/glibc/2.27/20190115002733".to_string(),
+                              "acme/redis/4.0.14/20190319155852".to_string()];
+        let mut timings = Timings::start();
+        timings.finish("package installation");
+        timings.finish("image build");
+
+        let summary = ExportSummary::new(image, "sha256:0123".to_string(), 4096, &timings);
+
+        assert_eq!(summary.image().name(), "acme/redis");
+        assert_eq!(summary.tags(), ["4.0.14", "latest"]);
+        assert_eq!(summary.digest(), "sha256:0123");
+        assert_eq!(summary.packages(),
+                   ["core/glibc/2.27/20190115002733",
+                    "acme/redis/4.0.14/20190319155852"]);
+        assert_eq!(summary.rootfs_size_bytes(), Some(2048));
+        assert_eq!(summary.image_size_bytes(), 4096);
+        let phases: Vec<_> = summary.phases().iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(phases, ["package installation", "image build"]);
+        assert_eq!(summary.duration(), timings.total());
+    }
+
+    #[test]
+    fn build_args_include_tags_and_memory() {
+        let builder = DockerBuilder::new(Path::new("/tmp"), "acme/app").tag("1.0.0")
+                                                                       .tag("latest")
+                                                                       .memory("2gb");
+
+        assert_eq!(builder.build_args(),
+                   vec!["build",
+                        "--force-rm",
+                        "--memory",
+                        "2gb",
+                        "--tag",
+                        "acme/app:1.0.0",
+                        "--tag",
+                        "acme/app:latest",
+                        "."]);
+    }
+
+    #[test]
+    fn build_args_include_memory_swap_from_cli() {
+        for swap in &["4gb", "-1"] {
+            let matches = crate::cli().get_matches_from(vec!["hab-pkg-export-docker",
+                                                             "--memory",
+                                                             "2gb",
+                                                             "--memory-swap",
+                                                             *swap,
+                                                             "acme/app"]);
+            let options = BuildOptions::new_from_cli_matches(&matches).unwrap();
+            let builder =
+                DockerBuilder::new(Path::new("/tmp"), "acme/app").memory(options.memory.unwrap())
+                                                                 .memory_swap(options.memory_swap
+                                                                                     .unwrap());
+
+            assert_eq!(builder.build_args(),
+                       vec!["build",
+                            "--force-rm",
+                            "--memory",
+                            "2gb",
+                            "--memory-swap",
+                            *swap,
+                            "--tag",
+                            "acme/app",
+                            "."]);
+        }
+    }
+
+    #[test]
+    fn invalid_memory_swap_is_rejected_by_cli() {
+        for args in &[vec!["--memory", "2gb", "--memory-swap", "lots"],
+                      vec!["--memory-swap", "4gb"]]
+        {
+            let mut argv = vec!["hab-pkg-export-docker"];
+            argv.extend(args);
+            argv.push("acme/app");
+
+            assert!(crate::cli().get_matches_from_safe(argv).is_err(),
+                    "Expected an error for {:?}",
+                    args);
+        }
+    }
+
+    #[test]
+    fn memory_swap_below_memory_is_invalid_input() {
+        let options = |swap| {
+            let matches = crate::cli().get_matches_from(vec!["hab-pkg-export-docker",
+                                                             "--memory",
+                                                             "2gb",
+                                                             "--memory-swap",
+                                                             swap,
+                                                             "acme/app"]);
+            BuildOptions::new_from_cli_matches(&matches).map(|o| o.memory_swap.map(String::from))
+        };
+
+        match options("1gb") {
+            Err(Error::MemorySwapBelowMemory { .. }) => (),
+            r => panic!("Expected MemorySwapBelowMemory, got {:?}", r),
+        }
+        assert_eq!(options("2048m").unwrap(), Some(String::from("2048m")));
+        assert_eq!(options("-1").unwrap(), Some(String::from("-1")));
+    }
+
+    #[test]
+    fn build_args_include_cache_from_images() {
+        let builder =
+            DockerBuilder::new(Path::new("/tmp"), "acme/app").cache_from("acme/app:latest")
+                                                             .cache_from("acme/base:1.0.0");
+
+        assert_eq!(builder.build_args(),
+                   vec!["build",
+                        "--force-rm",
+                        "--cache-from",
+                        "acme/app:latest",
+                        "--cache-from",
+                        "acme/base:1.0.0",
+                        "--tag",
+                        "acme/app",
+                        "."]);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn build_args_include_build_args_from_cli() {
+        let matches = crate::cli().get_matches_from(vec!["hab-pkg-export-docker",
+                                                         "--base-image",
+                                                         "acme/base:${BASE_TAG}",
+                                                         "--build-arg",
+                                                         "BASE_TAG=3.11",
+                                                         "--build-arg",
+                                                         "http_proxy=http://proxy:3128",
+                                                         "acme/app"]);
+        let options = BuildOptions::new_from_cli_matches(&matches).unwrap();
+        let builder = options.build_args
+                             .iter()
+                             .fold(DockerBuilder::new(Path::new("/tmp"), "acme/app"),
+                                   DockerBuilder::build_arg);
+
+        assert_eq!(builder.build_args(),
+                   vec!["build",
+                        "--force-rm",
+                        "--build-arg",
+                        "BASE_TAG=3.11",
+                        "--build-arg",
+                        "http_proxy=http://proxy:3128",
+                        "--tag",
+                        "acme/app",
+                        "."]);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn invalid_build_args_are_rejected_by_cli() {
+        let result = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                             "--base-image",
+                                                             "acme/base:${BASE_TAG}",
+                                                             "--build-arg",
+                                                             "BASE-TAG=3.11",
+                                                             "acme/app"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn build_args_are_not_accepted_without_base_image() {
+        let result = crate::cli().get_matches_from_safe(vec!["hab-pkg-export-docker",
+                                                             "--build-arg",
+                                                             "BASE_TAG=3.11",
+                                                             "acme/app"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn build_args_include_platform() {
+        let builder = DockerBuilder::new(Path::new("/tmp"), "acme/app").platform("linux/arm64");
+
+        assert_eq!(builder.build_args(),
+                   vec!["build",
+                        "--force-rm",
+                        "--platform",
+                        "linux/arm64",
+                        "--tag",
+                        "acme/app",
+                        "."]);
+    }
+
+    #[test]
+    fn build_args_pass_engine_opts_before_build_context() {
+        let builder = DockerBuilder::new(Path::new("/tmp"), "acme/app").engine_opt("--network")
+                                                                       .engine_opt("host")
+                                                                       .engine_opt("--no-cache");
+
+        assert_eq!(builder.build_args(),
+                   vec!["build",
+                        "--force-rm",
+                        "--tag",
+                        "acme/app",
+                        "--network",
+                        "host",
+                        "--no-cache",
+                        "."]);
+    }
 }
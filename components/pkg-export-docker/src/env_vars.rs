@@ -0,0 +1,251 @@
+//! Environment variables which are set in the image with `ENV` directives, given either inline as
+//! `KEY=VALUE` pairs or in a dotenv-style file.
+
+use crate::error::{Error,
+                   Result};
+use std::{fs,
+          path::Path,
+          result,
+          str::FromStr};
+
+/// An environment variable set in the image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvVar {
+    pub name:  String,
+    pub value: String,
+}
+
+impl EnvVar {
+    /// Returns the value as a double-quoted Dockerfile string, escaping characters which would
+    /// otherwise end the string or be substituted by the container engine.
+    pub fn quoted_value(&self) -> String {
+        let mut quoted = String::with_capacity(self.value.len() + 2);
+        quoted.push('"');
+        for c in self.value.chars() {
+            if c == '"' || c == '\\' || c == '$' {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+impl FromStr for EnvVar {
+    type Err = Error;
+
+    /// Parses a `KEY=VALUE` pair, in the same form accepted by `docker run --env`. The value is
+    /// taken as is, as any quoting has already been handled by the shell.
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, '=');
+        let name = parts.next().unwrap_or_default();
+        match parts.next() {
+            Some(var_value) if valid_name(name) => {
+                Ok(EnvVar { name:  name.to_string(),
+                            value: var_value.to_string(), })
+            }
+            _ => Err(Error::InvalidEnvVar(value.to_string())),
+        }
+    }
+}
+
+/// Returns `true` if the name is a letter or underscore followed by letters, digits, or
+/// underscores.
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// An ordered set of environment variables, where setting a variable again replaces its earlier
+/// value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnvVars {
+    vars: Vec<EnvVar>,
+}
+
+impl EnvVars {
+    /// Reads environment variables from a dotenv-style file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read
+    /// * If a line is not a valid `KEY=VALUE` pair
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses environment variables with one `KEY=VALUE` pair per line. Blank lines and lines
+    /// starting with `#` are ignored, as is an `export` prefix. Values may be wrapped in single
+    /// quotes, which are taken literally, or double quotes, within which `\"` and `\\` are
+    /// escapes. Unquoted values end at a `#` preceded by whitespace.
+    ///
+    /// # Errors
+    ///
+    /// * If a line is not a valid `KEY=VALUE` pair
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut env_vars = EnvVars::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidEnvVar(line.to_string());
+            let assignment = if line.starts_with("export ") {
+                line["export ".len()..].trim_start()
+            } else {
+                line
+            };
+            let mut parts = assignment.splitn(2, '=');
+            let name = parts.next().unwrap_or_default().trim_end();
+            let value = parts.next().ok_or_else(invalid)?.trim_start();
+            if !valid_name(name) {
+                return Err(invalid().into());
+            }
+            let value = parse_value(value).ok_or_else(invalid)?;
+            env_vars.set(EnvVar { name: name.to_string(),
+                                  value });
+        }
+        Ok(env_vars)
+    }
+
+    /// Sets a variable, replacing the value of any earlier variable with the same name.
+    pub fn set(&mut self, var: EnvVar) {
+        match self.vars.iter_mut().find(|v| v.name == var.name) {
+            Some(existing) => existing.value = var.value,
+            None => self.vars.push(var),
+        }
+    }
+
+    /// Returns `true` if no variables are set.
+    pub fn is_empty(&self) -> bool { self.vars.is_empty() }
+
+    /// Returns the variables in the order they were first set.
+    pub fn iter(&self) -> impl Iterator<Item = &EnvVar> { self.vars.iter() }
+}
+
+/// Parses the value of a dotenv-style assignment, returning `None` if a quoted value is not
+/// terminated or is followed by anything other than a comment.
+fn parse_value(value: &str) -> Option<String> {
+    let (parsed, rest) = if value.starts_with('\'') {
+        let end = value[1..].find('\'')? + 1;
+        (value[1..end].to_string(), &value[end + 1..])
+    } else if value.starts_with('"') {
+        let mut parsed = String::new();
+        let mut chars = value.char_indices().skip(1);
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => {
+                    match chars.next()? {
+                        (_, c @ '"') | (_, c @ '\\') => parsed.push(c),
+                        (_, c) => {
+                            parsed.push('\\');
+                            parsed.push(c);
+                        }
+                    }
+                }
+                (_, c) => parsed.push(c),
+            }
+        };
+        (parsed, &value[end + 1..])
+    } else {
+        let end = value.find(" #")
+                       .or_else(|| value.find("\t#"))
+                       .unwrap_or_else(|| value.len());
+        return Some(value[..end].trim_end().to_string());
+    };
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Some(parsed)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars(env_vars: &EnvVars) -> Vec<(&str, &str)> {
+        env_vars.iter()
+                .map(|v| (v.name.as_str(), v.value.as_str()))
+                .collect()
+    }
+
+    #[test]
+    fn env_var_parses_key_value_pairs() {
+        let var: EnvVar = "JAVA_OPTS=-Xmx1g -Dfoo=bar".parse().unwrap();
+        assert_eq!("JAVA_OPTS", var.name);
+        assert_eq!("-Xmx1g -Dfoo=bar", var.value);
+
+        let var: EnvVar = "EMPTY=".parse().unwrap();
+        assert_eq!("", var.value);
+    }
+
+    #[test]
+    fn invalid_env_vars_are_rejected() {
+        assert!("".parse::<EnvVar>().is_err());
+        assert!("NO_VALUE".parse::<EnvVar>().is_err());
+        assert!("=value".parse::<EnvVar>().is_err());
+        assert!("1ST=value".parse::<EnvVar>().is_err());
+        assert!("MY VAR=value".parse::<EnvVar>().is_err());
+    }
+
+    #[test]
+    fn env_var_values_are_quoted_for_dockerfiles() {
+        let var: EnvVar = r#"GREETING=say "hi" to $USER\n"#.parse().unwrap();
+        assert_eq!(r#""say \"hi\" to \$USER\\n""#, var.quoted_value());
+    }
+
+    #[test]
+    fn env_file_ignores_comments_and_blank_lines() {
+        let contents = ["# database settings",
+                        "",
+                        "DB_HOST=db.example.com",
+                        "    # port",
+                        "export DB_PORT = 5432 # default"].join("\n");
+        let env_vars = EnvVars::parse(&contents).unwrap();
+
+        assert_eq!(vec![("DB_HOST", "db.example.com"), ("DB_PORT", "5432")],
+                   vars(&env_vars));
+    }
+
+    #[test]
+    fn env_file_parses_quoted_values() {
+        let contents = [r#"SINGLE='it has "quotes" and a # sign'"#,
+                        r#"DOUBLE="escaped \"quotes\" and \\ backslash" # comment"#,
+                        r#"RAW="C:\path""#,
+                        "HASH=abc#123",
+                        r#"EMPTY="""#].join("\n");
+        let env_vars = EnvVars::parse(&contents).unwrap();
+
+        assert_eq!(vec![("SINGLE", r#"it has "quotes" and a # sign"#),
+                        ("DOUBLE", r#"escaped "quotes" and \ backslash"#),
+                        ("RAW", r#"C:\path"#),
+                        ("HASH", "abc#123"),
+                        ("EMPTY", "")],
+                   vars(&env_vars));
+    }
+
+    #[test]
+    fn env_file_rejects_invalid_lines() {
+        assert!(EnvVars::parse("NO_VALUE").is_err());
+        assert!(EnvVars::parse("UNTERMINATED=\"value").is_err());
+        assert!(EnvVars::parse("TRAILING='value' extra").is_err());
+        assert!(EnvVars::parse("BAD-NAME=value").is_err());
+    }
+
+    #[test]
+    fn setting_a_var_again_replaces_its_value_in_place() {
+        let mut env_vars = EnvVars::parse("A=1\nB=2\nA=3").unwrap();
+        env_vars.set("B=4".parse().unwrap());
+        env_vars.set("C=5".parse().unwrap());
+
+        assert_eq!(vec![("A", "3"), ("B", "4"), ("C", "5")], vars(&env_vars));
+    }
+}
@@ -8,36 +8,538 @@ use std::{process::ExitStatus,
 
 pub type Result<T> = result::Result<T, failure::Error>;
 
+/// Process exit code for any failure which does not fall into one of the classes below.
+pub const EXIT_CODE_GENERIC: i32 = 1;
+/// Process exit code when the provided arguments are invalid.
+pub const EXIT_CODE_INVALID_INPUT: i32 = 2;
+/// Process exit code when Habitat packages cannot be resolved or installed into the build root.
+pub const EXIT_CODE_RESOLUTION: i32 = 3;
+/// Process exit code when the Docker image cannot be built.
+pub const EXIT_CODE_BUILD: i32 = 4;
+/// Process exit code when authenticating with a remote registry fails.
+pub const EXIT_CODE_AUTH: i32 = 5;
+/// Process exit code when pushing the Docker image to a remote registry fails.
+pub const EXIT_CODE_PUSH: i32 = 6;
+/// Process exit code when removing the local Docker image fails.
+pub const EXIT_CODE_CLEANUP: i32 = 7;
+/// Process exit code when scanning the Docker image reports a failure.
+pub const EXIT_CODE_SCAN: i32 = 8;
+/// Process exit code when signing the pushed Docker image fails.
+pub const EXIT_CODE_SIGN: i32 = 9;
+/// Process exit code when the export is interrupted by a signal, following the shell convention
+/// for a process terminated by SIGINT.
+pub const EXIT_CODE_INTERRUPTED: i32 = 130;
+
 #[derive(Debug, Fail)]
 pub enum Error {
+    #[fail(display = "The artifact of {} is not in the artifact cache", _0)]
+    ArtifactNotCached(String),
     #[fail(display = "{}", _0)]
     Base64DecodeError(DecodeError),
+    #[fail(display = "The program '{}' was not found in the bin path of {}",
+           binary, ident)]
+    BinaryNotFound { binary: String, ident: String },
     #[fail(display = "Docker build failed with exit code: {}", _0)]
     BuildFailed(ExitStatus),
+    #[fail(display = "Cannot create the build root in '{}': {}. Check that the directory exists \
+                      and is writable",
+           path, reason)]
+    BuildRootDirNotWritable { path: String, reason: String },
+    #[fail(display = "Cannot render the configuration of {}: {}", ident, reason)]
+    ConfigRenderFailed { ident: String, reason: String },
     #[fail(display = "Could not determine Docker image ID for image: {}", _0)]
     DockerImageIdNotFound(String),
     #[fail(display = "Switch to Windows containers to export Docker images on Windows. Current \
                       Docker Server OS is set to: {}",
            _0)]
     DockerNotInWindowsMode(String),
+    #[fail(display = "The generated Dockerfile failed the Dockerfile policy with exit code: {}",
+           _0)]
+    DockerfilePolicyFailed(ExitStatus),
+    #[fail(display = "Docker is not available: {}", _0)]
+    DockerUnavailable(String),
+    #[fail(display = "Could not determine the registry digest of pushed image: {}",
+           _0)]
+    RepoDigestNotFound(String),
+    #[fail(display = "Could not determine the registry digest of pushed image {} to write to \
+                      --digest-file",
+           _0)]
+    PushedDigestNotFound(String),
+    #[fail(display = "The package {} cannot be excluded, as it is required at runtime by {}",
+           ident, required_by)]
+    ExcludedDepRequired {
+        ident:       String,
+        required_by: String,
+    },
+    #[fail(display = "No package in the image matches the excluded dependency '{}'",
+           _0)]
+    ExcludedDepNotFound(String),
+    #[fail(display = "The {} hook of {} requires a shell, which is not available in an image \
+                      exported with --no-shell. Hooks must be programs which can be run directly",
+           hook, ident)]
+    HookRequiresShell { ident: String, hook: String },
+    #[fail(display = "The package identifier '{}' is not fully qualified, as required by \
+                      --strict-ident. Fully qualified package identifiers have the form \
+                      origin/name/version/release",
+           _0)]
+    IdentNotFullyQualified(String),
+    #[fail(display = "The package identifier '{}' is not fully qualified, as required by \
+                      --list-tags to determine the image's tags without building it. Fully \
+                      qualified package identifiers have the form origin/name/version/release",
+           _0)]
+    ListTagsIdentNotFullyQualified(String),
+    #[fail(display = "Invalid annotation: '{}'. Annotations have the form KEY=VALUE, where the \
+                      key is made of lowercase letters, digits, '.', '-', '_', and '/'",
+           _0)]
+    InvalidAnnotation(String),
+    #[fail(display = "Invalid build arg: '{}'. Build args have the form KEY=VALUE, where the \
+                      key is made of letters, digits, and '_', and does not start with a digit",
+           _0)]
+    InvalidBuildArg(String),
+    #[fail(display = "Invalid capability: '{}'. Capabilities are Linux capability names (ex: \
+                      NET_ADMIN or CAP_NET_ADMIN)",
+           _0)]
+    InvalidCapability(String),
+    #[fail(display = "Invalid container engine options: '{}'. Check that quotes are balanced",
+           _0)]
+    InvalidEngineOpts(String),
+    #[fail(display = "Invalid environment variable: '{}'. Environment variables have the form \
+                      KEY=VALUE",
+           _0)]
+    InvalidEnvVar(String),
+    #[fail(display = "Invalid exclude pattern: '{}'", _0)]
+    InvalidExcludePattern(String),
+    #[fail(display = "Invalid lock file entry: '{}'. Lock files list one fully qualified \
+                      package identifier (origin/name/version/release) per line",
+           _0)]
+    InvalidLockFileEntry(String),
+    #[fail(display = "Invalid bundle spec '{}': {}", path, reason)]
+    InvalidBundleSpec { path: String, reason: String },
+    #[fail(display = "Invalid Builder URL: '{}'", _0)]
+    InvalidBldrUrl(String),
+    #[fail(display = "The Docker image is {} bytes, which exceeds the maximum image size of {} \
+                      bytes",
+           actual, limit)]
+    ImageTooLarge { actual: u64, limit: u64 },
+    #[fail(display = "Invalid image name: '{}'. Image names are lowercase alphanumeric path \
+                      components separated by '/', with an optional registry host",
+           _0)]
+    InvalidImageName(String),
+    #[fail(display = "Invalid image name placeholder: '{}'. Supported placeholders are \
+                      {{origin}}, {{name}}, {{version}}, and {{release}}",
+           _0)]
+    InvalidImageNamePlaceholder(String),
+    #[fail(display = "Invalid init system: '{}'. Init systems are none, tini, or dumb-init",
+           _0)]
+    InvalidInitSystem(String),
+    #[fail(display = "Invalid host entry: '{}'. Host entries have the form NAME:IP",
+           _0)]
+    InvalidHostEntry(String),
+    #[fail(display = "The token for Harbor robot account '{}' is malformed. Check that it was \
+                      copied without surrounding whitespace",
+           _0)]
+    InvalidRobotToken(String),
+    #[fail(display = "Invalid configuration values file '{}': {}", path, reason)]
+    InvalidRenderConfig { path: String, reason: String },
+    #[fail(display = "Invalid response file '{}': {}", path, reason)]
+    InvalidResponseFile { path: String, reason: String },
+    #[fail(display = "Invalid registry client certificate '{}': {}", path, reason)]
+    InvalidRegistryClientCert { path: String, reason: String },
     #[fail(display = "Invalid registry type: {}", _0)]
     InvalidRegistryType(String),
+    #[fail(display = "The registry did not respond within {} seconds. Use --registry-timeout to \
+                      allow more time",
+           _0)]
+    RegistryTimeout(u64),
+    #[fail(display = "Invalid size: '{}'. Sizes are a number of bytes with an optional unit of \
+                      k, m, g, t, or p (ex: 500m)",
+           _0)]
+    InvalidSize(String),
+    #[fail(display = "The --memory-swap limit '{}' is less than the --memory limit '{}'. The \
+                      limit on memory plus swap must be at least the memory limit, or -1 for \
+                      unlimited swap",
+           memory_swap, memory)]
+    MemorySwapBelowMemory {
+        memory:      String,
+        memory_swap: String,
+    },
+    #[fail(display = "Invalid stop signal: '{}'. Stop signals are a signal name (ex: SIGTERM) \
+                      or number (ex: 15)",
+           _0)]
+    InvalidStopSignal(String),
+    #[fail(display = "Invalid image tag: '{}'. Tags are up to 128 letters, digits, '_', '.', \
+                      and '-', and do not start with '.' or '-'",
+           _0)]
+    InvalidTag(String),
+    #[fail(display = "Invalid tag version format: '{}'. Formats contain one {{version}} and one \
+                      {{release}} placeholder (ex: {{version}}_{{release}})",
+           _0)]
+    InvalidTagVersionFormat(String),
+    #[fail(display = "Invalid user namespace mapping: '{}'. Mappings have the form START:COUNT \
+                      (ex: 100000:65536), where COUNT is at least 1",
+           _0)]
+    InvalidUsernsRemap(String),
     #[fail(display = "{}", _0)]
     InvalidToken(FromUtf8Error),
+    #[fail(display = "The export was interrupted by {}", _0)]
+    Interrupted(String),
+    #[fail(display = "The resolved packages do not match the lock file: {:?}", _0)]
+    LockFileMismatch(Vec<String>),
+    #[fail(display = "No package in the lock file satisfies '{}'. Emit a new lock file with \
+                      --emit-lock-file to include it",
+           _0)]
+    LockedPackageNotFound(String),
     #[fail(display = "Docker login failed with exit code: {}", _0)]
     LoginFailed(ExitStatus),
     #[fail(display = "Docker logout failed with exit code: {}", _0)]
     LogoutFailed(ExitStatus),
+    #[fail(display = "A registry password is required to push the Docker image. Provide one \
+                      with --password, HAB_REGISTRY_PASSWORD or --interactive-password-prompt")]
+    MissingRegistryPassword,
+    #[fail(display = "A registry username is required to push the Docker image. Provide one \
+                      with --username or HAB_REGISTRY_USERNAME")]
+    MissingRegistryUsername,
     #[fail(display = "No ECR Tokens returned")]
     NoECRTokensReturned,
+    #[fail(display = "No Habitat package identifiers or artifacts were provided. At least one \
+                      package must be specified to export.")]
+    NoPackagesSpecified,
+    #[fail(display = "The tag '{}' already exists in the remote registry. Remove \
+                      --fail-on-existing-tag to overwrite it",
+           tag)]
+    TagAlreadyExists { tag: String },
+    #[fail(display = "Could not determine whether the tag '{}' exists in the remote registry, \
+                      so it may not be overwritten: {}",
+           tag, reason)]
+    TagLookupFailed { tag: String, reason: String },
+    #[fail(display = "Docker image tag failed with exit code: {}", _0)]
+    TagImageFailed(ExitStatus),
+    #[fail(display = "Images cannot be built for the package target {}. Supported targets are \
+                      x86_64-linux, x86_64-linux-kernel2, x86_64-windows and aarch64-linux",
+           _0)]
+    UnsupportedTarget(String),
+    #[fail(display = "Registry token exchange failed: {}", _0)]
+    TokenExchangeFailed(String),
     #[fail(display = "{}", _0)]
     TokenFetchFailed(RusotoError<GetAuthorizationTokenError>),
+    #[fail(display = "Amazon ECR did not issue an authorization token within {} seconds. Check \
+                      the network connection to AWS, or use --ecr-token-timeout to allow more \
+                      time",
+           _0)]
+    TokenFetchTimedOut(u64),
+    #[fail(display = "Packages could not be installed: {:?}", _0)]
+    PackagesNotInstalled(Vec<String>),
     #[fail(display = "A primary service package could not be determined from: {:?}. At least \
                       one package with a run hook must be provided.",
            _0)]
     PrimaryServicePackageNotFound(Vec<String>),
+    #[fail(display = "Cannot prompt for a registry password in a non-interactive session. \
+                      Provide one with --password instead")]
+    PasswordPromptNotInteractive,
+    #[fail(display = "No registry token found at '{}' in the token endpoint response",
+           _0)]
+    TokenNotFound(String),
     #[fail(display = "Docker image push failed with exit code: {}", _0)]
     PushImageFailed(ExitStatus),
     #[fail(display = "Removing Docker local images failed with exit code: {}", _0)]
     RemoveImageFailed(ExitStatus),
+    #[fail(display = "Docker image scan failed with exit code: {}", _0)]
+    ScanFailed(ExitStatus),
+    #[fail(display = "Docker image signing failed with exit code: {}", _0)]
+    SignFailed(ExitStatus),
+    #[fail(display = "Docker image smoke test failed: {}", _0)]
+    SmokeTestFailed(String),
+    #[fail(display = "The package {} targets {}, which cannot run on the Linux Docker daemon \
+                      used on this host. Export it on a Windows host with Docker switched to \
+                      Windows containers",
+           ident, target)]
+    WindowsPackageOnLinux { ident: String, target: String },
+    #[fail(display = "The export emitted warnings, which are errors with --warn-as-error: {:?}",
+           _0)]
+    WarningsAsErrors(Vec<String>),
+}
+
+impl Error {
+    /// Returns the process exit code for the class of failure this error belongs to.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Error::BuildRootDirNotWritable { .. }
+            | Error::ExcludedDepNotFound(_)
+            | Error::IdentNotFullyQualified(_)
+            | Error::ListTagsIdentNotFullyQualified(_)
+            | Error::InvalidAnnotation(_)
+            | Error::InvalidBldrUrl(_)
+            | Error::InvalidBuildArg(_)
+            | Error::InvalidBundleSpec { .. }
+            | Error::InvalidCapability(_)
+            | Error::InvalidEngineOpts(_)
+            | Error::InvalidEnvVar(_)
+            | Error::InvalidExcludePattern(_)
+            | Error::InvalidHostEntry(_)
+            | Error::InvalidImageName(_)
+            | Error::InvalidImageNamePlaceholder(_)
+            | Error::InvalidInitSystem(_)
+            | Error::InvalidLockFileEntry(_)
+            | Error::InvalidRegistryClientCert { .. }
+            | Error::InvalidRegistryType(_)
+            | Error::InvalidRenderConfig { .. }
+            | Error::InvalidResponseFile { .. }
+            | Error::InvalidSize(_)
+            | Error::InvalidStopSignal(_)
+            | Error::InvalidTag(_)
+            | Error::InvalidTagVersionFormat(_)
+            | Error::InvalidUsernsRemap(_)
+            | Error::MemorySwapBelowMemory { .. }
+            | Error::MissingRegistryPassword
+            | Error::MissingRegistryUsername
+            | Error::PasswordPromptNotInteractive
+            | Error::NoPackagesSpecified
+            | Error::UnsupportedTarget(_)
+            | Error::WarningsAsErrors(_)
+            | Error::WindowsPackageOnLinux { .. } => EXIT_CODE_INVALID_INPUT,
+            Error::ArtifactNotCached(_)
+            | Error::BinaryNotFound { .. }
+            | Error::ConfigRenderFailed { .. }
+            | Error::ExcludedDepRequired { .. }
+            | Error::HookRequiresShell { .. }
+            | Error::LockFileMismatch(_)
+            | Error::LockedPackageNotFound(_)
+            | Error::PackagesNotInstalled(_)
+            | Error::PrimaryServicePackageNotFound(_) => EXIT_CODE_RESOLUTION,
+            Error::BuildFailed(_)
+            | Error::DockerfilePolicyFailed(_)
+            | Error::DockerImageIdNotFound(_)
+            | Error::DockerNotInWindowsMode(_)
+            | Error::DockerUnavailable(_)
+            | Error::ImageTooLarge { .. }
+            | Error::SmokeTestFailed(_)
+            | Error::TagImageFailed(_) => EXIT_CODE_BUILD,
+            Error::Base64DecodeError(_)
+            | Error::InvalidRobotToken(_)
+            | Error::InvalidToken(_)
+            | Error::LoginFailed(_)
+            | Error::LogoutFailed(_)
+            | Error::NoECRTokensReturned
+            | Error::RegistryTimeout(_)
+            | Error::TokenExchangeFailed(_)
+            | Error::TokenFetchFailed(_)
+            | Error::TokenFetchTimedOut(_)
+            | Error::TokenNotFound(_) => EXIT_CODE_AUTH,
+            Error::PushImageFailed(_)
+            | Error::PushedDigestNotFound(_)
+            | Error::TagAlreadyExists { .. }
+            | Error::TagLookupFailed { .. } => EXIT_CODE_PUSH,
+            Error::RemoveImageFailed(_) => EXIT_CODE_CLEANUP,
+            Error::ScanFailed(_) => EXIT_CODE_SCAN,
+            Error::RepoDigestNotFound(_) | Error::SignFailed(_) => EXIT_CODE_SIGN,
+            Error::Interrupted(_) => EXIT_CODE_INTERRUPTED,
+        }
+    }
+}
+
+/// Returns the process exit code for an error returned from an export.
+///
+/// | Exit code | Failure class                                                |
+/// |-----------|--------------------------------------------------------------|
+/// | 1         | Any other failure                                            |
+/// | 2         | Invalid arguments                                            |
+/// | 3         | Package resolution, installation, or primary service lookup |
+/// | 4         | Docker image build                                           |
+/// | 5         | Registry authentication                                      |
+/// | 6         | Docker image push                                            |
+/// | 7         | Local Docker image removal                                   |
+/// | 8         | Docker image scan                                            |
+/// | 9         | Docker image signing                                         |
+/// | 130       | Interrupted by a signal, such as from Ctrl-C                 |
+///
+/// Errors originating from the Habitat core and common libraries are raised while resolving and
+/// installing packages into the build root, and are therefore reported as resolution failures.
+pub fn exit_code(err: &failure::Error) -> i32 {
+    if let Some(e) = err.downcast_ref::<Error>() {
+        e.exit_code()
+    } else if err.downcast_ref::<habitat_common::Error>().is_some()
+              || err.downcast_ref::<habitat_core::Error>().is_some()
+    {
+        EXIT_CODE_RESOLUTION
+    } else {
+        EXIT_CODE_GENERIC
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io;
+
+    fn exit_status() -> ExitStatus {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+
+        ExitStatus::from_raw(1)
+    }
+
+    fn assert_exit_code(expected: i32, err: Error) {
+        assert_eq!(expected, err.exit_code(), "{:?}", err);
+        assert_eq!(expected, exit_code(&err.into()));
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes_by_class() {
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::ExcludedDepNotFound(String::from("core/perl")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::BuildRootDirNotWritable { path:   String::from("/mnt/build"),
+                                                          reason: String::from("read-only"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::IdentNotFullyQualified(String::from("core/redis")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::ListTagsIdentNotFullyQualified(String::from("core/redis")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidAnnotation(String::from("NO_VALUE")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidBldrUrl(String::from("nope")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidBuildArg(String::from("NO_VALUE")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidBundleSpec { path:   String::from("bundle.toml"),
+                                                    reason: String::from("unknown key 'x'"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidCapability(String::from("ALL")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidEngineOpts(String::from("--label \"a")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidEnvVar(String::from("NO_VALUE")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidExcludePattern(String::from("[")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidHostEntry(String::from("nope")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidImageName(String::from("Acme/Redis")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidImageNamePlaceholder(String::from("{channel}")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidInitSystem(String::from("systemd")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidLockFileEntry(String::from("core/redis")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidRegistryClientCert { path:   String::from("client.pem"),
+                                                            reason: String::from("not found"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidRegistryType(String::from("nope")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidRenderConfig { path:   String::from("values.toml"),
+                                                      reason: String::from("expected a value"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidResponseFile { path:   String::from("options.txt"),
+                                                      reason: String::from("not found"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidSize(String::from("2bg")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::MemorySwapBelowMemory { memory:      String::from("2gb"),
+                                                        memory_swap: String::from("1gb"), });
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidStopSignal(String::from("SIGNOPE")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidTag(String::from("-4.0.14")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidTagVersionFormat(String::from("{version}")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::InvalidUsernsRemap(String::from("100000")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT, Error::MissingRegistryPassword);
+        assert_exit_code(EXIT_CODE_INVALID_INPUT, Error::MissingRegistryUsername);
+        assert_exit_code(EXIT_CODE_INVALID_INPUT, Error::NoPackagesSpecified);
+        assert_exit_code(EXIT_CODE_INVALID_INPUT, Error::PasswordPromptNotInteractive);
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::UnsupportedTarget(String::from("x86_64-darwin")));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::WarningsAsErrors(vec![String::from("Skipping the version tag")]));
+        assert_exit_code(EXIT_CODE_INVALID_INPUT,
+                         Error::WindowsPackageOnLinux { ident:  String::from("acme/iis"),
+                                                        target: String::from("x86_64-windows"), });
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::ArtifactNotCached(String::from("core/redis/4.0.14/\
+                                                                20190319155852")));
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::BinaryNotFound { binary: String::from("redis-cli"),
+                                                 ident:  String::from("acme/redis"), });
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::ConfigRenderFailed { ident:  String::from("acme/redis"),
+                                                     reason: String::from("bad template"), });
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::ExcludedDepRequired { ident:       String::from("core/openssl"),
+                                                      required_by: String::from("core/hab-sup"), });
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::HookRequiresShell { ident: String::from("acme/redis"),
+                                                    hook:  String::from("run"), });
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::LockFileMismatch(vec![String::from("core/redis/4.0.14/\
+                                                                    20190319155852 (not \
+                                                                    resolved)")]));
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::LockedPackageNotFound(String::from("core/nginx")));
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::PackagesNotInstalled(vec![String::from("acme/nope: not found")]));
+        assert_exit_code(EXIT_CODE_RESOLUTION,
+                         Error::PrimaryServicePackageNotFound(vec![String::from("acme/lib")]));
+        assert_exit_code(EXIT_CODE_BUILD, Error::BuildFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::DockerfilePolicyFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::DockerImageIdNotFound(String::from("acme/app")));
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::DockerNotInWindowsMode(String::from("linux")));
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::DockerUnavailable(String::from("daemon down")));
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::ImageTooLarge { actual: 2048,
+                                                limit:  1024, });
+        assert_exit_code(EXIT_CODE_BUILD,
+                         Error::SmokeTestFailed(String::from("the container exited")));
+        assert_exit_code(EXIT_CODE_BUILD, Error::TagImageFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_AUTH,
+                         Error::Base64DecodeError(DecodeError::InvalidLength));
+        assert_exit_code(EXIT_CODE_AUTH,
+                         Error::InvalidRobotToken(String::from("robot$acme+ci")));
+        assert_exit_code(EXIT_CODE_AUTH,
+                         Error::InvalidToken(String::from_utf8(vec![0xff]).unwrap_err()));
+        assert_exit_code(EXIT_CODE_AUTH, Error::LoginFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_AUTH, Error::LogoutFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_AUTH, Error::NoECRTokensReturned);
+        assert_exit_code(EXIT_CODE_AUTH, Error::RegistryTimeout(30));
+        assert_exit_code(EXIT_CODE_AUTH,
+                         Error::TokenExchangeFailed(String::from("401 Unauthorized")));
+        assert_exit_code(EXIT_CODE_AUTH,
+                         Error::TokenFetchFailed(RusotoError::Validation(String::from("nope"))));
+        assert_exit_code(EXIT_CODE_AUTH, Error::TokenFetchTimedOut(30));
+        assert_exit_code(EXIT_CODE_AUTH, Error::TokenNotFound(String::from("token")));
+        assert_exit_code(EXIT_CODE_PUSH, Error::PushImageFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_PUSH,
+                         Error::PushedDigestNotFound(String::from("acme/redis")));
+        assert_exit_code(EXIT_CODE_PUSH,
+                         Error::TagAlreadyExists { tag: String::from("acme/redis:4.0.14"), });
+        assert_exit_code(EXIT_CODE_PUSH,
+                         Error::TagLookupFailed { tag:    String::from("acme/redis:4.0.14"),
+                                                  reason: String::from("unauthorized"), });
+        assert_exit_code(EXIT_CODE_CLEANUP, Error::RemoveImageFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_SCAN, Error::ScanFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_SIGN,
+                         Error::RepoDigestNotFound(String::from("acme/redis")));
+        assert_exit_code(EXIT_CODE_SIGN, Error::SignFailed(exit_status()));
+        assert_exit_code(EXIT_CODE_INTERRUPTED,
+                         Error::Interrupted(String::from("SIGINT")));
+    }
+
+    #[test]
+    fn library_errors_map_to_resolution_exit_code() {
+        let err = habitat_core::Error::InvalidPackageIdent(String::from("nope"));
+        assert_eq!(EXIT_CODE_RESOLUTION, exit_code(&err.into()));
+    }
+
+    #[test]
+    fn other_errors_map_to_generic_exit_code() {
+        let err = io::Error::new(io::ErrorKind::Other, "nope");
+        assert_eq!(EXIT_CODE_GENERIC, exit_code(&err.into()));
+    }
 }
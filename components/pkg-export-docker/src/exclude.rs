@@ -0,0 +1,200 @@
+//! Exclusion rules, in the style of a `.dockerignore` file, for paths in the root file system of
+//! a Docker export of a Habitat package.
+
+use crate::error::{Error,
+                   Result};
+use glob::{MatchOptions,
+           Pattern};
+use std::{fs,
+          path::{Path,
+                 PathBuf}};
+
+/// Patterns are matched against paths relative to the root file system, where `*` and `?` never
+/// match a path separator and `**` matches any number of directories.
+const MATCH_OPTIONS: MatchOptions = MatchOptions { case_sensitive:              true,
+                                                   require_literal_separator:   true,
+                                                   require_literal_leading_dot: false, };
+
+/// A single exclusion pattern, which re-includes matching paths when negated.
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+}
+
+/// An ordered set of exclusion rules.
+///
+/// A path is excluded when the last rule matching it, or any of its parent directories, is not
+/// negated.
+#[derive(Debug, Default)]
+pub struct Exclusions {
+    rules: Vec<Rule>,
+}
+
+impl Exclusions {
+    /// Reads exclusion rules from a file with one pattern per line.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read
+    /// * If a pattern is not a valid glob
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses exclusion rules with one pattern per line. Blank lines and lines starting with `#`
+    /// are ignored, and a pattern starting with `!` re-includes paths excluded by earlier
+    /// patterns.
+    ///
+    /// # Errors
+    ///
+    /// * If a pattern is not a valid glob
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = if line.starts_with('!') {
+                (true, &line[1..])
+            } else {
+                (false, line)
+            };
+            let pattern = pattern.trim().trim_start_matches('/').trim_end_matches('/');
+            let pattern =
+                Pattern::new(pattern).map_err(|_| Error::InvalidExcludePattern(line.to_string()))?;
+            rules.push(Rule { pattern, negated });
+        }
+        Ok(Exclusions { rules })
+    }
+
+    /// Returns `true` if there are no exclusion rules.
+    pub fn is_empty(&self) -> bool { self.rules.is_empty() }
+
+    /// Returns `true` if the given path, relative to the root file system, is excluded.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                path.ancestors()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .any(|p| rule.pattern.matches_path_with(p, MATCH_OPTIONS))
+            })
+            .map_or(false, |rule| !rule.negated)
+    }
+
+    /// Removes all excluded paths under the given root file system, returning the removed paths.
+    ///
+    /// Symbolic links are removed rather than followed. A directory is only removed if it is
+    /// excluded and none of its contents are re-included.
+    ///
+    /// # Errors
+    ///
+    /// * If a directory cannot be read
+    /// * If an excluded path cannot be removed
+    pub fn apply<P: AsRef<Path>>(&self, root: P) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        if !self.is_empty() {
+            self.apply_in_dir(root.as_ref(), root.as_ref(), &mut removed)?;
+        }
+        Ok(removed)
+    }
+
+    fn apply_in_dir(&self, root: &Path, dir: &Path, removed: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let relative = path.strip_prefix(root).expect("path is under root");
+            if fs::symlink_metadata(&path)?.is_dir() {
+                self.apply_in_dir(root, &path, removed)?;
+                if self.is_excluded(relative) && fs::read_dir(&path)?.next().is_none() {
+                    fs::remove_dir(&path)?;
+                    removed.push(relative.to_path_buf());
+                }
+            } else if self.is_excluded(relative) {
+                fs::remove_file(&path)?;
+                removed.push(relative.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch<P: AsRef<Path>>(root: &Path, path: P) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_ignored() {
+        let exclusions = Exclusions::parse("# Documentation\n\n   \n# more\n").unwrap();
+
+        assert!(exclusions.is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(Exclusions::parse("hab/pkgs/[").is_err());
+    }
+
+    #[test]
+    fn patterns_match_directories_and_their_contents() {
+        let exclusions = Exclusions::parse("/hab/pkgs/*/*/*/*/docs/").unwrap();
+
+        let pkg = Path::new("hab/pkgs/core/redis/4.0.14/20190319155852");
+
+        assert!(exclusions.is_excluded(&pkg.join("docs")));
+        assert!(exclusions.is_excluded(&pkg.join("docs").join("README.md")));
+        assert!(!exclusions.is_excluded(&pkg.join("bin")));
+        assert!(!exclusions.is_excluded(Path::new("docs")));
+    }
+
+    #[test]
+    fn single_wildcards_do_not_match_path_separators() {
+        let exclusions = Exclusions::parse("hab/*.md\nhab/**/*.txt").unwrap();
+
+        assert!(exclusions.is_excluded(Path::new("hab/README.md")));
+        assert!(!exclusions.is_excluded(Path::new("hab/pkgs/README.md")));
+        assert!(exclusions.is_excluded(Path::new("hab/pkgs/core/LICENSE.txt")));
+    }
+
+    #[test]
+    fn later_patterns_take_precedence() {
+        let exclusions = Exclusions::parse("**/*.md\n!**/README.md\nhab/svc/**/README.md").unwrap();
+
+        assert!(exclusions.is_excluded(Path::new("hab/pkgs/CHANGELOG.md")));
+        assert!(!exclusions.is_excluded(Path::new("hab/pkgs/README.md")));
+        assert!(exclusions.is_excluded(Path::new("hab/svc/redis/README.md")));
+    }
+
+    #[test]
+    fn negated_patterns_re_include_paths_in_excluded_directories() {
+        let root = TempDir::new().unwrap();
+        touch(root.path(), "hab/pkgs/acme/app/docs/README.md");
+        touch(root.path(), "hab/pkgs/acme/app/docs/LICENSE");
+        touch(root.path(), "hab/pkgs/acme/app/man/app.1");
+        touch(root.path(), "hab/pkgs/acme/app/bin/app");
+        let ignore_file = ["# Trim the image",
+                           "hab/pkgs/*/*/docs",
+                           "hab/pkgs/*/*/man",
+                           "!hab/pkgs/*/*/docs/LICENSE"].join("\n");
+        let exclusions = Exclusions::parse(&ignore_file).unwrap();
+
+        let mut removed = exclusions.apply(root.path()).unwrap();
+        removed.sort();
+
+        assert_eq!(removed,
+                   vec![PathBuf::from("hab/pkgs/acme/app/docs/README.md"),
+                        PathBuf::from("hab/pkgs/acme/app/man"),
+                        PathBuf::from("hab/pkgs/acme/app/man/app.1"),]);
+        assert!(root.path().join("hab/pkgs/acme/app/docs/LICENSE").is_file());
+        assert!(root.path().join("hab/pkgs/acme/app/bin/app").is_file());
+        assert!(!root.path().join("hab/pkgs/acme/app/man").exists());
+    }
+}
@@ -1,14 +1,19 @@
 use crate::{build::BasePkgIdents,
-            error::Result};
+            error::{Error,
+                    Result}};
 use habitat_common::package_graph::PackageGraph;
-use habitat_core::package::PackageIdent;
+use habitat_core::package::{Identifiable,
+                            PackageIdent};
 use linked_hash_map::LinkedHashMap;
-use std::path::Path;
+use std::{collections::HashSet,
+          path::Path};
 
 pub struct Graph {
-    g:    PackageGraph,
-    base: BasePkgIdents,
-    user: Vec<PackageIdent>,
+    g:        PackageGraph,
+    base:     BasePkgIdents,
+    user:     Vec<PackageIdent>,
+    /// Transitive dependencies which are left out of the image.
+    excluded: Vec<PackageIdent>,
 }
 
 impl Graph {
@@ -17,7 +22,10 @@ impl Graph {
                          rootfs: &Path)
                          -> Result<Graph> {
         let g = PackageGraph::from_root_path(rootfs)?;
-        Ok(Graph { g, base, user })
+        Ok(Graph { g,
+                   base,
+                   user,
+                   excluded: Vec::new() })
     }
 
     /// Helper function to create a Vec of our base idents in a
@@ -31,6 +39,9 @@ impl Graph {
         if let Some(ref busybox) = self.base.busybox {
             idents.push(busybox.clone());
         }
+        if let Some(ref init) = self.base.init {
+            idents.push(init.clone());
+        }
         idents.push(self.base.launcher.clone());
         idents.push(self.base.hab.clone());
         idents.push(self.base.sup.clone());
@@ -48,12 +59,20 @@ impl Graph {
     /// User packages will be last. Ideally, as users are iterating on
     /// their packages and creating images, this should mean that all
     /// the dependencies are already available as cached layers.
+    ///
+    /// Excluded transitive dependencies, and any of their own
+    /// dependencies which are no longer reachable, are left out.
     pub fn reverse_topological_sort(&self) -> Vec<PackageIdent> {
         self.idents_from_base()
             .into_iter()
             .chain(self.user.clone())
+            .filter(|ident| !self.excluded.contains(ident))
             .map(|ident| {
                 let mut pkgs = self.g.owned_ordered_deps(&ident);
+                if !self.excluded.is_empty() {
+                    let retained = self.retained_deps(&ident);
+                    pkgs.retain(|pkg| retained.contains(pkg));
+                }
                 // We want the most basic dependencies first.
                 pkgs.reverse();
                 // owned_ordered_deps does not include the given
@@ -88,11 +107,100 @@ impl Graph {
             .map(|(k, _v)| k)
             .collect()
     }
+
+    /// Returns the dependencies of a package which are reachable without
+    /// passing through an excluded package.
+    fn retained_deps(&self, ident: &PackageIdent) -> HashSet<PackageIdent> {
+        let mut retained = HashSet::new();
+        let mut pending = vec![ident];
+        while let Some(pkg) = pending.pop() {
+            for dep in self.g.deps(pkg) {
+                if !self.excluded.contains(dep) && retained.insert(dep.clone()) {
+                    pending.push(dep);
+                }
+            }
+        }
+        retained
+    }
+
+    /// Excludes the packages matching the given identifiers from the
+    /// image, along with any of their own dependencies which nothing
+    /// else requires, and returns every package that was left out.
+    ///
+    /// Package metadata can't tell a runtime dependency from one
+    /// that's only needed to build a package, so this is an escape
+    /// hatch for dependencies the user knows aren't needed. Only what
+    /// is certainly needed at runtime is protected: the base packages
+    /// with all of their dependencies, and the user packages with
+    /// their direct dependencies.
+    ///
+    /// # Errors
+    ///
+    /// * If an identifier doesn't match a package in the image
+    /// * If a package that is required at runtime would be excluded
+    pub fn exclude_transitive_deps(&mut self,
+                                   idents: &[PackageIdent])
+                                   -> Result<Vec<PackageIdent>> {
+        let before = self.reverse_topological_sort();
+        let previously_excluded = self.excluded.len();
+        for ident in idents {
+            let matches: Vec<_> = before.iter()
+                                        .filter(|pkg| pkg.satisfies(ident))
+                                        .cloned()
+                                        .collect();
+            if matches.is_empty() {
+                return Err(Error::ExcludedDepNotFound(ident.to_string()).into());
+            }
+            self.excluded.extend(matches);
+        }
+
+        let after = self.reverse_topological_sort();
+        if let Err(e) = self.check_runtime_deps(&after) {
+            self.excluded.truncate(previously_excluded);
+            return Err(e);
+        }
+        Ok(before.into_iter()
+                 .filter(|pkg| !after.contains(pkg))
+                 .collect())
+    }
+
+    /// Checks that the remaining packages still contain everything
+    /// required at runtime.
+    fn check_runtime_deps(&self, remaining: &[PackageIdent]) -> Result<()> {
+        let require = |ident: &PackageIdent, required_by: String| -> Result<()> {
+            if remaining.contains(ident) {
+                Ok(())
+            } else {
+                Err(Error::ExcludedDepRequired { ident: ident.to_string(),
+                                                 required_by }.into())
+            }
+        };
+        for ident in self.idents_from_base() {
+            require(&ident, String::from("the exported image"))?;
+            for dep in self.g.ordered_deps(&ident) {
+                require(dep, ident.to_string())?;
+            }
+        }
+        for ident in &self.user {
+            require(ident, String::from("the exported image"))?;
+            for dep in self.g.deps(ident) {
+                require(dep, ident.to_string())?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{closure::ClosureManifest,
+                lockfile::PackageLock};
+    use habitat_core::{crypto::hash,
+                       package::PackageTarget};
+    use std::{fs,
+              str::FromStr};
+    use tempfile::TempDir;
 
     /// Helper macro to create PackageIdents and make them easily
     /// accessible via function invocation.
@@ -131,6 +239,12 @@ mod tests {
     pkg!(openssl_fips, "core/openssl-fips/2.0.16/20190115014207");
     pkg!(zeromq, "core/zeromq/4.3.1/20190802173651");
 
+    // A dependency of the user package which pulls in a build-time
+    // tool, along with the tool's own dependency
+    pkg!(jemalloc, "core/jemalloc/5.2.1/20200305224417");
+    pkg!(perl, "core/perl/5.30.1/20200305211934");
+    pkg!(gdbm, "core/gdbm/1.18.1/20200305205718");
+
     /// Create a Graph manually, bypassing the need to generate one
     /// based on the package contents of a local directory.
     fn test_graph() -> Result<Graph> {
@@ -172,13 +286,15 @@ mod tests {
                                    sup:      sup(),
                                    launcher: launcher(),
                                    busybox:  Some(busybox()),
+                                   init:     None,
                                    cacerts:  cacerts(), };
 
         let user = vec![redis()];
 
         Ok(Graph { base,
                    user,
-                   g: graph })
+                   g: graph,
+                   excluded: Vec::new() })
     }
 
     #[test]
@@ -211,4 +327,163 @@ mod tests {
 
         assert_eq!(actual_deps, expected_deps);
     }
+
+    /// The test graph, where the user package's dependency on jemalloc
+    /// transitively pulls in perl and gdbm.
+    fn test_graph_with_build_tool() -> Graph {
+        let mut g = test_graph().unwrap();
+        g.g.extend(&redis(), &[jemalloc()]);
+        g.g.extend(&jemalloc(), &[glibc(), perl()]);
+        g.g.extend(&perl(), &[glibc(), gdbm()]);
+        g.g.extend(&gdbm(), &[glibc()]);
+        g
+    }
+
+    #[test]
+    fn transitive_deps_only_needed_by_user_deps_can_be_excluded() {
+        let mut g = test_graph_with_build_tool();
+
+        let mut excluded = g.exclude_transitive_deps(&["core/perl".parse().unwrap()])
+                            .unwrap();
+        excluded.sort();
+        assert_eq!(vec![gdbm(), perl()], excluded);
+
+        let remaining = g.reverse_topological_sort();
+        assert!(!remaining.contains(&perl()));
+        assert!(!remaining.contains(&gdbm()));
+        assert!(remaining.contains(&jemalloc()));
+        assert!(remaining.contains(&glibc()));
+        assert_eq!(Some(&redis()), remaining.last());
+    }
+
+    #[test]
+    fn deps_required_at_runtime_cannot_be_excluded() {
+        for (ident, required_by) in &[("core/openssl", launcher()),
+                                      ("core/jemalloc", redis()),
+                                      ("core/zeromq", sup())]
+        {
+            let mut g = test_graph_with_build_tool();
+            let before = g.reverse_topological_sort();
+
+            let err = g.exclude_transitive_deps(&[ident.parse().unwrap()])
+                       .unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::ExcludedDepRequired { required_by: r, .. }) => {
+                    assert_eq!(&required_by.to_string(), r)
+                }
+                e => panic!("Expected ExcludedDepRequired, got {:?}", e),
+            }
+            assert_eq!(before, g.reverse_topological_sort());
+        }
+    }
+
+    #[test]
+    fn top_level_packages_cannot_be_excluded() {
+        let mut g = test_graph_with_build_tool();
+
+        let err = g.exclude_transitive_deps(&[redis()]).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::ExcludedDepRequired { ident, .. }) => {
+                assert_eq!(&redis().to_string(), ident)
+            }
+            e => panic!("Expected ExcludedDepRequired, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn excluding_a_package_that_is_not_installed_fails() {
+        let mut g = test_graph().unwrap();
+
+        let err = g.exclude_transitive_deps(&["core/perl".parse().unwrap()])
+                   .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::ExcludedDepNotFound(ident)) => assert_eq!("core/perl", ident),
+            e => panic!("Expected ExcludedDepNotFound, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn lock_file_round_trips_to_the_same_graph() {
+        let g = test_graph().unwrap();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("redis.lock");
+
+        PackageLock::from_graph(&g).write(&path).unwrap();
+        let lock = PackageLock::from_file(&path).unwrap();
+
+        assert_eq!(g.reverse_topological_sort(), lock.idents());
+        assert_eq!(&redis(), lock.pin(&"core/redis".parse().unwrap()).unwrap());
+        assert_eq!(&sup(), lock.pin(&"core/hab-sup".parse().unwrap()).unwrap());
+        assert!(lock.verify(&g).is_ok());
+    }
+
+    #[test]
+    fn graph_which_differs_from_the_lock_file_fails_verification() {
+        let lock = PackageLock::from_graph(&test_graph().unwrap());
+
+        let err = lock.verify(&test_graph_with_build_tool()).unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::LockFileMismatch(differences)) => {
+                assert_eq!(3, differences.len());
+                assert!(differences.contains(&format!("{} (not in the lock file)", perl())));
+            }
+            e => panic!("Expected LockFileMismatch, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn closure_manifest_lists_all_transitive_deps_with_checksums() {
+        let g = test_graph().unwrap();
+        let target = PackageTarget::from_str("x86_64-linux").unwrap();
+        let cache = TempDir::new().unwrap();
+        for ident in g.reverse_topological_sort() {
+            fs::write(cache.path()
+                           .join(ident.archive_name_with_target(target).unwrap()),
+                      ident.to_string()).unwrap();
+        }
+
+        let manifest = ClosureManifest::from_graph(&g, cache.path(), target).unwrap();
+
+        let idents: Vec<_> = manifest.entries()
+                                     .iter()
+                                     .map(|entry| entry.ident.clone())
+                                     .collect();
+        assert_eq!(g.reverse_topological_sort(), idents);
+        assert!(idents.contains(&openssl_fips()));
+        for entry in manifest.entries() {
+            let artifact = cache.path()
+                                .join(entry.ident.archive_name_with_target(target).unwrap());
+            assert_eq!(hash::hash_file(&artifact).unwrap(), entry.checksum);
+            assert_eq!(entry.ident.to_string().len() as u64, entry.size);
+        }
+
+        let path = cache.path().join("closure.json");
+        manifest.write(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!("x86_64-linux", json["target"]);
+        assert_eq!(idents.len(), json["packages"].as_array().unwrap().len());
+        assert_eq!(redis().to_string(),
+                   json["packages"][idents.len() - 1]["ident"]);
+    }
+
+    #[test]
+    fn closure_manifest_requires_every_artifact_to_be_cached() {
+        let g = test_graph().unwrap();
+        let cache = TempDir::new().unwrap();
+
+        let err = ClosureManifest::from_graph(&g,
+                                              cache.path(),
+                                              PackageTarget::from_str("x86_64-linux").unwrap())
+                  .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::ArtifactNotCached(ident)) => assert_eq!(&busybox().to_string(), ident),
+            e => panic!("Expected ArtifactNotCached, got {:?}", e),
+        }
+    }
 }
@@ -0,0 +1,72 @@
+//! Types for representing entries in the `/etc/hosts` file that may be
+//! added to the root file system of a Docker export of a Habitat package.
+
+use crate::error::Error;
+use std::{fmt,
+          net::IpAddr,
+          result,
+          str::FromStr};
+
+/// Represents an entry for a host in `/etc/hosts`
+#[derive(Clone, Debug, PartialEq)]
+pub struct EtcHostsEntry {
+    pub name: String,
+    pub ip:   IpAddr,
+}
+
+impl FromStr for EtcHostsEntry {
+    type Err = Error;
+
+    /// Parses a `NAME:IP` pair, in the same form accepted by `docker run --add-host`.
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        // Split on the first colon only, as IPv6 addresses contain colons themselves
+        let mut parts = value.splitn(2, ':');
+        let name = parts.next().unwrap_or_default();
+        let ip = parts.next().unwrap_or_default();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(Error::InvalidHostEntry(value.to_string()));
+        }
+        match ip.parse() {
+            Ok(ip) => {
+                Ok(EtcHostsEntry { name: name.to_string(),
+                                   ip })
+            }
+            Err(_) => Err(Error::InvalidHostEntry(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for EtcHostsEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}", self.ip, self.name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn etc_hosts_entry_renders_correctly() {
+        let entry: EtcHostsEntry = "db.example.com:10.0.0.5".parse().unwrap();
+        let rendered = format!("{}", entry);
+
+        assert_eq!(rendered, "10.0.0.5\tdb.example.com");
+    }
+
+    #[test]
+    fn etc_hosts_entry_parses_ipv6_address() {
+        let entry: EtcHostsEntry = "db:fe80::1".parse().unwrap();
+
+        assert_eq!(entry.name, "db");
+        assert_eq!(entry.ip, "fe80::1".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn invalid_etc_hosts_entries_are_rejected() {
+        assert!("db.example.com".parse::<EtcHostsEntry>().is_err());
+        assert!("db.example.com:not-an-ip".parse::<EtcHostsEntry>().is_err());
+        assert!(":10.0.0.5".parse::<EtcHostsEntry>().is_err());
+        assert!("db example:10.0.0.5".parse::<EtcHostsEntry>().is_err());
+    }
+}
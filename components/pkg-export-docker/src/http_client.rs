@@ -0,0 +1,218 @@
+//! The HTTP client for calls to registry APIs, such as requesting an ECR authorization token,
+//! which applies timeouts so that an unresponsive endpoint cannot stall an export indefinitely.
+
+use crate::error::{Error,
+                   Result};
+use habitat_http_client::ca_bundle_certificates;
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use native_tls::{Certificate,
+                 Identity,
+                 TlsConnector};
+use rusoto_core::request::HttpClient;
+use std::{fs,
+          future::Future,
+          path::{Path,
+                 PathBuf},
+          time::Duration};
+use tokio::time;
+
+/// The default timeout, in seconds, for connecting to a registry and awaiting its response.
+pub const DEFAULT_REGISTRY_TIMEOUT_SECS: u64 = 30;
+
+/// Builds the HTTP client shared by registry API calls, and applies its timeouts to them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistryClientBuilder {
+    connect_timeout: Duration,
+    read_timeout:    Duration,
+    ca_bundle:       Option<PathBuf>,
+    client_cert:     Option<(PathBuf, PathBuf)>,
+}
+
+impl Default for RegistryClientBuilder {
+    fn default() -> Self { Self::new(Duration::from_secs(DEFAULT_REGISTRY_TIMEOUT_SECS)) }
+}
+
+impl RegistryClientBuilder {
+    /// Creates a builder which uses the given timeout both for connecting to a registry and for
+    /// awaiting each response.
+    pub fn new(timeout: Duration) -> Self {
+        RegistryClientBuilder { connect_timeout: timeout,
+                                read_timeout:    timeout,
+                                ca_bundle:       None,
+                                client_cert:     None, }
+    }
+
+    /// Trusts the certificates in the given CA bundle file, such as the certificate of a
+    /// TLS-intercepting proxy, in addition to the system's.
+    pub fn ca_bundle<P: Into<PathBuf>>(self, path: P) -> Self {
+        RegistryClientBuilder { ca_bundle: Some(path.into()),
+                                ..self }
+    }
+
+    /// Presents the certificate in the given PEM file, along with its PEM-encoded PKCS #8
+    /// private key, to registry APIs which require mutual TLS.
+    pub fn client_cert<C, K>(self, cert: C, key: K) -> Self
+        where C: Into<PathBuf>,
+              K: Into<PathBuf>
+    {
+        RegistryClientBuilder { client_cert: Some((cert.into(), key.into())),
+                                ..self }
+    }
+
+    /// Returns the timeout for establishing a connection to a registry.
+    pub fn connect_timeout(&self) -> Duration { self.connect_timeout }
+
+    /// Returns the timeout for awaiting the response to a registry API call.
+    pub fn read_timeout(&self) -> Duration { self.read_timeout }
+
+    /// Returns the CA bundle file whose certificates are trusted, if any.
+    pub fn ca_bundle_path(&self) -> Option<&Path> { self.ca_bundle.as_deref() }
+
+    /// Returns the certificates trusted in addition to the system's.
+    ///
+    /// # Errors
+    ///
+    /// * If the CA bundle file cannot be read or holds an invalid certificate
+    pub fn root_certificates(&self) -> Result<Vec<Certificate>> {
+        match self.ca_bundle {
+            Some(ref path) => Ok(ca_bundle_certificates(path)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the identity presented to registries which require mutual TLS, if any.
+    ///
+    /// # Errors
+    ///
+    /// * If the client certificate or key file cannot be read, or they do not form an identity
+    pub fn client_identity(&self) -> Result<Option<Identity>> {
+        let (cert, key) = match self.client_cert {
+            Some((ref cert, ref key)) => (cert, key),
+            None => return Ok(None),
+        };
+        let invalid = |reason: String| {
+            Error::InvalidRegistryClientCert { path: cert.display().to_string(),
+                                               reason }
+        };
+        let cert_pem = fs::read(cert).map_err(|e| invalid(e.to_string()))?;
+        let key_pem =
+            fs::read(key).map_err(|e| {
+                             invalid(format!("cannot read key '{}': {}", key.display(), e))
+                         })?;
+        let identity =
+            Identity::from_pkcs8(&cert_pem, &key_pem).map_err(|e| invalid(e.to_string()))?;
+        Ok(Some(identity))
+    }
+
+    /// Builds an HTTP client for rusoto service clients, which gives up on connections that are
+    /// not established within the connect timeout.
+    ///
+    /// # Errors
+    ///
+    /// * If the CA bundle file cannot be read or holds an invalid certificate
+    /// * If the client certificate or key is invalid
+    /// * If the TLS configuration cannot be created
+    pub fn build(&self) -> Result<HttpClient<HttpsConnector<HttpConnector>>> {
+        let mut http = HttpConnector::new();
+        http.set_connect_timeout(Some(self.connect_timeout));
+        http.enforce_http(false);
+        let mut tls = TlsConnector::builder();
+        for certificate in self.root_certificates()? {
+            tls.add_root_certificate(certificate);
+        }
+        if let Some(identity) = self.client_identity()? {
+            tls.identity(identity);
+        }
+        let https = HttpsConnector::from((http, tokio_tls::TlsConnector::from(tls.build()?)));
+        Ok(HttpClient::from_connector(https))
+    }
+
+    /// Awaits a registry API call, failing if it does not complete within the read timeout.
+    ///
+    /// # Errors
+    ///
+    /// * If the call does not complete within the read timeout
+    pub async fn send<F: Future>(&self, call: F) -> Result<F::Output> {
+        let secs = self.read_timeout.as_secs();
+        Ok(time::timeout(self.read_timeout, call).await
+                                                 .map_err(|_| Error::RegistryTimeout(secs))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timeout_applies_to_connecting_and_reading() {
+        let builder = RegistryClientBuilder::new(Duration::from_secs(5));
+        assert_eq!(Duration::from_secs(5), builder.connect_timeout());
+        assert_eq!(Duration::from_secs(5), builder.read_timeout());
+
+        let builder = RegistryClientBuilder::default();
+        assert_eq!(Duration::from_secs(DEFAULT_REGISTRY_TIMEOUT_SECS),
+                   builder.connect_timeout());
+        assert_eq!(Duration::from_secs(DEFAULT_REGISTRY_TIMEOUT_SECS),
+                   builder.read_timeout());
+    }
+
+    #[test]
+    fn ca_bundle_certificates_are_trusted() {
+        let ca_bundle = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ca-bundle.pem");
+        let builder = RegistryClientBuilder::default().ca_bundle(&ca_bundle);
+
+        assert_eq!(Some(ca_bundle.as_path()), builder.ca_bundle_path());
+        assert_eq!(2, builder.root_certificates().unwrap().len());
+        assert!(builder.build().is_ok());
+
+        assert!(RegistryClientBuilder::default().root_certificates()
+                                                .unwrap()
+                                                .is_empty());
+    }
+
+    #[test]
+    fn missing_ca_bundle_is_an_error() {
+        let builder = RegistryClientBuilder::default().ca_bundle("/no/such/ca-bundle.pem");
+        assert!(builder.root_certificates().is_err());
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn client_cert_is_loaded_as_the_tls_identity() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let cert = fixtures.join("client-cert.pem");
+        let builder =
+            RegistryClientBuilder::default().client_cert(&cert, fixtures.join("client-key.pem"));
+
+        assert!(builder.client_identity().unwrap().is_some());
+        assert!(builder.build().is_ok());
+        assert!(RegistryClientBuilder::default().client_identity()
+                                                .unwrap()
+                                                .is_none());
+
+        let builder =
+            RegistryClientBuilder::default().client_cert(&cert, fixtures.join("ca-bundle.pem"));
+        match builder.client_identity()
+                     .unwrap_err()
+                     .downcast_ref::<Error>()
+        {
+            Some(Error::InvalidRegistryClientCert { .. }) => {}
+            e => panic!("Expected InvalidRegistryClientCert, got {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_which_do_not_complete_in_time_fail() {
+        let builder = RegistryClientBuilder::new(Duration::from_millis(10));
+        let err = builder.send(time::delay_for(Duration::from_secs(60)))
+                         .await
+                         .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::RegistryTimeout(_)) => {}
+            e => panic!("Expected RegistryTimeout, got {:?}", e),
+        }
+        assert_eq!(42, builder.send(async { 42 }).await.unwrap());
+    }
+}
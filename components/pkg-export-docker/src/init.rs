@@ -0,0 +1,102 @@
+//! Types for representing the init program which runs as PID 1 in an image, forwarding signals to
+//! the entrypoint and reaping zombie processes.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// The init program which wraps the image's entrypoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitSystem {
+    /// The entrypoint runs as PID 1 itself.
+    None,
+    Tini,
+    DumbInit,
+}
+
+impl Default for InitSystem {
+    fn default() -> Self { InitSystem::None }
+}
+
+impl InitSystem {
+    pub fn variants() -> &'static [&'static str] { &["none", "tini", "dumb-init"] }
+
+    /// Returns the package which provides the init program by default, if any.
+    pub fn default_pkg(self) -> Option<&'static str> {
+        match self {
+            InitSystem::None => None,
+            InitSystem::Tini => Some("core/tini"),
+            InitSystem::DumbInit => Some("core/dumb-init"),
+        }
+    }
+
+    /// Returns the name of the init program, which is linked into `/bin`, if any.
+    pub fn program(self) -> Option<&'static str> {
+        match self {
+            InitSystem::None => None,
+            InitSystem::Tini => Some("tini"),
+            InitSystem::DumbInit => Some("dumb-init"),
+        }
+    }
+
+    /// Returns the command which precedes the image's entrypoint to run it under the init
+    /// program, which is empty if there is none.
+    pub fn entrypoint(self) -> &'static [&'static str] {
+        match self {
+            InitSystem::None => &[],
+            InitSystem::Tini => &["/bin/tini", "--"],
+            InitSystem::DumbInit => &["/bin/dumb-init", "--"],
+        }
+    }
+}
+
+impl FromStr for InitSystem {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(InitSystem::None),
+            "tini" => Ok(InitSystem::Tini),
+            "dumb-init" => Ok(InitSystem::DumbInit),
+            _ => Err(Error::InvalidInitSystem(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for InitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            InitSystem::None => "none",
+            InitSystem::Tini => "tini",
+            InitSystem::DumbInit => "dumb-init",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn init_system_parses_every_variant() {
+        for value in InitSystem::variants() {
+            let init: InitSystem = value.parse().unwrap();
+            assert_eq!(*value, init.to_string());
+        }
+        assert_eq!(InitSystem::None, InitSystem::default());
+        assert!(InitSystem::None.entrypoint().is_empty());
+        assert_eq!(&["/bin/tini", "--"], InitSystem::Tini.entrypoint());
+    }
+
+    #[test]
+    fn init_system_rejects_unknown_programs() {
+        for value in &["", "Tini", "dumb_init", "systemd"] {
+            match value.parse::<InitSystem>() {
+                Err(Error::InvalidInitSystem(v)) => assert_eq!(&v, value),
+                r => panic!("Expected InvalidInitSystem for {}, got {:?}", value, r),
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Interruption of an export by a signal, such as from Ctrl-C, so that the export is cancelled and
+//! its build root cleaned up rather than left behind.
+
+use crate::error::{Error,
+                   Result};
+#[cfg(windows)]
+use std::process;
+use std::{future::Future,
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 Arc}};
+#[cfg(windows)]
+use tokio::signal;
+#[cfg(unix)]
+use tokio::signal::unix::{signal,
+                          SignalKind};
+use tokio::sync::watch;
+
+/// The exit code of a Windows process terminated by Ctrl-C.
+#[cfg(windows)]
+const STATUS_CONTROL_C_EXIT: i32 = 0xC000_013Au32 as i32;
+
+/// Notifies an export that the process was interrupted, with the name of the signal. Clones are
+/// notified of the same interruption.
+///
+/// The default `Interrupt` is never notified.
+#[derive(Clone, Debug)]
+pub struct Interrupt {
+    received: watch::Receiver<Option<&'static str>>,
+    /// Whether the signals are still handled, rather than terminating the process as they would
+    /// by default. Only an `Interrupt` which listens for signals has this.
+    handling: Option<Arc<AtomicBool>>,
+}
+
+impl Default for Interrupt {
+    fn default() -> Self {
+        let (_, rx) = watch::channel(None);
+        Interrupt { received: rx,
+                    handling: None, }
+    }
+}
+
+impl Interrupt {
+    /// Handles SIGINT and SIGTERM, rather than letting them terminate the process, and notifies
+    /// of the first one received. The signals are handled until `restore_default` is called.
+    ///
+    /// # Errors
+    ///
+    /// * If the signal handlers cannot be installed
+    #[cfg(unix)]
+    pub fn listen() -> Result<Self> {
+        let mut sigint = signal(SignalKind::interrupt())?;
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let mut interrupt = Self::on(async move {
+            tokio::select! {
+                _ = sigint.recv() => "SIGINT",
+                _ = sigterm.recv() => "SIGTERM",
+            }
+        });
+        interrupt.handling = Some(Arc::new(AtomicBool::new(true)));
+        Ok(interrupt)
+    }
+
+    /// Handles Ctrl-C, rather than letting it terminate the process, and notifies of it once
+    /// received. Ctrl-C is handled until `restore_default` is called.
+    ///
+    /// # Errors
+    ///
+    /// * If the Ctrl-C handler cannot be installed
+    #[cfg(windows)]
+    pub fn listen() -> Result<Self> {
+        let (tx, rx) = watch::channel(None);
+        let handling = Arc::new(AtomicBool::new(true));
+        let still_handling = Arc::clone(&handling);
+        tokio::spawn(async move {
+            // The handler is only installed when first polled, so a failure to
+            // install it can't be reported from here.
+            while signal::ctrl_c().await.is_ok() {
+                // The handler can't be removed, so terminate as Ctrl-C would without it.
+                if !still_handling.load(Ordering::SeqCst) {
+                    process::exit(STATUS_CONTROL_C_EXIT);
+                }
+                let _ = tx.broadcast(Some("Ctrl-C"));
+            }
+        });
+        Ok(Interrupt { received: rx,
+                       handling: Some(handling), })
+    }
+
+    /// Notifies of the signal named by the given future once the future completes.
+    pub fn on<F>(signal: F) -> Self
+        where F: Future<Output = &'static str> + Send + 'static
+    {
+        let (tx, rx) = watch::channel(None);
+        tokio::spawn(async move {
+            // Nothing is left to notify once every receiver is gone.
+            let _ = tx.broadcast(Some(signal.await));
+        });
+        Interrupt { received: rx,
+                    handling: None, }
+    }
+
+    /// Stops handling the signals this `Interrupt` listens for, so that they terminate the
+    /// process as they would by default. This is called once the export can no longer be
+    /// cancelled, so that later signals aren't silently ignored. An `Interrupt` which doesn't
+    /// listen for signals is unaffected.
+    pub fn restore_default(&self) {
+        if let Some(ref handling) = self.handling {
+            handling.store(false, Ordering::SeqCst);
+            #[cfg(unix)]
+            unsafe {
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
+                libc::signal(libc::SIGTERM, libc::SIG_DFL);
+            }
+        }
+    }
+
+    /// Checks whether the process has been interrupted.
+    ///
+    /// # Errors
+    ///
+    /// * If the process has been interrupted
+    pub fn check(&self) -> Result<()> {
+        match *self.received.borrow() {
+            Some(signal) => Err(Error::Interrupted(signal.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs a future to completion unless the process is interrupted first, in which case the
+    /// future is cancelled by dropping it, along with any temporary directory it holds.
+    ///
+    /// # Errors
+    ///
+    /// * If the future fails
+    /// * If the process is interrupted before the future completes
+    pub async fn cancel_on_interrupt<F, T>(&self, future: F) -> Result<T>
+        where F: Future<Output = Result<T>>
+    {
+        let mut rx = self.received.clone();
+        tokio::select! {
+            result = future => result,
+            Some(signal) = received(&mut rx) => Err(Error::Interrupted(signal.to_string()).into()),
+        }
+    }
+}
+
+/// Waits for the name of the signal which interrupted the process, or returns `None` if the
+/// process can no longer be interrupted.
+async fn received(rx: &mut watch::Receiver<Option<&'static str>>) -> Option<&'static str> {
+    while let Some(signal) = rx.recv().await {
+        if signal.is_some() {
+            return signal;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::{task,
+                time};
+
+    /// An export which creates its build root and then never completes, leaving it to be
+    /// cancelled.
+    async fn never_completing_export(build_root: TempDir) -> Result<()> {
+        time::delay_for(Duration::from_secs(60)).await;
+        drop(build_root);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn interrupted_export_is_cancelled_and_cleaned_up() {
+        let build_root = TempDir::new().unwrap();
+        let path = build_root.path().to_path_buf();
+        let interrupt = Interrupt::on(async { "SIGINT" });
+
+        let err = interrupt.cancel_on_interrupt(never_completing_export(build_root))
+                           .await
+                           .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::Interrupted(signal)) => assert_eq!("SIGINT", signal),
+            e => panic!("Expected Interrupted, got {:?}", e),
+        }
+        assert!(!path.exists());
+        assert!(interrupt.check().is_err());
+    }
+
+    #[tokio::test]
+    async fn uninterrupted_export_completes() {
+        let interrupt = Interrupt::default();
+
+        assert_eq!(42,
+                   interrupt.cancel_on_interrupt(async { Ok(42) })
+                            .await
+                            .unwrap());
+        assert!(interrupt.check().is_ok());
+    }
+
+    #[tokio::test]
+    async fn interrupt_received_before_the_export_cancels_it_at_once() {
+        let interrupt = Interrupt::on(async { "SIGTERM" });
+        task::yield_now().await;
+        let build_root = TempDir::new().unwrap();
+        let path = build_root.path().to_path_buf();
+
+        let err = interrupt.cancel_on_interrupt(never_completing_export(build_root))
+                           .await
+                           .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::Interrupted(signal)) => assert_eq!("SIGTERM", signal),
+            e => panic!("Expected Interrupted, got {:?}", e),
+        }
+        assert!(!path.exists());
+    }
+}
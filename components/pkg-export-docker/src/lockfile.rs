@@ -0,0 +1,153 @@
+//! Lock files, which record the fully qualified identifiers of every package resolved into an
+//! image so that a later export installs exactly the same packages.
+
+use crate::{error::{Error,
+                    Result},
+            graph::Graph,
+            VERSION};
+use habitat_core::package::{Identifiable,
+                            PackageIdent};
+use std::{fs,
+          io::Write,
+          path::Path,
+          str::FromStr};
+
+/// The fully qualified identifiers of the packages in an image, direct and transitive, in the
+/// order they are added to it.
+#[derive(Debug, Default, PartialEq)]
+pub struct PackageLock {
+    idents: Vec<PackageIdent>,
+}
+
+impl PackageLock {
+    /// Creates a lock of the packages in a dependency graph.
+    pub fn from_graph(graph: &Graph) -> Self {
+        PackageLock { idents: graph.reverse_topological_sort(), }
+    }
+
+    /// Reads a lock file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read
+    /// * If an entry is not a fully qualified package identifier
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a lock file with one fully qualified package identifier per line. Blank lines and
+    /// lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * If an entry is not a fully qualified package identifier
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut idents = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match PackageIdent::from_str(line) {
+                Ok(ident) if ident.fully_qualified() => idents.push(ident),
+                _ => return Err(Error::InvalidLockFileEntry(line.to_string()).into()),
+            }
+        }
+        Ok(PackageLock { idents })
+    }
+
+    /// Writes the lock file, replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be written
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "# Packages resolved by {}", VERSION.trim())?;
+        for ident in &self.idents {
+            writeln!(file, "{}", ident)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the locked package identifiers.
+    pub fn idents(&self) -> &[PackageIdent] { &self.idents }
+
+    /// Returns the locked package which satisfies the given package identifier, which is
+    /// installed in its place.
+    ///
+    /// # Errors
+    ///
+    /// * If no locked package satisfies the identifier
+    pub fn pin(&self, ident: &PackageIdent) -> Result<&PackageIdent> {
+        self.idents
+            .iter()
+            .find(|locked| locked.satisfies(ident))
+            .ok_or_else(|| Error::LockedPackageNotFound(ident.to_string()).into())
+    }
+
+    /// Checks that the packages resolved into an image are exactly the locked packages.
+    ///
+    /// # Errors
+    ///
+    /// * If a package was resolved which is not locked, or a locked package was not resolved
+    pub fn verify(&self, graph: &Graph) -> Result<()> {
+        let resolved = graph.reverse_topological_sort();
+        let differences: Vec<_> =
+            resolved.iter()
+                    .filter(|ident| !self.idents.contains(ident))
+                    .map(|ident| format!("{} (not in the lock file)", ident))
+                    .chain(self.idents
+                               .iter()
+                               .filter(|ident| !resolved.contains(ident))
+                               .map(|ident| format!("{} (not resolved)", ident)))
+                    .collect();
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::LockFileMismatch(differences).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lock_file_skips_comments_and_blank_lines() {
+        let lock = PackageLock::parse("# Packages resolved by \
+                                       0.0.0\n\ncore/glibc/2.27/20190115002733\n  \
+                                       core/redis/4.0.14/20190319155852  \n").unwrap();
+        assert_eq!(vec![PackageIdent::from_str("core/glibc/2.27/20190115002733").unwrap(),
+                        PackageIdent::from_str("core/redis/4.0.14/20190319155852").unwrap()],
+                   lock.idents());
+    }
+
+    #[test]
+    fn lock_file_rejects_idents_which_are_not_fully_qualified() {
+        for entry in &["core/redis", "core/redis/4.0.14", "nope"] {
+            match PackageLock::parse(entry) {
+                Err(e) => {
+                    match e.downcast_ref::<Error>() {
+                        Some(Error::InvalidLockFileEntry(v)) => assert_eq!(v, entry),
+                        e => panic!("Expected InvalidLockFileEntry for {}, got {:?}", entry, e),
+                    }
+                }
+                Ok(lock) => panic!("Expected an error for {}, got {:?}", entry, lock),
+            }
+        }
+    }
+
+    #[test]
+    fn pin_requires_a_locked_package() {
+        let lock = PackageLock::parse("core/redis/4.0.14/20190319155852").unwrap();
+
+        let err = lock.pin(&PackageIdent::from_str("core/nginx").unwrap())
+                      .unwrap_err();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::LockedPackageNotFound(ident)) => assert_eq!("core/nginx", ident),
+            e => panic!("Expected LockedPackageNotFound, got {:?}", e),
+        }
+    }
+}
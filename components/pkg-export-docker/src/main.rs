@@ -5,22 +5,45 @@ use env_logger;
 use habitat_common::ui::{UIWriter,
                          UI};
 use habitat_pkg_export_docker::{cli,
+                                exit_code,
+                                expand_args,
                                 export_for_cli_matches,
+                                quiet_ui,
+                                Interrupt,
                                 Result};
+use std::{env,
+          io};
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    let mut ui = UI::default_with_env();
-    if let Err(e) = start(&mut ui).await {
+    let args = expand_args(env::args_os()).unwrap_or_else(|e| {
+                                              let code = exit_code(&e);
+                                              UI::default_with_env().fatal(e).unwrap();
+                                              std::process::exit(code)
+                                          });
+    let m = cli().get_matches_from(args);
+    debug!("clap cli args: {:?}", m);
+    let quiet = m.is_present("QUIET");
+    let mut ui = if quiet || m.is_present("REPORT_STDOUT") || m.is_present("LIST_TAGS") {
+        quiet_ui(|| Box::new(io::stderr()))
+    } else {
+        UI::default_with_env()
+    };
+    if let Err(e) = start(&mut ui, &m, quiet).await {
+        let code = exit_code(&e);
         ui.fatal(e).unwrap();
-        std::process::exit(1)
+        std::process::exit(code)
     }
 }
 
-async fn start(ui: &mut UI) -> Result<()> {
-    let cli = cli();
-    let m = cli.get_matches();
-    debug!("clap cli args: {:?}", m);
-    export_for_cli_matches(ui, &m).await.map(|_| ())
+async fn start(ui: &mut UI, m: &clap::ArgMatches<'_>, quiet: bool) -> Result<()> {
+    let interrupt = Interrupt::listen()?;
+    let image = export_for_cli_matches(ui, m, &interrupt).await?;
+    if quiet && !m.is_present("REPORT_STDOUT") {
+        if let Some(image) = image {
+            println!("{}", image.reference());
+        }
+    }
+    Ok(())
 }
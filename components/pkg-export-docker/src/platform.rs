@@ -0,0 +1,109 @@
+//! Types for representing the OCI platform, an operating system and CPU architecture, declared by
+//! the image built from a Habitat package for a given package target.
+
+use crate::error::{Error,
+                   Result};
+use habitat_core::package::{target,
+                            PackageTarget};
+use std::fmt;
+
+/// The OCI operating system and architecture of the images built for each supported package
+/// target.
+const PLATFORMS: &[(PackageTarget, &str, &str)] =
+    &[(target::X86_64_LINUX, "linux", "amd64"),
+      (target::X86_64_LINUX_KERNEL2, "linux", "amd64"),
+      (target::X86_64_WINDOWS, "windows", "amd64"),
+      (target::AARCH64_LINUX, "linux", "arm64")];
+
+/// The OCI platform of an image, as set by `docker build --platform`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Platform {
+    target:       PackageTarget,
+    os:           &'static str,
+    architecture: &'static str,
+}
+
+impl Platform {
+    /// Returns the platform of the images built for a package target.
+    ///
+    /// # Errors
+    ///
+    /// * If images cannot be built for the package target, such as `x86_64-darwin`
+    pub fn for_target(target: PackageTarget) -> Result<Self> {
+        PLATFORMS.iter()
+                 .find(|(t, ..)| *t == target)
+                 .map(|&(target, os, architecture)| {
+                     Platform { target,
+                                os,
+                                architecture }
+                 })
+                 .ok_or_else(|| Error::UnsupportedTarget(target.to_string()).into())
+    }
+
+    /// Returns the package target the platform was determined from.
+    pub fn target(&self) -> PackageTarget { self.target }
+
+    /// Returns the OCI operating system (ex: `linux`).
+    pub fn os(&self) -> &'static str { self.os }
+
+    /// Returns the OCI architecture (ex: `amd64`).
+    pub fn architecture(&self) -> &'static str { self.architecture }
+
+    /// Returns `true` if the platform has the operating system and architecture of this host,
+    /// which Docker builds images for by default.
+    pub fn is_host(&self) -> bool {
+        match Platform::for_target(PackageTarget::active_target()) {
+            Ok(host) => host.os == self.os && host.architecture == self.architecture,
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.os, self.architecture)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn platform(target: &str) -> Result<Platform> {
+        Platform::for_target(PackageTarget::from_str(target).unwrap())
+    }
+
+    #[test]
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    fn host_platform_is_recognized() {
+        assert!(platform("x86_64-linux").unwrap().is_host());
+        assert!(platform("x86_64-linux-kernel2").unwrap().is_host());
+        assert!(!platform("aarch64-linux").unwrap().is_host());
+        assert!(!platform("x86_64-windows").unwrap().is_host());
+    }
+
+    #[test]
+    fn package_targets_map_to_oci_os_and_architecture() {
+        for &(target, os, architecture) in &[("x86_64-linux", "linux", "amd64"),
+                                             ("x86_64-linux-kernel2", "linux", "amd64"),
+                                             ("x86_64-windows", "windows", "amd64"),
+                                             ("aarch64-linux", "linux", "arm64")]
+        {
+            let platform = platform(target).unwrap();
+            assert_eq!(target, platform.target().to_string());
+            assert_eq!(os, platform.os());
+            assert_eq!(architecture, platform.architecture());
+            assert_eq!(format!("{}/{}", os, architecture), platform.to_string());
+        }
+    }
+
+    #[test]
+    fn darwin_packages_have_no_platform() {
+        let err = platform("x86_64-darwin").unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::UnsupportedTarget(target)) => assert_eq!("x86_64-darwin", target),
+            e => panic!("Expected UnsupportedTarget, got {:?}", e),
+        }
+    }
+}
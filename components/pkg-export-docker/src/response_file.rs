@@ -0,0 +1,130 @@
+//! Response files, which hold some of the command line arguments of an export so that long
+//! command lines can be kept in a file and given as `@PATH`.
+
+use crate::error::{Error,
+                   Result};
+use std::{ffi::OsString,
+          fs,
+          path::{Path,
+                 PathBuf}};
+
+/// The deepest nesting of response files which is expanded, so that a response file which
+/// includes itself fails rather than recursing forever.
+const MAX_DEPTH: usize = 8;
+
+/// Expands every argument of the form `@PATH` into the arguments read from the response file at
+/// `PATH`. The first argument, the program name, is never expanded.
+///
+/// Each line of a response file is split as a shell would, so arguments containing spaces can be
+/// quoted, and blank lines and lines starting with `#` are skipped. A response file may itself
+/// give `@PATH` arguments, whose relative paths are resolved from the directory of the response
+/// file.
+///
+/// # Errors
+///
+/// * If a response file cannot be read or a line cannot be split
+/// * If response files are nested too deeply, such as a response file which includes itself
+pub fn expand_args<I>(args: I) -> Result<Vec<OsString>>
+    where I: IntoIterator<Item = OsString>
+{
+    let mut args = args.into_iter();
+    let mut expanded: Vec<OsString> = args.next().into_iter().collect();
+    for arg in args {
+        expand_arg(arg, Path::new(""), 0, &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+/// Expands an argument into the given arguments, reading it as a response file, relative to the
+/// given directory, if it has the form `@PATH`.
+fn expand_arg(arg: OsString, dir: &Path, depth: usize, expanded: &mut Vec<OsString>) -> Result<()> {
+    let path = match arg.to_str() {
+        Some(arg) if arg.starts_with('@') && arg.len() > 1 => dir.join(&arg[1..]),
+        _ => {
+            expanded.push(arg);
+            return Ok(());
+        }
+    };
+    let invalid = |reason: String| {
+        Error::InvalidResponseFile { path: path.display().to_string(),
+                                     reason }
+    };
+    if depth >= MAX_DEPTH {
+        return Err(invalid(format!("response files are nested more than {} deep",
+                                   MAX_DEPTH)).into());
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| invalid(e.to_string()))?;
+    let parent = path.parent()
+                     .map(Path::to_path_buf)
+                     .unwrap_or_else(PathBuf::new);
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words = shlex::split(line).ok_or_else(|| invalid(format!("cannot split '{}'", line)))?;
+        for word in words {
+            expand_arg(word.into(), &parent, depth + 1, expanded)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn args(args: &[&str]) -> Vec<OsString> { args.iter().map(OsString::from).collect() }
+
+    #[test]
+    fn response_file_arguments_are_applied() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("tags.txt"),
+                  "# Tagging\n--no-tag-latest\n\n--tag-custom 'release candidate'\n").unwrap();
+        fs::write(dir.path().join("options.txt"), "--memory 2g\n@tags.txt\n").unwrap();
+        let options = format!("@{}", dir.path().join("options.txt").display());
+
+        let expanded =
+            expand_args(args(&["hab-pkg-export-docker", &options, "acme/redis"])).unwrap();
+
+        assert_eq!(args(&["hab-pkg-export-docker",
+                          "--memory",
+                          "2g",
+                          "--no-tag-latest",
+                          "--tag-custom",
+                          "release candidate",
+                          "acme/redis"]),
+                   expanded);
+        let matches = crate::cli().get_matches_from(expanded);
+        assert_eq!(Some("2g"), matches.value_of("MEMORY_LIMIT"));
+        assert!(matches.is_present("NO_TAG_LATEST"));
+        assert_eq!(Some("release candidate"), matches.value_of("TAG_CUSTOM"));
+    }
+
+    #[test]
+    fn arguments_which_are_not_response_files_are_unchanged() {
+        let argv = args(&["@hab-pkg-export-docker", "--tag-custom", "@", "acme/redis"]);
+
+        assert_eq!(argv, expand_args(argv.clone()).unwrap());
+    }
+
+    #[test]
+    fn missing_and_self_including_response_files_fail() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.txt");
+        let looping = dir.path().join("loop.txt");
+        fs::write(&looping, "@loop.txt\n").unwrap();
+
+        for path in &[missing, looping] {
+            let err = expand_args(args(&["hab-pkg-export-docker",
+                                         &format!("@{}", path.display())])).unwrap_err();
+
+            match err.downcast_ref::<Error>() {
+                Some(Error::InvalidResponseFile { path: p, .. }) => {
+                    assert_eq!(path, Path::new(p))
+                }
+                e => panic!("Expected InvalidResponseFile, got {:?}", e),
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use crate::{error::Result,
+            hosts::EtcHostsEntry,
             util::write_file};
-use std::{fs,
-          path::Path};
+use habitat_core::fs::pkg_root_path;
+use std::{fs::{self,
+               OpenOptions},
+          io::{self,
+               Write},
+          os::unix::fs::PermissionsExt,
+          path::{Path,
+                 PathBuf}};
 
 /// The default password file contents.
 const ETC_PASSWD: &str = include_str!("../defaults/etc/passwd");
@@ -11,6 +18,14 @@ const ETC_GROUP: &str = include_str!("../defaults/etc/group");
 const ETC_RESOLV_CONF: &str = include_str!("../defaults/etc/resolv.conf");
 /// The default `nsswitch.conf` contents.
 const ETC_NSSWITCH_CONF: &str = include_str!("../defaults/etc/nsswitch.conf");
+/// The default `hosts` file contents.
+const ETC_HOSTS: &str = include_str!("../defaults/etc/hosts");
+/// Metadata files of an installed package which only describe how it was built: its build
+/// dependencies and the environment of its build. Nothing reads them when a service runs.
+const PKG_BUILD_ARTIFACTS: &[&str] = &["BUILD_DEPS",
+                                       "BUILD_TDEPS",
+                                       "BUILDTIME_ENVIRONMENT",
+                                       "BUILDTIME_ENVIRONMENT_PROVENANCE"];
 
 /// Creates a root file system under the given path.
 ///
@@ -41,6 +56,190 @@ pub fn create<T>(root: T) -> Result<()>
     Ok(())
 }
 
+/// Replaces the default `/etc/resolv.conf` under the given root file system path with the
+/// contents of the provided file.
+///
+/// # Errors
+///
+/// * If the source file cannot be read or the destination file cannot be written
+pub fn set_resolv_conf<T, P>(root: T, src: P) -> Result<()>
+    where T: AsRef<Path>,
+          P: AsRef<Path>
+{
+    fs::copy(src, root.as_ref().join("etc/resolv.conf"))?;
+    Ok(())
+}
+
+/// Writes an `/etc/hosts` file under the given root file system path containing the default
+/// loopback entries, followed by the provided host entries.
+///
+/// # Errors
+///
+/// * If the hosts file cannot be created or written
+pub fn add_hosts<T>(root: T, hosts: &[EtcHostsEntry]) -> Result<()>
+    where T: AsRef<Path>
+{
+    let file = root.as_ref().join("etc/hosts");
+    write_file(&file, ETC_HOSTS)?;
+    let mut f = OpenOptions::new().append(true).open(&file)?;
+    for host in hosts {
+        writeln!(f, "{}", host)?;
+    }
+    Ok(())
+}
+
+/// Creates the given absolute working directory, and any missing parents, under the given root
+/// file system path.
+///
+/// # Errors
+///
+/// * If the directory cannot be created
+pub fn create_workdir<T, P>(root: T, workdir: P) -> Result<()>
+    where T: AsRef<Path>,
+          P: AsRef<Path>
+{
+    let workdir = workdir.as_ref();
+    fs::create_dir_all(root.as_ref()
+                           .join(workdir.strip_prefix("/").unwrap_or(workdir)))?;
+    Ok(())
+}
+
+/// Prepares the root file system for an image without a shell, where the Dockerfile can't run
+/// commands to do so. The directories the Supervisor needs at runtime are created, and every
+/// directory under `/hab` is given the same group permissions as its owner, so that the
+/// Supervisor can run as a non-root user in the root group.
+///
+/// # Errors
+///
+/// * If a directory cannot be created or read
+/// * If the permissions of a directory cannot be read or set
+pub fn create_runtime_dirs<T>(root: T) -> Result<()>
+    where T: AsRef<Path>
+{
+    let root = root.as_ref();
+    for (dir, mode) in &[("root", 0o750), ("tmp", 0o1777), ("var/tmp", 0o1777)] {
+        let path = root.join(dir);
+        fs::create_dir_all(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(*mode))?;
+    }
+    let hab = root.join("hab");
+    if hab.is_dir() {
+        share_owner_perms_with_group(&hab)?;
+    }
+    Ok(())
+}
+
+fn share_owner_perms_with_group(dir: &Path) -> Result<()> {
+    let mut perms = fs::metadata(dir)?.permissions();
+    let mode = perms.mode();
+    perms.set_mode((mode & !0o070) | ((mode & 0o700) >> 3));
+    fs::set_permissions(dir, perms)?;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if fs::symlink_metadata(&path)?.is_dir() {
+            share_owner_perms_with_group(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates an empty file at the given absolute path under the given root file system path, which
+/// only its owner can read and write, for a secret to be mounted onto at runtime. An existing
+/// file is emptied.
+///
+/// # Errors
+///
+/// * If the file or its parent directories cannot be created
+/// * If the permissions of the file cannot be set
+pub fn create_secret_mount<T, P>(root: T, path: P) -> Result<()>
+    where T: AsRef<Path>,
+          P: AsRef<Path>
+{
+    let path = path.as_ref();
+    let file = root.as_ref().join(path.strip_prefix("/").unwrap_or(path));
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_file(&file, "")?;
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+/// Clears the setuid and setgid bits from all files under the given root file system path,
+/// returning the paths of every file which was modified. Symbolic links are not followed.
+///
+/// # Errors
+///
+/// * If a directory cannot be read
+/// * If the permissions of a file cannot be read or set
+pub fn strip_setuid<T>(root: T) -> Result<Vec<PathBuf>>
+    where T: AsRef<Path>
+{
+    let mut modified = Vec::new();
+    strip_setuid_in_dir(root.as_ref(), &mut modified)?;
+    Ok(modified)
+}
+
+fn strip_setuid_in_dir(dir: &Path, modified: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let metadata = fs::symlink_metadata(&path)?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        } else if metadata.is_dir() {
+            strip_setuid_in_dir(&path, modified)?;
+        } else {
+            let mut perms = metadata.permissions();
+            let mode = perms.mode();
+            if mode & 0o6000 != 0 {
+                perms.set_mode(mode & !0o6000);
+                fs::set_permissions(&path, perms)?;
+                modified.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the build-only metadata files of every package installed under the given root file
+/// system path, returning the paths which were removed. Packages of any target are included, as
+/// an image may be exported for a target other than the host's.
+///
+/// # Errors
+///
+/// * If the installed packages cannot be listed
+/// * If a file cannot be removed
+pub fn strip_build_artifacts<T>(root: T) -> Result<Vec<PathBuf>>
+    where T: AsRef<Path>
+{
+    // Installed packages live in `origin/name/version/release` directories
+    let mut pkg_paths = vec![pkg_root_path(Some(root.as_ref()))];
+    for _ in 0..4 {
+        let mut children = Vec::new();
+        for dir in pkg_paths.iter().filter(|p| p.is_dir()) {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if fs::symlink_metadata(&path)?.is_dir() {
+                    children.push(path);
+                }
+            }
+        }
+        pkg_paths = children;
+    }
+    let mut removed = Vec::new();
+    for pkg_path in pkg_paths {
+        for file in PKG_BUILD_ARTIFACTS {
+            let path = pkg_path.join(file);
+            match fs::remove_file(&path) {
+                Ok(()) => removed.push(path),
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    Ok(removed)
+}
+
 #[cfg(unix)]
 #[cfg(test)]
 mod test {
@@ -80,4 +279,158 @@ mod test {
         assert!(etc_nsswitch_conf.is_file());
         assert_eq!(ETC_NSSWITCH_CONF, file_content(etc_nsswitch_conf));
     }
+
+    #[test]
+    fn adds_host_entries() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        let hosts = vec!["db.example.com:10.0.0.5".parse().unwrap(),
+                         "cache:10.0.0.6".parse().unwrap()];
+        add_hosts(&root, &hosts).unwrap();
+
+        let etc_hosts = file_content(root.path().join("etc").join("hosts"));
+        assert!(etc_hosts.starts_with(ETC_HOSTS));
+        assert!(etc_hosts.contains("10.0.0.5\tdb.example.com\n"));
+        assert!(etc_hosts.contains("10.0.0.6\tcache\n"));
+    }
+
+    #[test]
+    fn creates_workdir() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        create_workdir(&root, "/hab/svc/redis").unwrap();
+
+        assert!(root.path().join("hab").join("svc").join("redis").is_dir());
+    }
+
+    #[test]
+    fn creates_secret_mounts() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        let existing = root.path().join("etc/passwd");
+        create_secret_mount(&root, "/run/secrets/db-password").unwrap();
+        create_secret_mount(&root, "/etc/passwd").unwrap();
+
+        for file in &[root.path().join("run/secrets/db-password"), existing] {
+            let metadata = fs::metadata(file).unwrap();
+            assert!(metadata.is_file(), "{}", file.display());
+            assert_eq!(0, metadata.len(), "{}", file.display());
+            assert_eq!(0o0600, metadata.permissions().mode() & 0o7777);
+        }
+    }
+
+    #[test]
+    fn strips_setuid_and_setgid_bits() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        let setuid = root.path().join("bin/setuid");
+        let plain = root.path().join("bin/plain");
+        write_file(&setuid, "").unwrap();
+        write_file(&plain, "").unwrap();
+        fs::set_permissions(&setuid, fs::Permissions::from_mode(0o6755)).unwrap();
+        fs::set_permissions(&plain, fs::Permissions::from_mode(0o0755)).unwrap();
+
+        let modified = strip_setuid(&root).unwrap();
+
+        assert_eq!(vec![setuid.clone()], modified);
+        assert_eq!(0o0755,
+                   fs::metadata(&setuid).unwrap().permissions().mode() & 0o7777);
+        assert_eq!(0o0755,
+                   fs::metadata(&plain).unwrap().permissions().mode() & 0o7777);
+    }
+
+    #[test]
+    fn strips_build_artifacts() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        assert!(strip_build_artifacts(&root).unwrap().is_empty());
+
+        // The metadata files of an installed package, as written by a package build
+        let redis = root.path()
+                        .join("hab/pkgs/core/redis/4.0.14/20190319155852");
+        write_file(redis.join("TARGET"), "aarch64-linux").unwrap();
+        for file in &["BUILD_DEPS",
+                      "BUILD_TDEPS",
+                      "BUILDTIME_ENVIRONMENT",
+                      "BUILDTIME_ENVIRONMENT_PROVENANCE",
+                      "DEPS",
+                      "TDEPS",
+                      "IDENT",
+                      "MANIFEST",
+                      "PATH",
+                      "RUNTIME_ENVIRONMENT",
+                      "SVC_USER",
+                      "default.toml",
+                      "hooks/run",
+                      "bin/redis-server"]
+        {
+            write_file(redis.join(file), "").unwrap();
+        }
+        // A package built without build dependencies has only some of them
+        let glibc = root.path().join("hab/pkgs/core/glibc/2.29/20190115012042");
+        write_file(glibc.join("BUILDTIME_ENVIRONMENT"), "").unwrap();
+        write_file(glibc.join("IDENT"), "").unwrap();
+
+        let mut removed = strip_build_artifacts(&root).unwrap();
+        removed.sort();
+
+        let mut expected = PKG_BUILD_ARTIFACTS.iter()
+                                              .map(|f| redis.join(f))
+                                              .collect::<Vec<_>>();
+        expected.push(glibc.join("BUILDTIME_ENVIRONMENT"));
+        expected.sort();
+        assert_eq!(expected, removed);
+        for path in &expected {
+            assert!(!path.exists(), "{}", path.display());
+        }
+        for file in &["DEPS",
+                      "TDEPS",
+                      "IDENT",
+                      "MANIFEST",
+                      "PATH",
+                      "RUNTIME_ENVIRONMENT",
+                      "SVC_USER",
+                      "TARGET",
+                      "default.toml",
+                      "hooks/run",
+                      "bin/redis-server"]
+        {
+            assert!(redis.join(file).is_file(), "{}", file);
+        }
+        assert!(glibc.join("IDENT").is_file());
+        assert!(strip_build_artifacts(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn creates_runtime_dirs() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        let svc = root.path().join("hab/svc/redis");
+        fs::create_dir_all(&svc).unwrap();
+        fs::set_permissions(&svc, fs::Permissions::from_mode(0o0750)).unwrap();
+
+        create_runtime_dirs(&root).unwrap();
+
+        let mode = |dir: &str| {
+            fs::metadata(root.path().join(dir)).unwrap()
+                                               .permissions()
+                                               .mode()
+        };
+        assert_eq!(0o0750, mode("root") & 0o7777);
+        assert_eq!(0o1777, mode("tmp") & 0o7777);
+        assert_eq!(0o1777, mode("var/tmp") & 0o7777);
+        assert_eq!(0o0770, mode("hab/svc/redis") & 0o7777);
+    }
+
+    #[test]
+    fn replaces_resolv_conf() {
+        let root = TempDir::new().unwrap();
+        create(&root).unwrap();
+        let src = root.path().join("custom-resolv.conf");
+        write_file(&src, "nameserver 10.0.0.1\n").unwrap();
+        set_resolv_conf(&root, &src).unwrap();
+
+        assert_eq!("nameserver 10.0.0.1\n",
+                   file_content(root.path().join("etc").join("resolv.conf")));
+    }
 }
@@ -0,0 +1,128 @@
+//! Types for representing sizes in bytes, in the format accepted by the container engine's
+//! `--memory` and `--memory-swap` options (ex: `500m` or `2gb`).
+
+use crate::error::Error;
+use std::{result,
+          str::FromStr};
+
+/// The unit suffixes, each 1024 times the last, starting with kibibytes.
+const UNITS: &[&str] = &["k", "m", "g", "t", "p"];
+
+/// A size in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    /// Returns the size in bytes.
+    pub fn bytes(self) -> u64 { self.0 }
+}
+
+impl FromStr for ByteSize {
+    type Err = Error;
+
+    /// Parses a number of bytes with an optional, case-insensitive unit suffix of `k`, `m`, `g`,
+    /// `t`, or `p`, which may be followed by `b` (ex: `1024`, `500m`, or `1.5GB`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let invalid = || Error::InvalidSize(value.to_string());
+        let lower = value.to_lowercase();
+        let unit_start = lower.find(|c: char| !c.is_ascii_digit() && c != '.')
+                              .unwrap_or_else(|| lower.len());
+        let (number, unit) = lower.split_at(unit_start);
+        if number.starts_with('.') || number.ends_with('.') {
+            return Err(invalid());
+        }
+        let number = number.parse::<f64>().map_err(|_| invalid())?;
+        let unit = if unit.len() == 2 && unit.ends_with('b') {
+            &unit[..unit.len() - 1]
+        } else {
+            unit
+        };
+        let exponent = match unit {
+            "" | "b" => 0,
+            _ => UNITS.iter().position(|u| *u == unit).ok_or_else(invalid)? + 1,
+        };
+        let bytes = number * 1024_f64.powi(exponent as i32);
+        if bytes > u64::max_value() as f64 {
+            return Err(invalid());
+        }
+        Ok(ByteSize(bytes as u64))
+    }
+}
+
+/// A limit on the memory plus swap a build may use, as passed to the container engine's
+/// `--memory-swap` option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwapLimit {
+    /// The build may use unlimited swap, given as `-1`.
+    Unlimited,
+    /// The build may use this much memory and swap combined.
+    Limited(ByteSize),
+}
+
+impl FromStr for SwapLimit {
+    type Err = Error;
+
+    /// Parses `-1`, for unlimited swap, or a size (ex: `4g`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value {
+            "-1" => Ok(SwapLimit::Unlimited),
+            _ => Ok(SwapLimit::Limited(value.parse()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn byte_size_parses_plain_numbers_as_bytes() {
+        assert_eq!("1024".parse::<ByteSize>().unwrap().bytes(), 1024);
+        assert_eq!("512b".parse::<ByteSize>().unwrap().bytes(), 512);
+        assert_eq!("0".parse::<ByteSize>().unwrap().bytes(), 0);
+    }
+
+    #[test]
+    fn byte_size_parses_units() {
+        assert_eq!("4k".parse::<ByteSize>().unwrap().bytes(), 4 * 1024);
+        assert_eq!("500m".parse::<ByteSize>().unwrap().bytes(),
+                   500 * 1024 * 1024);
+        assert_eq!("2gb".parse::<ByteSize>().unwrap().bytes(),
+                   2 * 1024 * 1024 * 1024);
+        assert_eq!("1.5G".parse::<ByteSize>().unwrap().bytes(),
+                   3 * 512 * 1024 * 1024);
+        assert_eq!("1TB".parse::<ByteSize>().unwrap().bytes(), 1 << 40);
+    }
+
+    #[test]
+    fn invalid_byte_sizes_are_rejected() {
+        assert!("".parse::<ByteSize>().is_err());
+        assert!("m".parse::<ByteSize>().is_err());
+        assert!("2bg".parse::<ByteSize>().is_err());
+        assert!("2 gb".parse::<ByteSize>().is_err());
+        assert!("2x".parse::<ByteSize>().is_err());
+        assert!(".5g".parse::<ByteSize>().is_err());
+        assert!("-1".parse::<ByteSize>().is_err());
+        assert!("1.2.3m".parse::<ByteSize>().is_err());
+        assert!("99999999p".parse::<ByteSize>().is_err());
+    }
+
+    #[test]
+    fn swap_limit_parses_sizes_and_unlimited() {
+        assert_eq!("4g".parse::<SwapLimit>().unwrap(),
+                   SwapLimit::Limited(ByteSize(4 * 1024 * 1024 * 1024)));
+        assert_eq!("1024".parse::<SwapLimit>().unwrap(),
+                   SwapLimit::Limited(ByteSize(1024)));
+        assert_eq!("-1".parse::<SwapLimit>().unwrap(), SwapLimit::Unlimited);
+    }
+
+    #[test]
+    fn invalid_swap_limits_are_rejected() {
+        for value in &["", "-2", "-1g", "unlimited", "2 gb"] {
+            match value.parse::<SwapLimit>() {
+                Err(Error::InvalidSize(v)) => assert_eq!(&v, value),
+                r => panic!("Expected InvalidSize for {}, got {:?}", value, r),
+            }
+        }
+    }
+}
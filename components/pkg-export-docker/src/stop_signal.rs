@@ -0,0 +1,90 @@
+//! Types for representing the signal sent to the container to stop the
+//! service in a Docker export of a Habitat package.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// The names of the Linux signals, without their `SIG` prefix.
+const SIGNAL_NAMES: &[&str] = &["HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "IOT", "BUS", "FPE",
+                                "KILL", "USR1", "SEGV", "USR2", "PIPE", "ALRM", "TERM", "STKFLT",
+                                "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU", "URG", "XCPU",
+                                "XFSZ", "VTALRM", "PROF", "WINCH", "IO", "POLL", "PWR", "SYS"];
+
+/// The highest signal number, including real-time signals, on Linux.
+const MAX_SIGNAL_NUMBER: u8 = 64;
+
+/// Represents the value of a `STOPSIGNAL` directive, which is either a signal name or number.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopSignal {
+    Name(String),
+    Number(u8),
+}
+
+impl FromStr for StopSignal {
+    type Err = Error;
+
+    /// Parses a signal name, with or without its `SIG` prefix (ex: `SIGTERM` or `TERM`), or a
+    /// signal number (ex: `15`).
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        if let Ok(number) = value.parse::<u8>() {
+            if number > 0 && number <= MAX_SIGNAL_NUMBER {
+                return Ok(StopSignal::Number(number));
+            }
+            return Err(Error::InvalidStopSignal(value.to_string()));
+        }
+        let name = value.to_uppercase();
+        let name = if name.starts_with("SIG") {
+            &name[3..]
+        } else {
+            &name
+        };
+        if SIGNAL_NAMES.contains(&name) {
+            Ok(StopSignal::Name(format!("SIG{}", name)))
+        } else {
+            Err(Error::InvalidStopSignal(value.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for StopSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StopSignal::Name(ref name) => write!(f, "{}", name),
+            StopSignal::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stop_signal_parses_names() {
+        assert_eq!("SIGTERM".parse::<StopSignal>().unwrap().to_string(),
+                   "SIGTERM");
+        assert_eq!("WINCH".parse::<StopSignal>().unwrap().to_string(),
+                   "SIGWINCH");
+        assert_eq!("sigquit".parse::<StopSignal>().unwrap().to_string(),
+                   "SIGQUIT");
+    }
+
+    #[test]
+    fn stop_signal_parses_numbers() {
+        assert_eq!("15".parse::<StopSignal>().unwrap(), StopSignal::Number(15));
+        assert_eq!("64".parse::<StopSignal>().unwrap().to_string(), "64");
+    }
+
+    #[test]
+    fn invalid_stop_signals_are_rejected() {
+        assert!("".parse::<StopSignal>().is_err());
+        assert!("SIG".parse::<StopSignal>().is_err());
+        assert!("SIGNOPE".parse::<StopSignal>().is_err());
+        assert!("SIGSIGTERM".parse::<StopSignal>().is_err());
+        assert!("0".parse::<StopSignal>().is_err());
+        assert!("65".parse::<StopSignal>().is_err());
+        assert!("-15".parse::<StopSignal>().is_err());
+    }
+}
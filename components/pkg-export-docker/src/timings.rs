@@ -0,0 +1,107 @@
+//! Timing of the consecutive phases of an export, for performance analysis.
+
+use std::time::{Duration,
+                Instant};
+
+/// The durations of consecutive phases, where each phase runs from the end of the previous phase
+/// (or the creation of the `Timings`) until it is finished.
+#[derive(Debug)]
+pub struct Timings {
+    last:   Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    /// Starts timing the first phase.
+    pub fn start() -> Self {
+        Timings { last:   Instant::now(),
+                  phases: Vec::new(), }
+    }
+
+    /// Finishes the current phase, logging its duration at debug level, and starts timing the
+    /// next phase.
+    pub fn finish(&mut self, phase: &'static str) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last);
+        debug!("Export phase '{}' took {:.3}s",
+               phase,
+               elapsed.as_secs_f64());
+        self.last = now;
+        self.phases.push((phase, elapsed));
+        elapsed
+    }
+
+    /// Returns the name and duration of every finished phase, in order.
+    pub fn phases(&self) -> &[(&'static str, Duration)] { &self.phases }
+
+    /// Returns the total duration of every finished phase.
+    pub fn total(&self) -> Duration { self.phases.iter().map(|(_, d)| *d).sum() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lazy_static::lazy_static;
+    use log::{Level,
+              LevelFilter,
+              Log,
+              Metadata,
+              Record};
+    use std::{sync::{Mutex,
+                     Once},
+              thread};
+
+    lazy_static! {
+        static ref RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    }
+
+    struct CaptureLogger;
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool { metadata.level() <= Level::Debug }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                RECORDS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger;
+    static INIT: Once = Once::new();
+
+    fn capture_logs() {
+        INIT.call_once(|| {
+                log::set_logger(&LOGGER).unwrap();
+                log::set_max_level(LevelFilter::Debug);
+            });
+    }
+
+    #[test]
+    fn finished_phases_are_recorded_in_order() {
+        let mut timings = Timings::start();
+        thread::sleep(Duration::from_millis(5));
+        let install = timings.finish("install");
+        let build = timings.finish("build");
+
+        let phases: Vec<_> = timings.phases().iter().map(|(p, _)| *p).collect();
+        assert_eq!(vec!["install", "build"], phases);
+        assert!(install >= Duration::from_millis(5));
+        assert_eq!(install + build, timings.total());
+    }
+
+    #[test]
+    fn finished_phases_log_their_duration() {
+        capture_logs();
+        let mut timings = Timings::start();
+        timings.finish("push");
+
+        let records = RECORDS.lock().unwrap();
+        assert!(records.iter()
+                       .any(|r| r.starts_with("Export phase 'push' took ") && r.ends_with('s')),
+                "{:?}",
+                records);
+    }
+}
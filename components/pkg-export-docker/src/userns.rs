@@ -0,0 +1,79 @@
+//! Types for representing the user namespace mapping an image expects from a rootless container
+//! runtime, which is recorded in an image label and whose first id owns the files copied into
+//! the image.
+
+use crate::error::Error;
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// A range of host user and group ids onto which a container's ids are mapped, starting with its
+/// root user and group.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UsernsRemap {
+    start: u32,
+    count: u32,
+}
+
+impl UsernsRemap {
+    /// Returns the first host id of the range, onto which the container's root id is mapped.
+    pub fn start(&self) -> u32 { self.start }
+
+    /// Returns the number of ids in the range.
+    pub fn count(&self) -> u32 { self.count }
+}
+
+impl FromStr for UsernsRemap {
+    type Err = Error;
+
+    /// Parses a `START:COUNT` pair (ex: `100000:65536`), where the count is at least one and the
+    /// range fits within the 32-bit id space.
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, ':');
+        let start = parts.next().and_then(|s| s.parse::<u32>().ok());
+        let count = parts.next().and_then(|c| c.parse::<u32>().ok());
+        match (start, count) {
+            (Some(start), Some(count)) if count > 0 && start.checked_add(count - 1).is_some() => {
+                Ok(UsernsRemap { start, count })
+            }
+            _ => Err(Error::InvalidUsernsRemap(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for UsernsRemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.start, self.count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn userns_remap_parses_start_and_count() {
+        let remap: UsernsRemap = "100000:65536".parse().unwrap();
+        assert_eq!(100_000, remap.start());
+        assert_eq!(65_536, remap.count());
+        assert_eq!("100000:65536", remap.to_string());
+    }
+
+    #[test]
+    fn userns_remap_rejects_invalid_ranges() {
+        for value in &["100000",
+                       "100000:",
+                       ":65536",
+                       "100000:0",
+                       "-1:65536",
+                       "100000:65536:1",
+                       "4294967295:2",
+                       "a:b"]
+        {
+            match value.parse::<UsernsRemap>() {
+                Err(Error::InvalidUsernsRemap(v)) => assert_eq!(&v, value),
+                r => panic!("Expected InvalidUsernsRemap for {}, got {:?}", value, r),
+            }
+        }
+    }
+}
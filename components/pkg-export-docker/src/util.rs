@@ -12,15 +12,6 @@ const BIN_PATH: &str = "/bin";
 /// Returns the `bin` path used for symlinking programs.
 pub fn bin_path() -> &'static Path { Path::new(BIN_PATH) }
 
-/// Returns the Package Identifier for a Busybox package.
-#[cfg(unix)]
-pub fn busybox_ident() -> Result<PackageIdent> {
-    use super::BUSYBOX_IDENT;
-    use std::str::FromStr;
-
-    Ok(PackageIdent::from_str(BUSYBOX_IDENT)?)
-}
-
 /// Returns the path to a package prefix for the provided Package Identifier in a root file system.
 ///
 /// # Errors
@@ -3,7 +3,8 @@ use clap::{App,
 use std::{result,
           str::FromStr};
 
-use crate::common::command::package::install::InstallSource;
+use crate::{common::command::package::install::InstallSource,
+            compression::CompressionLevel};
 use url::Url;
 
 /// The version of this library and program when built.
@@ -119,6 +120,18 @@ impl<'a, 'b> Cli<'a, 'b> {
         Cli { app }
     }
 
+    pub fn add_compress_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("COMPRESS").long("compress")
+                                                     .value_name("LEVEL")
+                                                     .possible_values(CompressionLevel::VARIANTS)
+                                                     .help("How much to compress the tarball, \
+                                                            trading export time for size \
+                                                            (default: gzip's default level)"));
+
+        Cli { app }
+    }
+
     pub fn add_pkg_ident_arg(self) -> Self {
         let help = "A Habitat package identifier (ex: acme/redis) and/or filepath to a Habitat \
                     Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)";
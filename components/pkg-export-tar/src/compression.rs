@@ -0,0 +1,66 @@
+//! The compression levels offered for the exported tarball.
+
+use crate::error::Error;
+use flate2::Compression;
+use std::{result,
+          str::FromStr};
+
+/// How much to compress the exported tarball, trading export time for size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompressionLevel {
+    /// No compression, leaving the tarball's contents stored as is in the gzip stream.
+    None,
+    /// The fastest gzip compression.
+    Fast,
+    /// The smallest gzip compression.
+    Best,
+}
+
+impl CompressionLevel {
+    /// The names of the compression levels, as accepted on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["none", "fast", "best"];
+}
+
+impl FromStr for CompressionLevel {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(CompressionLevel::None),
+            "fast" => Ok(CompressionLevel::Fast),
+            "best" => Ok(CompressionLevel::Best),
+            _ => Err(Error::InvalidCompressionLevel(value.to_string())),
+        }
+    }
+}
+
+impl From<CompressionLevel> for Compression {
+    fn from(level: CompressionLevel) -> Self {
+        match level {
+            CompressionLevel::None => Compression::none(),
+            CompressionLevel::Fast => Compression::fast(),
+            CompressionLevel::Best => Compression::best(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compression_levels_parse_from_their_names() {
+        for name in CompressionLevel::VARIANTS {
+            assert!(name.parse::<CompressionLevel>().is_ok(), "{}", name);
+        }
+        assert!("fastest".parse::<CompressionLevel>().is_err());
+        assert!("9".parse::<CompressionLevel>().is_err());
+    }
+
+    #[test]
+    fn compression_levels_map_to_gzip_levels() {
+        assert_eq!(Compression::from(CompressionLevel::None).level(), 0);
+        assert_eq!(Compression::from(CompressionLevel::Fast).level(), 1);
+        assert_eq!(Compression::from(CompressionLevel::Best).level(), 9);
+    }
+}
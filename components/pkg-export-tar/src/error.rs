@@ -8,6 +8,10 @@ pub type Result<T> = result::Result<T, failure::Error>;
 pub enum Error {
     #[fail(display = "{}", _0)]
     Base64DecodeError(DecodeError),
+    #[fail(display = "Invalid compression level: '{}'. Compression levels are none, fast, or \
+                      best",
+           _0)]
+    InvalidCompressionLevel(String),
     #[fail(display = "A primary service package could not be determined from: {:?}. At least \
                       one package with a run hook must be provided.",
            _0)]
@@ -11,10 +11,12 @@ extern crate log;
 
 mod build;
 pub mod cli;
+mod compression;
 mod error;
 mod rootfs;
 
 pub use crate::{cli::Cli,
+                compression::CompressionLevel,
                 error::{Error,
                         Result}};
 use crate::{common::ui::UI,
@@ -39,27 +41,39 @@ const BUSYBOX_IDENT: &str = "core/busybox-static";
 pub async fn export_for_cli_matches(ui: &mut UI, matches: &clap::ArgMatches<'_>) -> Result<()> {
     let default_url = hurl::default_bldr_url();
     let spec = BuildSpec::new_from_cli_matches(&matches, &default_url);
-    export(ui, spec).await?;
+    let compression = matches.value_of("COMPRESS")
+                             .map(CompressionLevel::from_str)
+                             .transpose()?;
+    export(ui, spec, compression).await?;
 
     Ok(())
 }
 
-pub async fn export(ui: &mut UI, build_spec: BuildSpec<'_>) -> Result<()> {
+/// Exports a tarball of the build root, compressed at the given level or the gzip encoder's
+/// default level when none is given.
+pub async fn export(ui: &mut UI,
+                    build_spec: BuildSpec<'_>,
+                    compression: Option<CompressionLevel>)
+                    -> Result<()> {
     let hab_pkg = build_spec.hab;
     let build_result = build_spec.create(ui).await.unwrap();
     let builder_dir_path = build_result.0.path();
     let pkg_ident = build_result.1;
 
-    tar_command(builder_dir_path, pkg_ident, hab_pkg);
+    let compression = compression.map(Compression::from).unwrap_or_default();
+    tar_command(builder_dir_path, pkg_ident, hab_pkg, compression);
     Ok(())
 }
 
 #[allow(unused_must_use)]
-fn tar_command(temp_dir_path: &Path, pkg_ident: PackageIdent, hab_pkg: &str) {
+fn tar_command(temp_dir_path: &Path,
+               pkg_ident: PackageIdent,
+               hab_pkg: &str,
+               compression: Compression) {
     let tarball_name = format_tar_name(pkg_ident);
 
     let tarball = File::create(tarball_name).unwrap();
-    let enc = GzEncoder::new(tarball, Compression::default());
+    let enc = GzEncoder::new(tarball, compression);
     let mut tar_builder = Builder::new(enc);
     tar_builder.follow_symlinks(false);
 
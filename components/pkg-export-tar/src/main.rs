@@ -34,6 +34,7 @@ fn cli<'a, 'b>() -> App<'a, 'b> {
     let about = "Creates a tar package from a Habitat package";
     Cli::new(name, about).add_base_packages_args()
                          .add_builder_args()
+                         .add_compress_arg()
                          .add_pkg_ident_arg()
                          .app
 }
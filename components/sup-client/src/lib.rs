@@ -38,18 +38,29 @@ extern crate log;
 use crate::{common::types::ListenCtlAddr,
             protocol::{codec::*,
                        net::NetErr}};
-use futures::{sink::SinkExt,
+use futures::{future::Either,
+              sink::SinkExt,
               stream::{Stream,
                        StreamExt}};
 use habitat_common as common;
+use rustls::ClientConfig;
 use std::{error,
           fmt,
-          io,
-          path::PathBuf,
+          fs::File,
+          io::{self,
+               BufReader},
+          path::{Path,
+                 PathBuf},
+          sync::Arc,
           time::Duration};
-use tokio::{net::TcpStream,
+use tokio::{io::{AsyncRead,
+                 AsyncWrite},
+            net::TcpStream,
             time};
+use tokio_rustls::TlsConnector;
 use tokio_util::codec::Framed;
+use webpki::{DNSName,
+             DNSNameRef};
 
 /// Time to wait in milliseconds for a client connection to timeout.
 pub const REQ_TIMEOUT: u64 = 10_000;
@@ -65,6 +76,11 @@ pub enum SrvClientError {
     CtlSecretNotFound(PathBuf),
     /// Decoding a message from the remote failed.
     Decode(prost::DecodeError),
+    /// The file given as the Control Gateway's trust root contains no PEM-format certificates.
+    InvalidCtlCaCert(PathBuf),
+    /// The name which the Control Gateway's certificate must be issued for is not a valid DNS
+    /// name.
+    InvalidCtlServerName(String),
     /// An Os level IO error occurred.
     Io(io::Error),
     /// An RPC call to the remote was received but failed.
@@ -98,6 +114,14 @@ impl fmt::Display for SrvClientError {
                         path.display())
             }
             SrvClientError::Decode(ref err) => format!("{}", err),
+            SrvClientError::InvalidCtlCaCert(ref path) => {
+                format!("No PEM-format CA certificates found in {}", path.display())
+            }
+            SrvClientError::InvalidCtlServerName(ref name) => {
+                format!("'{}' is not a valid DNS name to verify the Supervisor's Control Gateway \
+                         certificate against",
+                        name)
+            }
             SrvClientError::Io(ref err) => format!("{}", err),
             SrvClientError::NetErr(ref err) => format!("{}", err),
             SrvClientError::ParseColor(ref err) => format!("{}", err),
@@ -127,6 +151,32 @@ impl From<termcolor::ParseColorError> for SrvClientError {
     fn from(err: termcolor::ParseColorError) -> Self { SrvClientError::ParseColor(err) }
 }
 
+/// The TLS configuration used to connect to a Control Gateway which serves TLS.
+#[derive(Clone)]
+pub struct CtlTlsConfig {
+    config:      Arc<ClientConfig>,
+    server_name: DNSName,
+}
+
+impl CtlTlsConfig {
+    /// Trust the PEM-format CA certificates in `ca_cert_path`, and require the Control Gateway's
+    /// certificate to be issued for `server_name`.
+    pub fn new(ca_cert_path: &Path, server_name: &str) -> Result<Self, SrvClientError> {
+        let mut config = ClientConfig::new();
+        let ca_file = &mut BufReader::new(File::open(ca_cert_path)?);
+        match config.root_store.add_pem_file(ca_file) {
+            Ok((added, _)) if added > 0 => (),
+            _ => return Err(SrvClientError::InvalidCtlCaCert(ca_cert_path.to_path_buf())),
+        }
+        let server_name = DNSNameRef::try_from_ascii_str(server_name).map_err(|_| {
+                              SrvClientError::InvalidCtlServerName(server_name.to_string())
+                          })?
+                          .to_owned();
+        Ok(CtlTlsConfig { config: Arc::new(config),
+                          server_name })
+    }
+}
+
 /// Client for connecting and communicating with a server speaking SrvProtocol.
 ///
 /// See module doc for usage.
@@ -141,8 +191,43 @@ impl SrvClient {
         secret_key: &str,
         request: impl Into<SrvMessage> + fmt::Debug)
         -> Result<impl Stream<Item = Result<SrvMessage, io::Error>>, SrvClientError> {
-        let socket = TcpStream::connect(address.as_ref()).await?;
-        let mut socket = Framed::new(socket, SrvCodec::new());
+        Self::request_with_tls(address, None, secret_key, request).await
+    }
+
+    /// Connect to the remote server with the given secret_key and make a request. If `tls_config`
+    /// is given, the connection is made over TLS.
+    ///
+    /// Returns a stream of `SrvMessage`'s representing the server response.
+    pub async fn request_with_tls(
+        address: &ListenCtlAddr,
+        tls_config: Option<&CtlTlsConfig>,
+        secret_key: &str,
+        request: impl Into<SrvMessage> + fmt::Debug)
+        -> Result<impl Stream<Item = Result<SrvMessage, io::Error>>, SrvClientError> {
+        let tcp_stream = TcpStream::connect(address.as_ref()).await?;
+        match tls_config {
+            Some(tls_config) => {
+                let connector = TlsConnector::from(Arc::clone(&tls_config.config));
+                let tls_stream = connector.connect(tls_config.server_name.as_ref(), tcp_stream)
+                                          .await?;
+                let socket = Framed::new(tls_stream, SrvCodec::new());
+                Ok(Either::Left(Self::send(socket, secret_key, request).await?))
+            }
+            None => {
+                let socket = Framed::new(tcp_stream, SrvCodec::new());
+                Ok(Either::Right(Self::send(socket, secret_key, request).await?))
+            }
+        }
+    }
+
+    /// Complete the handshake with the remote server over the framed socket stream and send the
+    /// request, returning the socket for use as a stream of responses.
+    async fn send<T>(mut socket: Framed<T, SrvCodec>,
+                     secret_key: &str,
+                     request: impl Into<SrvMessage> + fmt::Debug)
+                     -> Result<Framed<T, SrvCodec>, SrvClientError>
+        where T: AsyncRead + AsyncWrite + Unpin
+    {
         let mut current_transaction = SrvTxn::default();
 
         // Send the handshake message to the server
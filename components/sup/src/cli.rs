@@ -65,6 +65,47 @@ mod test {
                         "BIND" => ["service.group1", "service.group2"],
                         "PKG_IDENT_OR_ARTIFACT" => "core/redis");
 
+        #[test]
+        fn auto_update_and_no_auto_update_are_mutually_exclusive() {
+            let cmd_vec =
+                Vec::from_iter("hab-sup run --auto-update --no-auto-update".split_whitespace());
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
+        #[test]
+        fn auto_update_channel_must_not_be_empty() {
+            let cmd_vec = vec!["hab-sup",
+                               "run",
+                               "--auto-update",
+                               "--auto-update-channel",
+                               ""];
+            assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                           .is_err());
+        }
+
+        #[test]
+        fn auto_update_period_must_be_a_positive_number_of_seconds() {
+            for period in &["0", "-5", "5m", "soon"] {
+                let cmd_vec = vec!["hab-sup",
+                                   "run",
+                                   "--auto-update",
+                                   "--auto-update-period",
+                                   period];
+                assert!(cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
+                                               .is_err(),
+                        "'{}' should be rejected",
+                        period);
+            }
+        }
+
+        assert_cli_cmd!(should_handle_auto_update_channel_and_period,
+                        no_feature_flags(),
+                        "hab-sup run --auto-update --auto-update-channel LTS-2024 \
+                         --auto-update-period 300",
+                        "AUTO_UPDATE_CHANNEL" => "LTS-2024",
+                        "AUTO_UPDATE_PERIOD" => "300");
+
         #[test]
         fn local_gossip_mode_and_listen_gossip_are_mutually_exclusive() {
             let cmd_vec = Vec::from_iter("hab-sup run --listen-gossip 1.1.1.1:1111 \
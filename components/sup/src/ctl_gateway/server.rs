@@ -23,7 +23,6 @@ use habitat_core::crypto;
 use habitat_sup_protocol::{self as protocol,
                            codec::{SrvCodec,
                                    SrvMessage,
-                                   SrvStream,
                                    SrvTxn},
                            net::{self,
                                  ErrCode,
@@ -34,6 +33,7 @@ use prometheus::{HistogramTimer,
                  HistogramVec,
                  IntCounterVec};
 use prost;
+use rustls::ServerConfig;
 use std::{error,
           fmt,
           io,
@@ -42,10 +42,14 @@ use std::{error,
           sync::{Arc,
                  Mutex},
           time::Duration};
-use tokio::{net::TcpListener,
+use tokio::{io::{AsyncRead,
+                 AsyncWrite},
+            net::TcpListener,
             task,
             time};
-use tokio_util::codec::Decoder;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::codec::{Decoder,
+                        Framed};
 
 lazy_static! {
     static ref RPC_CALLS: IntCounterVec = register_int_counter_vec!("hab_sup_rpc_call_total",
@@ -146,8 +150,11 @@ struct Client {
 }
 
 impl Client {
-    /// Serve the client from the given framed socket stream.
-    pub async fn serve(self, mut socket: SrvStream) -> Result<(), HandlerError> {
+    /// Serve the client from the given framed socket stream, which is either a plain TCP stream
+    /// or a TLS stream over one.
+    pub async fn serve<T>(self, mut socket: Framed<T, SrvCodec>) -> Result<(), HandlerError>
+        where T: AsyncRead + AsyncWrite + Unpin
+    {
         let mgr_sender = self.state
                              .lock()
                              .expect("SrvState mutex poisoned")
@@ -164,7 +171,9 @@ impl Client {
 
     /// Initiate a handshake with the connected client before allowing future requests. A failed
     /// handshake will close the connection.
-    async fn handshake(&self, socket: &mut SrvStream) -> Result<(), HandlerError> {
+    async fn handshake<T>(&self, socket: &mut Framed<T, SrvCodec>) -> Result<(), HandlerError>
+        where T: AsyncRead + AsyncWrite + Unpin
+    {
         let message = socket.next()
                             .await
                             .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))??;
@@ -205,9 +214,9 @@ impl Client {
 /// A `Future` that will resolve into a stream of one or more `SrvMessage` replies.
 #[must_use = "futures do nothing unless polled"]
 #[pin_project]
-struct SrvHandler {
+struct SrvHandler<T> {
     #[pin]
-    io:           SrvStream,
+    io:           Framed<T, SrvCodec>,
     state:        SrvHandlerState,
     mgr_sender:   MgrSender,
     ctl_receiver: CtlReceiver,
@@ -215,8 +224,8 @@ struct SrvHandler {
     timer:        Option<HistogramTimer>,
 }
 
-impl SrvHandler {
-    fn new(io: SrvStream, mgr_sender: MgrSender) -> Self {
+impl<T> SrvHandler<T> {
+    fn new(io: Framed<T, SrvCodec>, mgr_sender: MgrSender) -> Self {
         let (ctl_sender, ctl_receiver) = mpsc::unbounded();
 
         SrvHandler { io,
@@ -352,7 +361,8 @@ impl SrvHandler {
     }
 }
 
-impl Future for SrvHandler {
+impl<T> Future for SrvHandler<T> where T: AsyncRead + AsyncWrite + Unpin
+{
     type Output = Result<(), HandlerError>;
 
     /// # Locking (see locking.md)
@@ -466,12 +476,17 @@ struct SrvState {
 
 /// Start a new thread which will run the CtlGateway server.
 ///
-/// New connections will be authenticated using `secret_key`. Messages from the main thread
-/// will be sent over the channel `mgr_sender`.
-pub async fn run(listen_addr: SocketAddr, secret_key: String, mgr_sender: MgrSender) {
+/// New connections will be authenticated using `secret_key`. If `tls_config` is given, they must
+/// also complete a TLS handshake before any message is read. Messages from the main thread will be
+/// sent over the channel `mgr_sender`.
+pub async fn run(listen_addr: SocketAddr,
+                 secret_key: String,
+                 tls_config: Option<Arc<ServerConfig>>,
+                 mgr_sender: MgrSender) {
     let state = SrvState { secret_key,
                            mgr_sender };
     let state = Arc::new(Mutex::new(state));
+    let tls_acceptor = tls_config.map(TlsAcceptor::from);
     let mut listner =
         TcpListener::bind(&listen_addr).await
                                        .expect("Could not bind ctl gateway listen address!");
@@ -486,12 +501,29 @@ pub async fn run(listen_addr: SocketAddr, secret_key: String, mgr_sender: MgrSen
                         continue;
                     }
                 };
-                let io = SrvCodec::new().framed(tcp_stream);
                 let client = Client { state: Arc::clone(&state), };
-                tokio::spawn(async move {
-                    let res = client.serve(io).await;
-                    debug!("DISCONNECTED from {:?} with result {:?}", addr, res);
-                });
+                match tls_acceptor {
+                    Some(ref tls_acceptor) => {
+                        let tls_acceptor = tls_acceptor.clone();
+                        tokio::spawn(async move {
+                            match tls_acceptor.accept(tcp_stream).await {
+                                Ok(tls_stream) => {
+                                    let io = SrvCodec::new().framed(tls_stream);
+                                    let res = client.serve(io).await;
+                                    debug!("DISCONNECTED from {:?} with result {:?}", addr, res);
+                                }
+                                Err(e) => debug!("TLS handshake with {:?} failed, err {}", addr, e),
+                            }
+                        });
+                    }
+                    None => {
+                        let io = SrvCodec::new().framed(tcp_stream);
+                        tokio::spawn(async move {
+                            let res = client.serve(io).await;
+                            debug!("DISCONNECTED from {:?} with result {:?}", addr, res);
+                        });
+                    }
+                }
             }
             Err(e) => error!("SrvHandler failed to connect, err: {}", e),
         }
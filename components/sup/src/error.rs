@@ -35,11 +35,13 @@ pub type Result<T> = result::Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
     Departed,
+    AutoUpdateDisabled(String),
     BadAddress(String),
     BadDataFile(PathBuf, io::Error),
     BadDataPath(PathBuf, io::Error),
     BadDesiredState(String),
     BadElectionStatus(String),
+    BadListenAddress(String, io::Error),
     BadSpecsPath(PathBuf, io::Error),
     BadStartStyle(String),
     BindTimeout(String),
@@ -128,6 +130,11 @@ impl fmt::Display for Error {
                         path.display(),
                         err)
             }
+            Error::AutoUpdateDisabled(ref flag) => {
+                format!("{} requires automatic updates, which are enabled with --auto-update or \
+                         the HAB_AUTO_UPDATE environment variable",
+                        flag)
+            }
             Error::BadDataPath(ref path, ref err) => {
                 format!("Unable to read or write to data directory, {}, {}",
                         path.display(),
@@ -137,6 +144,9 @@ impl fmt::Display for Error {
                 format!("Unknown service desired state style '{}'", state)
             }
             Error::BadElectionStatus(ref status) => format!("Unknown election status '{}'", status),
+            Error::BadListenAddress(ref addr, ref err) => {
+                format!("Unable to resolve listen address '{}' ({})", addr, err)
+            }
             Error::BadSpecsPath(ref path, ref err) => {
                 format!("Unable to create the specs directory '{}' ({})",
                         path.display(),
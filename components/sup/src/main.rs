@@ -33,7 +33,9 @@ use habitat_common::{cli::cache_key_path_from_matches,
                               OutputFormat,
                               OutputVerbosity},
                      outputln,
-                     types::GossipListenAddr,
+                     templating,
+                     types::{GossipListenAddr,
+                             HttpListenAddr},
                      ui::{UIWriter,
                           NONINTERACTIVE_ENVVAR,
                           UI},
@@ -58,6 +60,7 @@ use habitat_sup_protocol::{self as sup_proto,
                                    UpdateCondition,
                                    UpdateStrategy}};
 use std::{env,
+          fs,
           io::{self,
                Write},
           net::{IpAddr,
@@ -68,7 +71,8 @@ use std::{env,
                  PathBuf},
           process,
           str::{self,
-                FromStr}};
+                FromStr},
+          time::Duration};
 #[cfg(test)]
 use tempfile::TempDir;
 use tokio::{self,
@@ -77,6 +81,10 @@ use tokio::{self,
 /// Our output key
 static LOGKEY: &str = "MN";
 
+/// Environment variable which enables Supervisor self-updates when neither `--auto-update` nor
+/// `--no-auto-update` is given
+const AUTO_UPDATE_ENVVAR: &str = "HAB_AUTO_UPDATE";
+
 #[cfg(unix)]
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
@@ -207,6 +215,10 @@ async fn sub_run_rsr_imlw_mlw_gsw_smw_rhw_msw(m: &ArgMatches<'_>,
 
     let cfg = mgrcfg_from_sup_run_matches(m, feature_flags)?;
 
+    if let Some(command) = m.value_of("SECRETS_PROVIDER") {
+        templating::helpers::set_secrets_provider(command.to_string());
+    }
+
     let sys_ip = m.value_of("SYS_IP_ADDRESS")
                   .and_then(|s| IpAddr::from_str(s).ok())
                   .or_else(|| {
@@ -286,26 +298,33 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
         None
     };
 
+    let auto_update = auto_update(m);
+    if !auto_update {
+        check_auto_update_disabled(m)?;
+    }
+
     #[rustfmt::skip]
     let cfg = ManagerConfig {
-        auto_update: m.is_present("AUTO_UPDATE"),
+        auto_update,
         custom_state_path: None, // remove entirely?
         cache_key_path,
         update_url: bldr_url(m),
-        update_channel: channel(m),
+        update_channel: auto_update_channel(m),
+        update_period: m.value_of("AUTO_UPDATE_PERIOD").and_then(|s| s.parse().ok()).map(Duration::from_secs),
         http_disable: m.is_present("HTTP_DISABLE"),
         organization: m.value_of("ORGANIZATION").map(str::to_string),
         gossip_permanent: m.is_present("PERMANENT_PEER"),
         ring_key: get_ring_key(m, &cache_key_path_from_matches(m))?,
         gossip_peers: get_peers(m)?,
         watch_peer_file: m.value_of("PEER_WATCH_FILE").map(str::to_string),
+        peer_dns: m.value_of("PEER_DNS").map(str::to_string),
         gossip_listen: if m.is_present("LOCAL_GOSSIP_MODE") {
             GossipListenAddr::local_only()
         } else {
-            m.value_of("LISTEN_GOSSIP").and_then(|s| s.parse().ok()).unwrap_or_default()
+            listen_addr_from_input(m, "LISTEN_GOSSIP")?.map(GossipListenAddr::from).unwrap_or_default()
         },
         ctl_listen: m.value_of("LISTEN_CTL").and_then(|s| s.parse().ok()).unwrap_or_default(),
-        http_listen: m.value_of("LISTEN_HTTP").and_then(|s| s.parse().ok()).unwrap_or_default(),
+        http_listen: listen_addr_from_input(m, "LISTEN_HTTP")?.map(HttpListenAddr::from).unwrap_or_default(),
         tls_config: m.value_of("KEY_FILE").map(|kf| {
             let cert_path = m
                 .value_of("CERT_FILE")
@@ -318,9 +337,21 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
                 ca_cert_path,
             }
         }),
+        ctl_tls_config: m.value_of("LISTEN_CTL_KEY_FILE").map(|kf| {
+            let cert_path = m
+                .value_of("LISTEN_CTL_CERT_FILE")
+                .map(PathBuf::from)
+                .expect("LISTEN_CTL_CERT_FILE should always have a value if LISTEN_CTL_KEY_FILE has a value.");
+            TLSConfig {
+                key_path: PathBuf::from(kf),
+                cert_path,
+                ca_cert_path: None,
+            }
+        }),
         feature_flags,
         event_stream_config,
         keep_latest_packages: m.value_of("NUM_LATEST_PACKAGES_TO_KEEP").and_then(|s| s.parse().ok()),
+        default_health_check_interval: m.value_of("DEFAULT_HEALTH_CHECK_INTERVAL").and_then(|s| s.parse().ok()),
     };
 
     Ok(cfg)
@@ -329,6 +360,33 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches,
 // Various CLI Parsing Functions
 ////////////////////////////////////////////////////////////////////////
 
+/// Determine whether the Supervisor should update itself. An explicit `--no-auto-update` or
+/// `--auto-update` takes precedence over the `HAB_AUTO_UPDATE` environment variable, which
+/// enables updates when set to `1` or `true`.
+fn auto_update(m: &ArgMatches) -> bool {
+    if m.is_present("NO_AUTO_UPDATE") {
+        false
+    } else if m.is_present("AUTO_UPDATE") {
+        true
+    } else {
+        habitat_core::env::var(AUTO_UPDATE_ENVVAR).map(|val| val == "1" || val == "true")
+                                                  .unwrap_or(false)
+    }
+}
+
+/// Check that no auto-update options were given when automatic updates are disabled, as they would
+/// have no effect.
+fn check_auto_update_disabled(m: &ArgMatches) -> Result<()> {
+    for (name, flag) in &[("AUTO_UPDATE_CHANNEL", "--auto-update-channel"),
+                          ("AUTO_UPDATE_PERIOD", "--auto-update-period")]
+    {
+        if m.is_present(name) {
+            return Err(Error::AutoUpdateDisabled(flag.to_string()));
+        }
+    }
+    Ok(())
+}
+
 fn get_peers(matches: &ArgMatches) -> Result<Vec<SocketAddr>> {
     // TODO fn: Clean this up--using a for loop doesn't feel good however an iterator was
     // causing a lot of developer/compiler type confusion
@@ -355,6 +413,30 @@ fn get_peers(matches: &ArgMatches) -> Result<Vec<SocketAddr>> {
     Ok(gossip_peers)
 }
 
+/// Resolves the listen address given for the named argument, if any.
+fn listen_addr_from_input(m: &ArgMatches, name: &str) -> Result<Option<SocketAddr>> {
+    m.value_of(name)
+     .map(|addr| resolve_listen_addr(addr, |a| a.to_socket_addrs().map(Iterator::collect)))
+     .transpose()
+}
+
+/// Resolves a listen address given as either `IP:PORT` or `HOST:PORT`. Numeric addresses are used
+/// as is, while a host name is looked up with `resolve` and the first address it resolves to is
+/// used.
+fn resolve_listen_addr<F>(addr: &str, resolve: F) -> Result<SocketAddr>
+    where F: FnOnce(&str) -> io::Result<Vec<SocketAddr>>
+{
+    if let Ok(socket_addr) = addr.parse() {
+        return Ok(socket_addr);
+    }
+    let addrs = resolve(addr).map_err(|e| Error::BadListenAddress(addr.to_string(), e))?;
+    addrs.into_iter().next().ok_or_else(|| {
+                                let e =
+                                    io::Error::new(io::ErrorKind::NotFound, "no addresses found");
+                                Error::BadListenAddress(addr.to_string(), e)
+                            })
+}
+
 // TODO: Make this more testable.
 // The use of env variables here makes it difficult to unit test. Since tests are run in parallel,
 // setting an env var in one test can adversely effect the results in another test. We need some
@@ -398,6 +480,14 @@ fn bldr_url_from_input(m: &ArgMatches) -> Option<String> {
 /// default value.
 fn channel(matches: &ArgMatches) -> ChannelIdent { channel_from_input(matches).unwrap_or_default() }
 
+/// Resolve the channel the Supervisor updates itself from. Taken from the auto-update channel, or
+/// (failing that) the channel used for services.
+fn auto_update_channel(m: &ArgMatches) -> ChannelIdent {
+    m.value_of("AUTO_UPDATE_CHANNEL")
+     .map(ChannelIdent::from)
+     .unwrap_or_else(|| channel(m))
+}
+
 /// A channel name, but *only* if the user specified via CLI args.
 fn channel_from_input(m: &ArgMatches) -> Option<ChannelIdent> {
     m.value_of("CHANNEL").map(ChannelIdent::from)
@@ -426,15 +516,39 @@ fn get_update_condition_from_input(m: &ArgMatches<'_>) -> Option<UpdateCondition
 }
 
 fn get_binds_from_input(m: &ArgMatches) -> Result<Option<ServiceBindList>> {
-    match m.values_of("BIND") {
-        Some(bind_strs) => {
-            let mut list = ServiceBindList::default();
-            for bind_str in bind_strs {
-                list.binds.push(ServiceBind::from_str(bind_str)?);
-            }
-            Ok(Some(list))
+    if !m.is_present("BIND") && !m.is_present("BIND_FILE") {
+        return Ok(None);
+    }
+    let mut list = ServiceBindList::default();
+    if let Some(bind_file) = m.value_of("BIND_FILE") {
+        list.binds = parse_bind_file(&fs::read_to_string(bind_file)?)?;
+    }
+    if let Some(bind_strs) = m.values_of("BIND") {
+        for bind_str in bind_strs {
+            list.binds.push(ServiceBind::from_str(bind_str)?);
+        }
+    }
+    Ok(Some(list))
+}
+
+/// Parse the contents of a bind file, which has one bind per line in the same form as the
+/// `--bind` option. Blank lines and lines starting with `#` are ignored.
+fn parse_bind_file(contents: &str) -> Result<Vec<ServiceBind>> {
+    let mut binds = Vec::new();
+    let mut invalid = Vec::new();
+    for (index, line) in contents.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match ServiceBind::from_str(line) {
+            Ok(bind) => binds.push(bind),
+            Err(_) => invalid.push(format!("'{}' on line {}", line, index + 1)),
         }
-        None => Ok(None),
+    }
+    if invalid.is_empty() {
+        Ok(binds)
+    } else {
+        Err(Error::InvalidBinds(invalid))
     }
 }
 
@@ -450,12 +564,27 @@ fn get_config_from_input(m: &ArgMatches) -> Option<String> {
         warn!("WARNING: Setting '--config-from' should only be used in development, not \
                production!");
         warn!("");
+        if config_dir_is_empty(Path::new(config_from)) {
+            warn!("The '--config-from' directory {} contains no config templates or hooks, so \
+                   the service will run without them",
+                  config_from);
+        }
         Some((*config_from).to_string())
     } else {
         None
     }
 }
 
+/// Returns `true` if neither the `config` nor the `hooks` directory under a `--config-from`
+/// directory contains any files.
+fn config_dir_is_empty(config_from: &Path) -> bool {
+    let is_empty = |dir: &str| {
+        fs::read_dir(config_from.join(dir)).map(|mut entries| entries.next().is_none())
+                                           .unwrap_or(true)
+    };
+    is_empty("config") && is_empty("hooks")
+}
+
 #[cfg(target_os = "windows")]
 fn get_password_from_input(m: &ArgMatches) -> Result<Option<String>> {
     if let Some(password) = m.value_of("PASSWORD") {
@@ -475,6 +604,9 @@ fn set_supervisor_logging_options(m: &ArgMatches) {
     if m.is_present("NO_COLOR") {
         output::set_format(OutputFormat::NoColor)
     }
+    if m.is_present("FORCE_COLOR") {
+        output::set_force_color(true)
+    }
     if m.is_present("JSON") {
         output::set_format(OutputFormat::JSON)
     }
@@ -525,7 +657,8 @@ mod test {
     use habitat_common::types::{GossipListenAddr,
                                 HttpListenAddr,
                                 ListenCtlAddr};
-    use habitat_core::locked_env_var;
+    use habitat_core::{locked_env_var,
+                       service::HealthCheckInterval};
 
     fn no_feature_flags() -> FeatureFlag { FeatureFlag::empty() }
 
@@ -566,17 +699,25 @@ mod test {
         fn cmd_vec_from_cmd_str(cmd: &str) -> Vec<&str> { Vec::from_iter(cmd.split_whitespace()) }
 
         fn config_from_cmd_vec(cmd_vec: Vec<&str>) -> ManagerConfig {
+            config_result_from_cmd_vec(cmd_vec).expect("Could not get config")
+        }
+
+        fn config_result_from_cmd_vec(cmd_vec: Vec<&str>) -> Result<ManagerConfig> {
             let matches = cli(no_feature_flags()).get_matches_from_safe(cmd_vec)
                                                  .expect("Error while getting matches");
             let (_, sub_matches) = matches.subcommand();
             let sub_matches = sub_matches.expect("Error getting sub command matches");
 
-            mgrcfg_from_sup_run_matches(&sub_matches, no_feature_flags()).expect("Could not get \
-                                                                                  config")
+            mgrcfg_from_sup_run_matches(&sub_matches, no_feature_flags())
         }
 
+        habitat_core::locked_env_var!(HAB_AUTO_UPDATE, lock_auto_update_env_var);
+
         #[test]
         fn auto_update_should_be_set() {
+            let env_var = lock_auto_update_env_var();
+            env_var.unset();
+
             let config = config_from_cmd_str("hab-sup run --auto-update");
             assert_eq!(config.auto_update, true);
 
@@ -584,6 +725,41 @@ mod test {
             assert_eq!(config.auto_update, false);
         }
 
+        #[test]
+        fn auto_update_should_be_set_from_env_var() {
+            let env_var = lock_auto_update_env_var();
+
+            env_var.set("true");
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.auto_update, true);
+
+            env_var.set("1");
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.auto_update, true);
+
+            env_var.set("false");
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.auto_update, false);
+        }
+
+        #[test]
+        fn auto_update_flag_overrides_env_var() {
+            let env_var = lock_auto_update_env_var();
+            env_var.set("false");
+
+            let config = config_from_cmd_str("hab-sup run --auto-update");
+            assert_eq!(config.auto_update, true);
+        }
+
+        #[test]
+        fn no_auto_update_flag_overrides_env_var() {
+            let env_var = lock_auto_update_env_var();
+            env_var.set("true");
+
+            let config = config_from_cmd_str("hab-sup run --no-auto-update");
+            assert_eq!(config.auto_update, false);
+        }
+
         #[test]
         fn update_url_should_be_set() {
             let config = config_from_cmd_str("hab-sup run -u http://fake.example.url");
@@ -608,6 +784,57 @@ mod test {
             assert_eq!(config.update_channel, ChannelIdent::stable());
         }
 
+        #[test]
+        fn auto_update_channel_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --channel unstable --auto-update \
+                                              --auto-update-channel LTS-2024");
+            assert_eq!(config.update_channel, ChannelIdent::from("LTS-2024"));
+        }
+
+        #[test]
+        fn auto_update_period_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --auto-update --auto-update-period 300");
+            assert_eq!(config.update_period, Some(Duration::from_secs(300)));
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.update_period, None);
+        }
+
+        #[test]
+        fn auto_update_options_can_be_used_when_enabled_by_env_var() {
+            let env_var = lock_auto_update_env_var();
+            env_var.set("true");
+
+            let config = config_from_cmd_str("hab-sup run --auto-update-channel LTS-2024 \
+                                              --auto-update-period 60");
+            assert_eq!(config.auto_update, true);
+            assert_eq!(config.update_channel, ChannelIdent::from("LTS-2024"));
+            assert_eq!(config.update_period, Some(Duration::from_secs(60)));
+        }
+
+        #[test]
+        fn auto_update_options_require_auto_update() {
+            let env_var = lock_auto_update_env_var();
+            env_var.unset();
+
+            for cmd in &["hab-sup run --auto-update-channel LTS-2024",
+                         "hab-sup run --auto-update-period 60",
+                         "hab-sup run --auto-update-period 60 --no-auto-update"]
+            {
+                if cmd.contains("--no-auto-update") {
+                    env_var.set("true");
+                }
+                match config_result_from_cmd_vec(cmd_vec_from_cmd_str(cmd)) {
+                    Err(Error::AutoUpdateDisabled(_)) => (),
+                    r => {
+                        panic!("Expected AutoUpdateDisabled for '{}', got {:?}",
+                               cmd,
+                               r.err())
+                    }
+                }
+            }
+        }
+
         #[test]
         fn gossip_listen_should_be_set() {
             let config = config_from_cmd_str("hab-sup run --listen-gossip 1.1.1.1:1111");
@@ -640,6 +867,43 @@ mod test {
             assert_eq!(config.http_listen, expected_addr);
         }
 
+        #[test]
+        fn numeric_listen_addrs_are_not_looked_up() {
+            let addr = resolve_listen_addr("10.0.0.1:9638", |_| {
+                           panic!("numeric addresses should not be resolved")
+                       }).unwrap();
+            assert_eq!(addr, SocketAddr::from(([10, 0, 0, 1], 9638)));
+        }
+
+        #[test]
+        fn listen_addr_host_names_resolve_to_the_first_address() {
+            let addr = resolve_listen_addr("sup.example.com:9631", |host| {
+                           assert_eq!(host, "sup.example.com:9631");
+                           Ok(vec![SocketAddr::from(([192, 168, 1, 10], 9631)),
+                                   SocketAddr::from(([192, 168, 1, 11], 9631)),])
+                       }).unwrap();
+            assert_eq!(addr, SocketAddr::from(([192, 168, 1, 10], 9631)));
+        }
+
+        #[test]
+        fn listen_addr_fails_when_host_name_does_not_resolve() {
+            let result = resolve_listen_addr("nowhere.example.com:9631", |_| {
+                Err(io::Error::new(io::ErrorKind::Other, "lookup failed"))
+            });
+            match result {
+                Err(Error::BadListenAddress(addr, _)) => {
+                    assert_eq!(addr, "nowhere.example.com:9631")
+                }
+                other => panic!("Expected BadListenAddress, got {:?}", other),
+            }
+
+            let result = resolve_listen_addr("nowhere.example.com:9631", |_| Ok(vec![]));
+            match result {
+                Err(Error::BadListenAddress(..)) => {}
+                other => panic!("Expected BadListenAddress, got {:?}", other),
+            }
+        }
+
         #[test]
         fn http_disable_should_be_set() {
             let config = config_from_cmd_str("hab-sup run --http-disable");
@@ -722,6 +986,28 @@ mod test {
             assert_eq!(config.watch_peer_file, None);
         }
 
+        #[test]
+        fn peer_dns_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --peer-dns \
+                                              _hab-gossip._tcp.example.com --peer 1.1.1.1");
+            assert_eq!(config.peer_dns,
+                       Some("_hab-gossip._tcp.example.com".to_string()));
+            assert_eq!(config.gossip_peers.len(), 1);
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.peer_dns, None);
+        }
+
+        #[test]
+        fn default_health_check_interval_should_be_set() {
+            let config = config_from_cmd_str("hab-sup run --default-health-check-interval 10");
+            assert_eq!(config.default_health_check_interval,
+                       Some(HealthCheckInterval::from(10)));
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.default_health_check_interval, None);
+        }
+
         #[test]
         fn ring_key_is_set_properly_by_name() {
             let key_cache = TempDir::new().expect("Could not create tempdir");
@@ -764,4 +1050,53 @@ RCFaO84j41GmrzWddxMdsXpGdn3iuIy7Mw3xYrjPLsE="#,
                        "foobar-20160504220722");
         }
     }
+
+    mod binds {
+        use super::*;
+
+        fn bind(bind_str: &str) -> ServiceBind { ServiceBind::from_str(bind_str).unwrap() }
+
+        #[test]
+        fn bind_file_ignores_comments_and_blank_lines() {
+            let contents = "# Databases\ndatabase:postgres.default\n\n   \n  cache:redis.prod  \n";
+
+            assert_eq!(parse_bind_file(contents).unwrap(),
+                       vec![bind("database:postgres.default"), bind("cache:redis.prod")]);
+        }
+
+        #[test]
+        fn bind_file_rejects_malformed_lines() {
+            let contents = "database:postgres.default\ncache\n# comment\nqueue:rabbitmq";
+
+            match parse_bind_file(contents) {
+                Err(Error::InvalidBinds(invalid)) => {
+                    assert_eq!(invalid,
+                               vec!["'cache' on line 2".to_string(),
+                                    "'queue:rabbitmq' on line 4".to_string()])
+                }
+                r => panic!("Expected InvalidBinds, got {:?}", r),
+            }
+        }
+
+        #[test]
+        fn bind_file_is_merged_with_bind_flags() {
+            let tmp = TempDir::new().expect("Could not create tempdir");
+            let bind_file = tmp.path().join("binds");
+            fs::write(&bind_file, "database:postgres.default\n").unwrap();
+            let matches = cli(no_feature_flags()).get_matches_from_safe(vec!["hab-sup",
+                                                                             "run",
+                                                                             "--bind-file",
+                                                                             bind_file.to_str()
+                                                                                      .unwrap(),
+                                                                             "--bind",
+                                                                             "cache:redis.prod"])
+                                                 .expect("Error while getting matches");
+            let (_, sub_matches) = matches.subcommand();
+
+            let list = get_binds_from_input(sub_matches.unwrap()).unwrap()
+                                                                 .expect("No binds");
+            assert_eq!(list.binds,
+                       vec![bind("database:postgres.default"), bind("cache:redis.prod")]);
+        }
+    }
 }
@@ -4,6 +4,7 @@ pub mod service;
 mod debug;
 pub mod commands;
 mod file_watcher;
+mod peer_dns;
 mod peer_watcher;
 mod self_updater;
 mod service_updater;
@@ -14,6 +15,7 @@ mod user_config_watcher;
 
 use self::{action::{ShutdownInput,
                     SupervisorAction},
+           peer_dns::PeerDns,
            peer_watcher::PeerWatcher,
            self_updater::{SelfUpdater,
                           SUP_PKG_IDENT},
@@ -71,7 +73,8 @@ use habitat_core::{crypto::SymKey,
                    package::{Identifiable,
                              PackageIdent,
                              PackageInstall},
-                   service::ServiceGroup,
+                   service::{HealthCheckInterval,
+                             ServiceGroup},
                    util::ToI64,
                    ChannelIdent};
 use habitat_launcher_client::{LauncherCli,
@@ -263,27 +266,39 @@ impl FsCfg {
 
 #[derive(Clone, Debug)]
 pub struct ManagerConfig {
-    pub auto_update:          bool,
-    pub custom_state_path:    Option<PathBuf>,
-    pub cache_key_path:       PathBuf,
-    pub update_url:           String,
-    pub update_channel:       ChannelIdent,
-    pub gossip_listen:        GossipListenAddr,
-    pub ctl_listen:           ListenCtlAddr,
-    pub http_listen:          HttpListenAddr,
-    pub http_disable:         bool,
-    pub gossip_peers:         Vec<SocketAddr>,
-    pub gossip_permanent:     bool,
-    pub ring_key:             Option<SymKey>,
-    pub organization:         Option<String>,
-    pub watch_peer_file:      Option<String>,
-    pub tls_config:           Option<TLSConfig>,
-    pub feature_flags:        FeatureFlag,
-    pub event_stream_config:  Option<EventStreamConfig>,
+    pub auto_update: bool,
+    pub custom_state_path: Option<PathBuf>,
+    pub cache_key_path: PathBuf,
+    pub update_url: String,
+    pub update_channel: ChannelIdent,
+    /// How often the Supervisor checks for updates to itself. If this field is `None`, the period
+    /// is taken from the environment or its default.
+    pub update_period: Option<Duration>,
+    pub gossip_listen: GossipListenAddr,
+    pub ctl_listen: ListenCtlAddr,
+    pub http_listen: HttpListenAddr,
+    pub http_disable: bool,
+    pub gossip_peers: Vec<SocketAddr>,
+    pub gossip_permanent: bool,
+    pub ring_key: Option<SymKey>,
+    pub organization: Option<String>,
+    pub watch_peer_file: Option<String>,
+    /// A name whose DNS SRV records give peers, which are combined with those of
+    /// `watch_peer_file`.
+    pub peer_dns: Option<String>,
+    pub tls_config: Option<TLSConfig>,
+    /// The TLS configuration of the Control Gateway. If this field is `None`, the Control
+    /// Gateway accepts plaintext connections.
+    pub ctl_tls_config: Option<TLSConfig>,
+    pub feature_flags: FeatureFlag,
+    pub event_stream_config: Option<EventStreamConfig>,
     /// If this field is `Some`, keep the indicated number of latest packages and uninstall all
     /// others during service start. If this field is `None`, automatic package cleanup is
     /// disabled.
     pub keep_latest_packages: Option<usize>,
+    /// The health check interval for services loaded without their own interval. If this field is
+    /// `None`, such services use the default interval.
+    pub default_health_check_interval: Option<HealthCheckInterval>,
 }
 
 #[derive(Clone, Debug)]
@@ -537,6 +552,7 @@ pub struct Manager {
     launcher:            LauncherCli,
     service_updater:     Arc<Mutex<ServiceUpdater>>,
     peer_watcher:        Option<PeerWatcher>,
+    peer_dns:            Option<PeerDns>,
     spec_watcher:        SpecWatcher,
     // This Arc<RwLock<>> business is a potentially temporary
     // change. Right now, in order to asynchronously shut down
@@ -632,7 +648,10 @@ impl Manager {
         let cfg_static = cfg.clone();
         let self_updater = if cfg.auto_update {
             if THIS_SUPERVISOR_IDENT.fully_qualified() {
-                Some(SelfUpdater::new(&*THIS_SUPERVISOR_IDENT, cfg.update_url, cfg.update_channel))
+                Some(SelfUpdater::new(&*THIS_SUPERVISOR_IDENT,
+                                      cfg.update_url,
+                                      cfg.update_channel,
+                                      cfg.update_period))
             } else {
                 warn!("Supervisor version not fully qualified, unable to start self-updater");
                 None
@@ -671,6 +690,12 @@ impl Manager {
             None
         };
 
+        let peer_dns = if let Some(name) = cfg.peer_dns {
+            Some(PeerDns::run(name)?)
+        } else {
+            None
+        };
+
         let spec_dir = SpecDir::new(&fs_cfg.specs_path)?;
         spec_dir.migrate_specs();
 
@@ -698,6 +723,7 @@ impl Manager {
                      butterfly: server,
                      launcher,
                      peer_watcher,
+                     peer_dns,
                      spec_watcher,
                      user_config_watcher: UserConfigWatcher::new(),
                      spec_dir,
@@ -938,8 +964,15 @@ impl Manager {
         let http_listen_addr = self.sys.http_listen();
         let ctl_listen_addr = self.sys.ctl_listen();
         let ctl_secret_key = ctl_gateway::readgen_secret_key(&self.fs_cfg.sup_root)?;
+        let ctl_tls_server_config = match &self.state.cfg.ctl_tls_config {
+            Some(c) => Some(Arc::new(tls_config(c)?)),
+            None => None,
+        };
         outputln!("Starting ctl-gateway on {}", &ctl_listen_addr);
-        tokio::spawn(ctl_gateway::server::run(ctl_listen_addr, ctl_secret_key, mgr_sender));
+        tokio::spawn(ctl_gateway::server::run(ctl_listen_addr,
+                                              ctl_secret_key,
+                                              ctl_tls_server_config,
+                                              mgr_sender));
         debug!("ctl-gateway started");
 
         if self.http_disable {
@@ -1125,7 +1158,7 @@ impl Manager {
                 self.maybe_spawn_service_futures_rsw_mlw_gsw_rhw_msw().await;
             }
 
-            self.update_peers_from_watch_file_mlr_imlw()?;
+            self.update_peers_mlr_imlw()?;
             self.update_running_services_from_user_config_watcher_msw();
 
             // Restart all services that need it
@@ -1720,20 +1753,26 @@ impl Manager {
     /// # Locking (see locking.md)
     /// * `MemberList::entries` (read)
     /// * `MemberList::initial_members` (write)
-    fn update_peers_from_watch_file_mlr_imlw(&mut self) -> Result<()> {
+    fn update_peers_mlr_imlw(&mut self) -> Result<()> {
         if !self.butterfly.need_peer_seeding_mlr() {
             return Ok(());
         }
-        match self.peer_watcher {
-            None => Ok(()),
-            Some(ref watcher) => {
-                if watcher.has_fs_events() {
-                    let members = watcher.get_members()?;
-                    self.butterfly.member_list.set_initial_members_imlw(members);
-                }
-                Ok(())
-            }
+        let watch_file_changed = self.peer_watcher
+                                     .as_ref()
+                                     .map_or(false, PeerWatcher::has_fs_events);
+        let dns_changed = self.peer_dns.as_ref().map_or(false, PeerDns::has_events);
+        if !watch_file_changed && !dns_changed {
+            return Ok(());
         }
+        let mut members = match self.peer_watcher {
+            Some(ref watcher) => watcher.get_members()?,
+            None => Vec::new(),
+        };
+        if let Some(ref peer_dns) = self.peer_dns {
+            members.extend(peer_dns.get_members());
+        }
+        self.butterfly.member_list.set_initial_members_imlw(members);
+        Ok(())
     }
 
     /// # Locking (see locking.md)
@@ -1969,24 +2008,28 @@ mod test {
     // code, so only implement it under test configuration.
     impl Default for ManagerConfig {
         fn default() -> Self {
-            ManagerConfig { auto_update:          false,
-                            custom_state_path:    None,
-                            cache_key_path:       cache_key_path(Some(&*FS_ROOT_PATH)),
-                            update_url:           "".to_string(),
-                            update_channel:       ChannelIdent::default(),
-                            gossip_listen:        GossipListenAddr::default(),
-                            ctl_listen:           ListenCtlAddr::default(),
-                            http_listen:          HttpListenAddr::default(),
-                            http_disable:         false,
-                            gossip_peers:         vec![],
-                            gossip_permanent:     false,
-                            ring_key:             None,
-                            organization:         None,
-                            watch_peer_file:      None,
-                            tls_config:           None,
-                            feature_flags:        FeatureFlag::empty(),
-                            event_stream_config:  None,
-                            keep_latest_packages: None, }
+            ManagerConfig { auto_update: false,
+                            custom_state_path: None,
+                            cache_key_path: cache_key_path(Some(&*FS_ROOT_PATH)),
+                            update_url: "".to_string(),
+                            update_channel: ChannelIdent::default(),
+                            update_period: None,
+                            gossip_listen: GossipListenAddr::default(),
+                            ctl_listen: ListenCtlAddr::default(),
+                            http_listen: HttpListenAddr::default(),
+                            http_disable: false,
+                            gossip_peers: vec![],
+                            gossip_permanent: false,
+                            ring_key: None,
+                            organization: None,
+                            watch_peer_file: None,
+                            peer_dns: None,
+                            tls_config: None,
+                            ctl_tls_config: None,
+                            feature_flags: FeatureFlag::empty(),
+                            event_stream_config: None,
+                            keep_latest_packages: None,
+                            default_health_check_interval: None, }
         }
     }
 
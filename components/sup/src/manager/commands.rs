@@ -182,6 +182,7 @@ pub async fn service_load(mgr: &ManagerState,
                           -> NetResult<()> {
     let ident: PackageIdent = opts.ident.clone().ok_or_else(err_update_client)?.into();
     let source = InstallSource::Ident(ident.clone(), PackageTarget::active_target());
+    let has_health_check_interval = opts.health_check_interval.is_some();
     let spec = if let Some(spec) = mgr.cfg.spec_for_ident(source.as_ref()) {
         // We've seen this service before. Thus `load` acts as a way to edit spec files from the
         // command line. As a result, we check that you *really* meant to change an existing spec.
@@ -195,7 +196,13 @@ pub async fn service_load(mgr: &ManagerState,
         }
         spec.merge_svc_load(opts)?
     } else {
-        ServiceSpec::try_from(opts)?
+        let mut spec = ServiceSpec::try_from(opts)?;
+        if let Some(interval) = mgr.cfg.default_health_check_interval {
+            if !has_health_check_interval {
+                spec.health_check_interval = interval;
+            }
+        }
+        spec
     };
 
     let package = util::pkg::satisfy_or_install(req, &source, &spec.bldr_url, &spec.channel).await?;
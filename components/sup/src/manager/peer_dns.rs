@@ -0,0 +1,180 @@
+//! Discovers the peers of the Supervisor from the DNS SRV records of a name, which are resolved
+//! at startup and periodically thereafter.
+
+use crate::error::Result;
+use habitat_butterfly::member::Member;
+use habitat_common::{liveliness_checker,
+                     outputln};
+use parking_lot::Mutex;
+use std::{net::{SocketAddr,
+                ToSocketAddrs},
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 Arc},
+          thread::{self,
+                   Builder as ThreadBuilder},
+          time::Duration};
+use trust_dns_resolver::{proto::rr::rdata::SRV,
+                         Resolver};
+
+static LOGKEY: &str = "PD";
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+habitat_core::env_config_duration!(
+    /// Represents how far apart the SRV records of the peer name are resolved, in seconds.
+    PeerDnsInterval,
+    HAB_PEER_DNS_INTERVAL_SECS => from_secs,
+    DEFAULT_INTERVAL);
+
+/// A record of an answer to an SRV query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SrvAnswer {
+    pub priority: u16,
+    pub weight:   u16,
+    pub port:     u16,
+    pub target:   String,
+}
+
+impl From<&SRV> for SrvAnswer {
+    fn from(srv: &SRV) -> Self {
+        SrvAnswer { priority: srv.priority(),
+                    weight:   srv.weight(),
+                    port:     srv.port(),
+                    target:   srv.target().to_utf8(), }
+    }
+}
+
+/// Returns the socket addresses of the peers given by the answers to an SRV query, most
+/// preferred first: by ascending priority, then by descending weight. Targets which cannot be
+/// resolved are skipped, as is the `.` target, which signals that there are no peers.
+pub fn peer_addrs(answers: &[SrvAnswer]) -> Vec<SocketAddr> {
+    let mut answers = answers.to_vec();
+    answers.sort_by(|a, b| a.priority.cmp(&b.priority).then(b.weight.cmp(&a.weight)));
+    let mut addrs: Vec<SocketAddr> = Vec::new();
+    for answer in answers {
+        let target = answer.target.trim_end_matches('.');
+        if target.is_empty() {
+            continue;
+        }
+        match (target, answer.port).to_socket_addrs() {
+            Ok(resolved) => {
+                for addr in resolved {
+                    if !addrs.contains(&addr) {
+                        addrs.push(addr);
+                    }
+                }
+            }
+            Err(e) => outputln!("Failed to resolve peer {}:{}, {}", target, answer.port, e),
+        }
+    }
+    addrs
+}
+
+pub struct PeerDns {
+    peers:       Arc<Mutex<Vec<SocketAddr>>>,
+    have_events: Arc<AtomicBool>,
+}
+
+impl PeerDns {
+    /// Starts resolving the SRV records of the given name, as often as `PeerDnsInterval`
+    /// dictates. A failed resolution keeps the peers of the last successful one.
+    pub fn run<S>(name: S) -> Result<Self>
+        where S: Into<String>
+    {
+        let name = name.into();
+        let peers = Arc::new(Mutex::new(Vec::new()));
+        let have_events = Arc::new(AtomicBool::new(false));
+        let peers_for_thread = Arc::clone(&peers);
+        let have_events_for_thread = Arc::clone(&have_events);
+        let interval: Duration = PeerDnsInterval::configured_value().into();
+
+        ThreadBuilder::new().name(format!("peer-dns-[{}]", name))
+                            .spawn(move || {
+                                let mut resolver = None;
+                                loop {
+                                    liveliness_checker::mark_thread_alive().and_divergent();
+                                    if let Some(addrs) = Self::resolve(&name, &mut resolver) {
+                                        *peers_for_thread.lock() = addrs;
+                                        have_events_for_thread.store(true, Ordering::Relaxed);
+                                    }
+                                    thread::sleep(interval);
+                                }
+                            })?;
+        Ok(PeerDns { peers, have_events })
+    }
+
+    /// Resolves the SRV records of a name into peer addresses, creating the resolver from the
+    /// system configuration if it does not exist yet. Returns `None` if the lookup fails.
+    fn resolve(name: &str, resolver: &mut Option<Resolver>) -> Option<Vec<SocketAddr>> {
+        if resolver.is_none() {
+            match Resolver::from_system_conf() {
+                Ok(r) => *resolver = Some(r),
+                Err(e) => {
+                    outputln!("PeerDns({}) could not create a resolver, will try again ({})",
+                              name,
+                              e);
+                    return None;
+                }
+            }
+        }
+        let lookup = match resolver.as_ref()?.lookup_srv(name) {
+            Ok(lookup) => lookup,
+            Err(e) => {
+                outputln!("PeerDns({}) failed to resolve SRV records, will try again ({})",
+                          name,
+                          e);
+                return None;
+            }
+        };
+        let answers: Vec<SrvAnswer> = lookup.iter().map(SrvAnswer::from).collect();
+        Some(peer_addrs(&answers))
+    }
+
+    pub fn has_events(&self) -> bool { self.have_events.load(Ordering::Relaxed) }
+
+    /// Returns the peers of the last successful resolution as members.
+    pub fn get_members(&self) -> Vec<Member> {
+        let members = self.peers
+                          .lock()
+                          .iter()
+                          .map(|addr| {
+                              let mut member = Member::default();
+                              member.address = format!("{}", addr.ip());
+                              member.swim_port = addr.port();
+                              member.gossip_port = addr.port();
+                              member
+                          })
+                          .collect();
+        self.have_events.store(false, Ordering::Relaxed);
+        members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(priority: u16, weight: u16, port: u16, target: &str) -> SrvAnswer {
+        SrvAnswer { priority,
+                    weight,
+                    port,
+                    target: target.to_string() }
+    }
+
+    #[test]
+    fn srv_answers_are_ordered_by_priority_then_weight() {
+        let answers = vec![answer(20, 0, 9638, "10.0.0.4."),
+                           answer(10, 5, 9638, "10.0.0.2."),
+                           answer(10, 50, 9000, "10.0.0.1."),
+                           answer(10, 5, 9638, "10.0.0.2"),
+                           answer(10, 5, 9638, "."),
+                           answer(15, 5, 9638, "::1.")];
+        let expected: Vec<SocketAddr> = vec!["10.0.0.1:9000".parse().unwrap(),
+                                             "10.0.0.2:9638".parse().unwrap(),
+                                             "[::1]:9638".parse().unwrap(),
+                                             "10.0.0.4:9638".parse().unwrap()];
+
+        assert_eq!(expected, peer_addrs(&answers));
+        assert!(peer_addrs(&[]).is_empty());
+    }
+}
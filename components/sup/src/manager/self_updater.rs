@@ -28,39 +28,52 @@ pub struct SelfUpdater {
     current:        PackageIdent,
     update_url:     String,
     update_channel: ChannelIdent,
+    period:         Duration,
 }
 
 // TODO (CM): Want to use the Periodic trait here, but can't due to
 // how things are currently structured (The service updater had a worker)
 
 impl SelfUpdater {
-    pub fn new(current: &PackageIdent, update_url: String, update_channel: ChannelIdent) -> Self {
-        let rx = Self::init(current.clone(), update_url.clone(), update_channel.clone());
+    /// Create a new `SelfUpdater`. If no `period` is given, checks for updates are made as often
+    /// as `SelfUpdatePeriod` dictates.
+    pub fn new(current: &PackageIdent,
+               update_url: String,
+               update_channel: ChannelIdent,
+               period: Option<Duration>)
+               -> Self {
+        let period = period.unwrap_or_else(|| SelfUpdatePeriod::configured_value().into());
+        let rx = Self::init(current.clone(),
+                            update_url.clone(),
+                            update_channel.clone(),
+                            period);
         SelfUpdater { rx,
                       current: current.clone(),
                       update_url,
-                      update_channel }
+                      update_channel,
+                      period }
     }
 
     /// Spawn a new Supervisor updater thread.
     fn init(current: PackageIdent,
             update_url: String,
-            update_channel: ChannelIdent)
+            update_channel: ChannelIdent,
+            period: Duration)
             -> Receiver<PackageInstall> {
         let (tx, rx) = oneshot::channel();
-        tokio::spawn(Self::run(tx, current, update_url, update_channel));
+        tokio::spawn(Self::run(tx, current, update_url, update_channel, period));
         rx
     }
 
     async fn run(tx: Sender<PackageInstall>,
                  current: PackageIdent,
                  update_url: String,
-                 update_channel: ChannelIdent) {
+                 update_channel: ChannelIdent,
+                 delay: Duration) {
         debug!("Self updater current package, {}", current);
         // SUP_PKG_IDENT will always parse as a valid PackageIdent,
         // and thus a valid InstallSource
         let install_source: InstallSource = SUP_PKG_IDENT.parse().unwrap();
-        let delay = SelfUpdatePeriod::configured_value().into();
         loop {
             match util::pkg::install_no_ui(&update_url, &install_source, &update_channel).await {
                 Ok(package) => {
@@ -89,7 +102,8 @@ impl SelfUpdater {
                 debug!("Self updater has died, restarting...");
                 self.rx = Self::init(self.current.clone(),
                                      self.update_url.clone(),
-                                     self.update_channel.clone());
+                                     self.update_channel.clone(),
+                                     self.period);
                 None
             }
         }